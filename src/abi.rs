@@ -0,0 +1,117 @@
+use ethers::{
+    abi::{Abi, Param},
+    types::{Bytes, H256},
+    utils::keccak256,
+};
+use serde::{Deserialize, Serialize};
+
+/// An overview of the functions, events, and custom errors in an ABI, with their signatures and
+/// selectors, so clients don't need to parse the full ABI to list them.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AbiSummary {
+    /// The functions in the ABI, sorted by signature.
+    pub functions: Vec<SelectorSignature>,
+    /// The events in the ABI, sorted by signature.
+    pub events: Vec<EventSignature>,
+    /// The custom errors in the ABI, sorted by signature.
+    pub errors: Vec<SelectorSignature>,
+}
+
+/// The signature of a function or custom error and its 4 byte selector.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SelectorSignature {
+    /// The signature, e.g. `transfer(address,uint256)`.
+    pub signature: String,
+    /// The first 4 bytes of the keccak256 hash of the signature.
+    pub selector: Bytes,
+}
+
+/// The signature of an event and its topic hash.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventSignature {
+    /// The signature, e.g. `Transfer(address,address,uint256)`.
+    pub signature: String,
+    /// The keccak256 hash of the signature, which is the first topic of the event's logs. This is
+    /// `None` for anonymous events, whose logs don't include it.
+    pub topic: Option<H256>,
+}
+
+/// Returns the signatures and selectors of every function, event, and custom error in the ABI.
+pub fn summarize_abi(abi: &Abi) -> AbiSummary {
+    let mut functions: Vec<SelectorSignature> = abi
+        .functions()
+        .map(|function| selector_signature(&function.name, &function.inputs))
+        .collect();
+    let mut events: Vec<EventSignature> = abi
+        .events()
+        .map(|event| {
+            let types = event.inputs.iter().map(|input| input.kind.to_string());
+            let signature = format!("{}({})", event.name, types.collect::<Vec<_>>().join(","));
+            let topic = (!event.anonymous).then(|| H256::from(keccak256(&signature)));
+            EventSignature { signature, topic }
+        })
+        .collect();
+    let mut errors: Vec<SelectorSignature> =
+        abi.errors().map(|error| selector_signature(&error.name, &error.inputs)).collect();
+
+    functions.sort_by(|a, b| a.signature.cmp(&b.signature));
+    events.sort_by(|a, b| a.signature.cmp(&b.signature));
+    errors.sort_by(|a, b| a.signature.cmp(&b.signature));
+    AbiSummary { functions, events, errors }
+}
+
+/// Returns the signature and selector of a function or custom error with the given inputs.
+fn selector_signature(name: &str, inputs: &[Param]) -> SelectorSignature {
+    let types = inputs.iter().map(|input| input.kind.to_string()).collect::<Vec<_>>();
+    let signature = format!("{name}({})", types.join(","));
+    let selector = keccak256(&signature)[..4].to_vec().into();
+    SelectorSignature { signature, selector }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_summarize_abi() -> Result<(), Box<dyn std::error::Error>> {
+        let abi: Abi = serde_json::from_str(
+            r#"[
+                {"type":"function","name":"transfer","stateMutability":"nonpayable","inputs":[{"name":"to","type":"address"},{"name":"amount","type":"uint256"}],"outputs":[{"name":"","type":"bool"}]},
+                {"type":"function","name":"balanceOf","stateMutability":"view","inputs":[{"name":"owner","type":"address"}],"outputs":[{"name":"","type":"uint256"}]},
+                {"type":"event","name":"Transfer","anonymous":false,"inputs":[{"name":"from","type":"address","indexed":true},{"name":"to","type":"address","indexed":true},{"name":"amount","type":"uint256","indexed":false}]},
+                {"type":"event","name":"Log","anonymous":true,"inputs":[]},
+                {"type":"error","name":"InsufficientBalance","inputs":[{"name":"needed","type":"uint256"},{"name":"limits","type":"tuple","components":[{"name":"a","type":"uint8"},{"name":"b","type":"bytes32[]"}]}]}
+            ]"#,
+        )?;
+        let summary = summarize_abi(&abi);
+
+        let functions: Vec<_> = summary
+            .functions
+            .iter()
+            .map(|function| (function.signature.as_str(), function.selector.to_string()))
+            .collect();
+        assert_eq!(
+            functions,
+            vec![
+                ("balanceOf(address)", "0x70a08231".into()),
+                ("transfer(address,uint256)", "0xa9059cbb".into())
+            ]
+        );
+
+        assert_eq!(summary.events[0].signature, "Log()");
+        assert_eq!(summary.events[0].topic, None);
+        assert_eq!(summary.events[1].signature, "Transfer(address,address,uint256)");
+        assert_eq!(
+            summary.events[1].topic,
+            Some(H256::from_str(
+                "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"
+            )?)
+        );
+
+        assert_eq!(summary.errors.len(), 1);
+        assert_eq!(summary.errors[0].signature, "InsufficientBalance(uint256,(uint8,bytes32[]))");
+        assert_eq!(summary.errors[0].selector.len(), 4);
+        Ok(())
+    }
+}
@@ -0,0 +1,170 @@
+use crate::frameworks::framework::BuildVariant;
+use ethers::utils::hex;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+/// The file in a cache entry containing the version of the toolchain that built it.
+const TOOLCHAIN_FILE: &str = "toolchain";
+
+/// Identifies the output of a build. Compilation is deterministic, so builds with the same key
+/// produce the same artifacts regardless of which contract is being verified.
+#[derive(Debug, Clone, Serialize)]
+pub struct ArtifactCacheKey {
+    /// The URL of the repository.
+    pub repo_url: String,
+    /// The full hash of the commit that was built. This is resolved from the checked out
+    /// repository rather than taken from the request, so a branch name never restores stale
+    /// output.
+    pub commit: String,
+    /// The configuration the project was built with.
+    pub variant: BuildVariant,
+    /// The remappings provided with the request, which override the project's remappings.
+    pub remappings: Vec<String>,
+}
+
+/// Caches build output on the filesystem, so verifying another contract from a codebase that was
+/// already built skips compilation. Each entry holds the output directory of one build, which
+/// includes that build's build info, along with the version of the toolchain that built it. Entries
+/// built by a different toolchain version are stale, since a different compiler may produce
+/// different artifacts.
+#[derive(Debug, Clone)]
+pub struct ArtifactCache {
+    /// The directory cache entries are stored in.
+    dir: PathBuf,
+    /// The version of the toolchain that builds are run with, as output by `forge --version`.
+    toolchain: String,
+}
+
+impl ArtifactCache {
+    /// Create a new `ArtifactCache` in the given directory for builds run with `toolchain`,
+    /// creating the directory if needed.
+    pub fn new(
+        dir: impl Into<PathBuf>,
+        toolchain: impl Into<String>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir, toolchain: toolchain.into() })
+    }
+
+    /// Copies the cached output of the build identified by `key` into `out_dir`, returning false
+    /// if there is no entry for it. An entry built by a different toolchain version is removed and
+    /// treated as missing.
+    pub fn restore(&self, key: &ArtifactCacheKey, out_dir: &Path) -> Result<bool, Box<dyn Error>> {
+        let entry = self.entry_dir(key)?;
+        match fs::read_to_string(entry.join(TOOLCHAIN_FILE)) {
+            Ok(toolchain) if toolchain == self.toolchain => {}
+            Ok(_) => {
+                fs::remove_dir_all(&entry)?;
+                return Ok(false)
+            }
+            Err(_) => return Ok(false),
+        }
+        copy_dir(&entry.join("out"), out_dir)?;
+        Ok(true)
+    }
+
+    /// Saves the output of the build identified by `key`, replacing any existing entry. Only the
+    /// build's own output directory is saved, so the entry never includes build info from other
+    /// builds of the project. The entry is written to a temporary directory that is renamed into
+    /// place once complete, so a partial entry is never restored.
+    pub fn save(&self, key: &ArtifactCacheKey, out_dir: &Path) -> Result<(), Box<dyn Error>> {
+        let staging = tempfile::TempDir::new_in(&self.dir)?;
+        copy_dir(out_dir, &staging.path().join("out"))?;
+        fs::write(staging.path().join(TOOLCHAIN_FILE), &self.toolchain)?;
+
+        let entry = self.entry_dir(key)?;
+        if entry.exists() {
+            fs::remove_dir_all(&entry)?;
+        }
+        fs::rename(staging.into_path(), &entry)?;
+        Ok(())
+    }
+
+    /// Returns the directory of the entry for `key`, named by the hash of the key.
+    fn entry_dir(&self, key: &ArtifactCacheKey) -> Result<PathBuf, Box<dyn Error>> {
+        let hash = Sha256::digest(serde_json::to_vec(key)?);
+        Ok(self.dir.join(hex::encode(hash)))
+    }
+}
+
+/// Recursively copies the contents of `from` into `to`, creating `to` if needed.
+fn copy_dir(from: &Path, to: &Path) -> Result<(), Box<dyn Error>> {
+    for entry in WalkDir::new(from) {
+        let entry = entry?;
+        let target = to.join(entry.path().strip_prefix(from)?);
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_restore_saved_build() -> Result<(), Box<dyn Error>> {
+        let cache_dir = TempDir::new()?;
+        let project = TempDir::new()?;
+        let out_dir = project.path().join("cove_out").join("default");
+        fs::create_dir_all(out_dir.join("Counter.sol"))?;
+        fs::write(out_dir.join("Counter.sol").join("Counter.json"), "{}")?;
+        fs::create_dir_all(out_dir.join("build-info"))?;
+        fs::write(out_dir.join("build-info").join("abc.json"), "{}")?;
+
+        // Build info written by other builds of the project isn't part of this build's entry.
+        let other_out_dir = project.path().join("cove_out").join("default-optimizer-true");
+        fs::create_dir_all(other_out_dir.join("build-info"))?;
+        fs::write(other_out_dir.join("build-info").join("def.json"), "{}")?;
+
+        let key = ArtifactCacheKey {
+            repo_url: "https://github.com/ScopeLift/cove-test-repo".to_string(),
+            commit: "188587df6652a0a3ad3ecd6fb7f1e5d5c1ec4bb2".to_string(),
+            variant: BuildVariant { profile: "default".to_string(), optimizer_override: None },
+            remappings: Vec::new(),
+        };
+        let cache = ArtifactCache::new(cache_dir.path(), "forge 0.2.0 (a1b2c3d)")?;
+        let restore_dir = TempDir::new()?;
+        let restored_out = restore_dir.path().join("out");
+        assert!(!cache.restore(&key, &restored_out)?);
+
+        // A saved build is restored into the given directory along with its build info.
+        cache.save(&key, &out_dir)?;
+        assert!(cache.restore(&key, &restored_out)?);
+        assert_eq!(
+            fs::read_to_string(restored_out.join("Counter.sol").join("Counter.json"))?,
+            "{}"
+        );
+        let build_infos = fs::read_dir(restored_out.join("build-info"))?
+            .map(|entry| Ok(entry?.file_name()))
+            .collect::<Result<Vec<_>, std::io::Error>>()?;
+        assert_eq!(build_infos, vec!["abc.json"]);
+
+        // Other build configurations aren't restored from it.
+        let other_key = ArtifactCacheKey {
+            variant: BuildVariant {
+                profile: "default".to_string(),
+                optimizer_override: Some(true),
+            },
+            ..key.clone()
+        };
+        assert!(!cache.restore(&other_key, &restored_out)?);
+
+        // A toolchain upgrade invalidates the entry.
+        let upgraded = ArtifactCache::new(cache_dir.path(), "forge 0.2.1 (d4e5f6a)")?;
+        assert!(!upgraded.restore(&key, &restored_out)?);
+        assert!(!cache.restore(&key, &restored_out)?);
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+use crate::startup::AppState;
+use axum::{
+    body::Body,
+    extract::State,
+    http::{header, Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use ethers::utils::hex;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::sync::Arc;
+
+/// The scheme of the `Authorization` header for signed requests.
+const HMAC_SCHEME: &str = "HMAC-SHA256";
+
+/// The maximum size of a request body that is buffered to check its signature, matching axum's
+/// default JSON body limit.
+const MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+/// Middleware that requires requests to be signed with the HMAC secret from the application
+/// settings. Clients sign the raw request body with HMAC-SHA256 and send the hex-encoded signature
+/// as `Authorization: HMAC-SHA256 <signature>`, and requests with a missing or invalid signature
+/// are rejected with `401 Unauthorized`. `GET` requests have no body, so clients sign the request
+/// path instead, e.g. `/verify/status/{job_id}`, which means a signature only grants access to the
+/// resource it was made for. If no secret is configured, all requests are allowed.
+pub async fn require_hmac_signature(
+    State(state): State<Arc<AppState>>,
+    request: Request<Body>,
+    next: Next<Body>,
+) -> Response {
+    let Some(secret) = &state.settings.application.hmac_secret else {
+        return next.run(request).await
+    };
+
+    let signature = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix(HMAC_SCHEME))
+        .and_then(|signature| hex::decode(signature.trim()).ok());
+    let Some(signature) = signature else {
+        let msg = format!("Missing or malformed `Authorization: {HMAC_SCHEME} <signature>` header");
+        return (StatusCode::UNAUTHORIZED, msg).into_response()
+    };
+
+    // The body must be buffered to check the signature, then passed on to the handler.
+    let (parts, body) = request.into_parts();
+    let body = match hyper::body::to_bytes(http_body::Limited::new(body, MAX_BODY_BYTES)).await {
+        Ok(body) => body,
+        Err(_) => {
+            let msg = "Request body is too large".to_string();
+            return (StatusCode::PAYLOAD_TOO_LARGE, msg).into_response()
+        }
+    };
+    let signed = if parts.method == Method::GET { parts.uri.path().as_bytes() } else { &body };
+    if !is_valid_signature(secret.as_bytes(), signed, &signature) {
+        return (StatusCode::UNAUTHORIZED, "Invalid HMAC signature".to_string()).into_response()
+    }
+    next.run(Request::from_parts(parts, Body::from(body))).await
+}
+
+/// Returns the hex-encoded HMAC-SHA256 signature of `body` using `secret`, as clients send it in
+/// the `Authorization` header.
+pub fn sign(secret: &[u8], body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Returns whether `signature` is the HMAC-SHA256 signature of `body` using `secret`. The
+/// comparison is constant-time so the signature can't be guessed byte by byte.
+pub fn is_valid_signature(secret: &[u8], body: &[u8], signature: &[u8]) -> bool {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    mac.verify_slice(signature).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_and_verify() {
+        let body = br#"{"repoUrl":"https://github.com/ScopeLift/cove-test-repo"}"#;
+        let signature = hex::decode(sign(b"secret", body)).unwrap();
+        assert!(is_valid_signature(b"secret", body, &signature));
+        assert!(!is_valid_signature(b"other secret", body, &signature));
+        assert!(!is_valid_signature(b"secret", b"{}", &signature));
+        assert!(!is_valid_signature(b"secret", body, &signature[1..]));
+
+        // Known answer from RFC 4231, test case 2.
+        assert_eq!(
+            sign(b"Jefe", b"what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+}
@@ -1,7 +1,12 @@
-use ethers::types::Bytes;
-use ethers_solc::artifacts::Offsets;
+use ciborium::value::Value;
+use ethers::{
+    abi::{Abi, Token},
+    types::{Address, Bytes, H256, I256},
+    utils::to_checksum,
+};
+use ethers_solc::artifacts::{BytecodeHash, Offsets, SettingsMetadata};
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, error::Error};
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 
@@ -9,6 +14,10 @@ use std::collections::BTreeMap;
 pub enum MatchType {
     /// A full match means the bytecode and the metadata hash match.
     Full,
+    /// A full match of creation code where the on-chain code is the compiled code followed by
+    /// encoded constructor arguments. The bytecode and the metadata hash match, so this is as
+    /// strong as a `Full` match.
+    FullWithConstructorArgs,
     /// A partial match means the bytecode matches, but the metadata hash does not.
     Partial,
     /// No match means the bytecode does not match.
@@ -17,7 +26,7 @@ pub enum MatchType {
 }
 
 /// Contains info about the the bytecode's metadata hash.
-#[derive(Debug, Default, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct MetadataInfo {
     /// The metadata hash if present.
     pub hash: Option<Bytes>,
@@ -27,6 +36,238 @@ pub struct MetadataInfo {
     pub end_index: Option<usize>,
 }
 
+impl MetadataInfo {
+    /// Returns the solc version embedded in the metadata hash, or `None` if there is no metadata
+    /// hash, it cannot be decoded, or the compiler version is unknown.
+    pub fn solc_version(&self) -> Option<String> {
+        let hash = self.hash.as_ref()?;
+        decode_cbor_metadata(hash).ok()?.solc_version
+    }
+}
+
+/// The decoded contents of the CBOR-encoded metadata appended to bytecode by solc. See
+/// https://docs.soliditylang.org/en/latest/metadata.html#encoding-of-the-metadata-hash-in-the-bytecode
+/// for details. All fields are optional since the compiler settings determine which are present.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CborMetadata {
+    /// The IPFS CID of the metadata file, which can be used to fetch the metadata from IPFS.
+    pub ipfs: Option<String>,
+    /// The Swarm hash of the metadata file, used by solc 0.5.x and earlier.
+    pub bzzr0: Option<Bytes>,
+    /// The Swarm hash of the metadata file, used by solc 0.6.x and later.
+    pub bzzr1: Option<Bytes>,
+    /// Whether experimental compiler features were enabled.
+    pub experimental: Option<bool>,
+    /// The solc version, e.g. `0.8.19`. This is `None` if the version is absent or is the
+    /// `0.0.0` placeholder, which means the compiler is unknown.
+    pub solc_version: Option<String>,
+}
+
+/// Decodes the CBOR-encoded metadata hash, as returned in `MetadataInfo::hash`. The input is
+/// expected to end with the two byte length of the CBOR data.
+pub fn decode_cbor_metadata(metadata_hash: &Bytes) -> Result<CborMetadata, Box<dyn Error>> {
+    if metadata_hash.len() < 2 {
+        return Err("Metadata hash is too short".into())
+    }
+    let cbor = &metadata_hash[..metadata_hash.len() - 2];
+    let entries = match ciborium::de::from_reader(cbor)? {
+        Value::Map(entries) => entries,
+        _ => return Err("Metadata hash is not a CBOR map".into()),
+    };
+
+    let mut metadata = CborMetadata::default();
+    for (key, value) in entries {
+        let Value::Text(key) = key else { continue };
+        match (key.as_str(), value) {
+            ("ipfs", Value::Bytes(hash)) => metadata.ipfs = Some(crate::ipfs::multihash_cid(&hash)),
+            ("bzzr0", Value::Bytes(hash)) => metadata.bzzr0 = Some(hash.into()),
+            ("bzzr1", Value::Bytes(hash)) => metadata.bzzr1 = Some(hash.into()),
+            ("experimental", Value::Bool(experimental)) => {
+                metadata.experimental = Some(experimental)
+            }
+            // Release builds encode the version as three bytes, and prerelease builds encode the
+            // full version string. A `0.0.0` version means the compiler is unknown.
+            ("solc", Value::Bytes(version)) if version.len() == 3 && version != [0, 0, 0] => {
+                metadata.solc_version =
+                    Some(format!("{}.{}.{}", version[0], version[1], version[2]))
+            }
+            ("solc", Value::Text(version))
+                if !version.is_empty() && !version.starts_with("0.0.0") =>
+            {
+                metadata.solc_version = Some(version)
+            }
+            _ => {}
+        }
+    }
+    Ok(metadata)
+}
+
+/// The type of metadata hash appended to code, as set by the compiler's `bytecodeHash` setting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum MetadataHashType {
+    /// An IPFS hash of the metadata file.
+    Ipfs,
+    /// A Swarm hash of the metadata file, used by solc 0.5.x and earlier.
+    Bzzr0,
+    /// A Swarm hash of the metadata file, used by solc 0.6.x and later.
+    Bzzr1,
+    /// No metadata hash, either because there is no CBOR metadata or because it has no hash.
+    None,
+}
+
+impl std::fmt::Display for MetadataHashType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            MetadataHashType::Ipfs => "ipfs",
+            MetadataHashType::Bzzr0 => "bzzr0",
+            MetadataHashType::Bzzr1 => "bzzr1",
+            MetadataHashType::None => "none",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Returns the type of metadata hash appended to the code. Code without CBOR metadata, or whose
+/// metadata can't be decoded, is treated as having no metadata hash.
+pub fn metadata_hash_type(code: &Bytes) -> MetadataHashType {
+    let Some(metadata) =
+        parse_metadata(code).hash.and_then(|hash| decode_cbor_metadata(&hash).ok())
+    else {
+        return MetadataHashType::None
+    };
+    if metadata.ipfs.is_some() {
+        MetadataHashType::Ipfs
+    } else if metadata.bzzr1.is_some() {
+        MetadataHashType::Bzzr1
+    } else if metadata.bzzr0.is_some() {
+        MetadataHashType::Bzzr0
+    } else {
+        MetadataHashType::None
+    }
+}
+
+/// A constructor argument decoded from the arguments appended to creation code.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConstructorArg {
+    /// The name of the parameter in the constructor signature.
+    pub name: String,
+    /// The Solidity type of the parameter, e.g. `address` or `uint256[]`.
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// The decoded value. Addresses are EIP-55 checksummed, integers are decimal strings, and
+    /// bytes are 0x-prefixed hex strings.
+    pub value: serde_json::Value,
+}
+
+/// Decodes the ABI-encoded constructor arguments using the constructor inputs from the ABI.
+pub fn decode_constructor_args(
+    abi: &Abi,
+    encoded: &Bytes,
+) -> Result<Vec<ConstructorArg>, Box<dyn Error>> {
+    let inputs = match abi.constructor() {
+        Some(constructor) => &constructor.inputs,
+        None if encoded.is_empty() => return Ok(Vec::new()),
+        None => return Err("ABI has no constructor but constructor arguments were found".into()),
+    };
+    let types: Vec<_> = inputs.iter().map(|input| input.kind.clone()).collect();
+    let tokens = ethers::abi::decode(&types, encoded)?;
+
+    Ok(inputs
+        .iter()
+        .zip(tokens.iter())
+        .map(|(input, token)| ConstructorArg {
+            name: input.name.clone(),
+            kind: input.kind.to_string(),
+            value: token_to_json(token),
+        })
+        .collect())
+}
+
+/// Converts a decoded ABI token to a JSON value for the response.
+fn token_to_json(token: &Token) -> serde_json::Value {
+    match token {
+        Token::Address(address) => to_checksum(address, None).into(),
+        Token::FixedBytes(bytes) | Token::Bytes(bytes) => {
+            Bytes::from(bytes.clone()).to_string().into()
+        }
+        Token::Int(int) => I256::from_raw(*int).to_string().into(),
+        Token::Uint(uint) => uint.to_string().into(),
+        Token::Bool(value) => (*value).into(),
+        Token::String(value) => value.clone().into(),
+        Token::FixedArray(tokens) | Token::Array(tokens) | Token::Tuple(tokens) => {
+            tokens.iter().map(token_to_json).collect()
+        }
+    }
+}
+
+/// A standard proxy pattern detected in deployed code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyKind {
+    /// An EIP-1167 minimal proxy, which delegates every call to the given implementation.
+    MinimalProxy(Address),
+    /// A proxy that stores its implementation address in the EIP-1967 implementation slot.
+    Eip1967,
+    /// A proxy that stores the address of a beacon, which returns the implementation address, in
+    /// the EIP-1967 beacon slot.
+    Eip1967Beacon,
+    /// An EIP-1822 UUPS proxy, which stores its implementation address in the `PROXIABLE` slot,
+    /// i.e. `keccak256("PROXIABLE")`. Unlike other proxies, the upgrade logic lives in the
+    /// implementation rather than in the proxy.
+    Eip1822,
+}
+
+/// Returns the implementation address if the deployed code is an EIP-1167 minimal proxy, i.e.
+/// exactly the 45 byte template with the implementation address embedded in it.
+pub fn is_minimal_proxy(code: &Bytes) -> Option<Address> {
+    const MINIMAL_PROXY_PREFIX: [u8; 10] =
+        [0x36, 0x3d, 0x3d, 0x37, 0x3d, 0x3d, 0x3d, 0x36, 0x3d, 0x73];
+    const MINIMAL_PROXY_SUFFIX: [u8; 15] =
+        [0x5a, 0xf4, 0x3d, 0x82, 0x80, 0x3e, 0x90, 0x3d, 0x91, 0x60, 0x2b, 0x57, 0xfd, 0x5b, 0xf3];
+    if code.len() == MINIMAL_PROXY_PREFIX.len() + 20 + MINIMAL_PROXY_SUFFIX.len()
+        && code.starts_with(&MINIMAL_PROXY_PREFIX)
+        && code.ends_with(&MINIMAL_PROXY_SUFFIX)
+    {
+        let implementation = &code[MINIMAL_PROXY_PREFIX.len()..MINIMAL_PROXY_PREFIX.len() + 20];
+        return Some(Address::from_slice(implementation))
+    }
+    None
+}
+
+/// Returns the kind of proxy the deployed code is, or `None` if it's not a known proxy pattern.
+/// Minimal proxies are matched exactly. Other proxies are detected by the storage slot constant
+/// their code reads the implementation or beacon address from: the EIP-1967 implementation and
+/// beacon slots, or the EIP-1822 `PROXIABLE` slot of UUPS proxies.
+pub fn detect_proxy(code: &Bytes) -> Option<ProxyKind> {
+    if let Some(implementation) = is_minimal_proxy(code) {
+        return Some(ProxyKind::MinimalProxy(implementation))
+    }
+
+    // The EIP-1967 slots are `keccak256("eip1967.proxy.implementation") - 1` and
+    // `keccak256("eip1967.proxy.beacon") - 1`, and the EIP-1822 slot is `keccak256("PROXIABLE")`.
+    // They're constants, so they're hardcoded rather than computed.
+    let slots = [
+        ("360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc", ProxyKind::Eip1967),
+        (
+            "a3f0ad74e5423aebfd80d3ef4346578335a9a72aeaee59ff6cb3582b35133d50",
+            ProxyKind::Eip1967Beacon,
+        ),
+        ("c5f16f0fcc639fa48a6947836d9850f504798523bf8c9a3a87d5876cf622bcf7", ProxyKind::Eip1822),
+    ];
+    slots.into_iter().find_map(|(slot, kind)| {
+        let slot: H256 = slot.parse().expect("valid slot");
+        code.windows(32).any(|window| window == slot.as_bytes()).then_some(kind)
+    })
+}
+
+/// Returns false only if both metadata hashes contain a known solc version and the versions differ.
+/// An unknown compiler version is not treated as a mismatch.
+fn compiler_versions_compatible(found: &MetadataInfo, expected: &MetadataInfo) -> bool {
+    match (found.solc_version(), expected.solc_version()) {
+        (Some(found), Some(expected)) => found == expected,
+        _ => true,
+    }
+}
+
 /// Data about found creation bytecode, where "found" bytecode is bytecode from an artifact that was
 /// output when compiling the repo.
 #[derive(Debug, PartialEq, Eq)]
@@ -58,6 +299,11 @@ pub struct ExpectedCreationBytecode {
 /// bytecode. This contains data from the `immutableReferences` field of an artifact.
 pub type ImmutableReferences = BTreeMap<String, Vec<Offsets>>;
 
+/// Type alias for a mapping between source files, the libraries they define, and the offsets of
+/// those libraries' address placeholders within bytecode. This contains data from the
+/// `linkReferences` field of an artifact.
+pub type LinkReferences = BTreeMap<String, BTreeMap<String, Vec<Offsets>>>;
+
 /// Data about found deployed bytecode, where "found" bytecode is bytecode from an artifact that was
 /// output when compiling the repo.
 #[derive(Debug, PartialEq, Eq)]
@@ -70,6 +316,8 @@ pub struct FoundDeployedBytecode {
     pub metadata: MetadataInfo,
     /// Immutable references and their offsets within bytecode.
     pub immutable_references: ImmutableReferences,
+    /// Library address placeholders and their offsets within bytecode.
+    pub link_references: LinkReferences,
 }
 
 /// Data about expected deployed bytecode, where "expected" bytecode is the bytecode that exists
@@ -84,6 +332,207 @@ pub struct ExpectedDeployedBytecode {
     pub metadata: MetadataInfo,
     /// Immutable references and their offsets within bytecode.
     pub immutable_references: ImmutableReferences,
+    /// Library address placeholders and their offsets within bytecode.
+    pub link_references: LinkReferences,
+}
+
+/// Structures the creation code output by the compiler, using the compiler's metadata settings to
+/// determine whether the code ends with a metadata hash.
+pub fn structure_found_creation_code(
+    raw_code: Bytes,
+    settings: &SettingsMetadata,
+) -> FoundCreationBytecode {
+    let (leading_code, metadata) = split_found_code(&raw_code, settings);
+    FoundCreationBytecode { raw_code, leading_code, metadata }
+}
+
+/// Uses data from the found creation code to structure the expected creation code.
+pub fn structure_expected_creation_code(
+    found: &FoundCreationBytecode,
+    expected: &Bytes,
+) -> Result<ExpectedCreationBytecode, Box<dyn Error>> {
+    if expected.len() < found.leading_code.len() {
+        return Err("Expected creation bytecode is shorter than found creation bytecode.".into())
+    }
+
+    let (leading_code, metadata) = split_expected_code(&found.raw_code, &found.metadata, expected);
+
+    // The encoded constructor arguments are everything that's left.
+    let accumulated_len = leading_code.len() + metadata.hash.as_ref().map_or(0, |hash| hash.len());
+    let encoded_constructor_args: Option<Bytes> = if expected.len() > accumulated_len {
+        // The remaining bytes are the encoded constructor arguments.
+        Some(expected.split_at(accumulated_len).1.to_vec().into())
+    } else {
+        None
+    };
+
+    Ok(ExpectedCreationBytecode {
+        raw_code: expected.clone(),
+        leading_code,
+        metadata,
+        constructor_args: encoded_constructor_args,
+    })
+}
+
+/// Structures the deployed code output by the compiler, using the compiler's metadata settings to
+/// determine whether the code ends with a metadata hash.
+pub fn structure_found_deployed_code(
+    raw_code: Bytes,
+    immutable_references: ImmutableReferences,
+    link_references: LinkReferences,
+    settings: &SettingsMetadata,
+) -> FoundDeployedBytecode {
+    let (leading_code, metadata) = split_found_code(&raw_code, settings);
+    FoundDeployedBytecode {
+        raw_code,
+        leading_code,
+        metadata,
+        immutable_references,
+        link_references,
+    }
+}
+
+/// Parses hex encoded code that may contain unlinked library placeholders, e.g. `__$<hash>$__`,
+/// which aren't valid hex. Each placeholder given by `link_references` is replaced with zeroes,
+/// which mirrors how solc leaves zeroes as placeholders for immutables, so linked libraries can be
+/// skipped when comparing code.
+pub fn parse_unlinked_code(
+    object: &str,
+    link_references: &LinkReferences,
+) -> Result<Bytes, Box<dyn Error>> {
+    let mut code = object.strip_prefix("0x").unwrap_or(object).as_bytes().to_vec();
+    for offset in link_offsets(link_references) {
+        // Offsets are in bytes, and each byte is two hex characters.
+        let start = 2 * offset.start as usize;
+        let end = start + 2 * offset.length as usize;
+        let placeholder = code.get_mut(start..end).ok_or("Library placeholder is out of bounds")?;
+        placeholder.fill(b'0');
+    }
+    let code = String::from_utf8(code)?;
+    code.parse::<Bytes>().map_err(|_| "Bytecode contains unknown library placeholders".into())
+}
+
+/// Returns the offsets of every library placeholder in the link references.
+fn link_offsets(link_references: &LinkReferences) -> impl Iterator<Item = &Offsets> {
+    link_references.values().flat_map(|libraries| libraries.values()).flatten()
+}
+
+/// Rebuilds deployed code that was compiled with `bytecodeHash: none` as it would have been
+/// compiled with `bytecodeHash: ipfs`, so it can fully match a deployment whose metadata uses an
+/// IPFS hash. The setting only changes the metadata appended to the code, so the IPFS hash is
+/// recomputed from `raw_metadata`, the metadata JSON output by the compiler, with the setting
+/// changed. Returns `None` if the code wasn't compiled with `bytecodeHash: none` or the metadata
+/// hash can't be reproduced.
+pub fn reproduce_ipfs_metadata_hash(
+    raw_code: &Bytes,
+    settings: &SettingsMetadata,
+    raw_metadata: &str,
+) -> Option<Bytes> {
+    if settings.bytecode_hash != Some(BytecodeHash::None) {
+        return None
+    }
+    let mut metadata: serde_json::Value = serde_json::from_str(raw_metadata).ok()?;
+    *metadata.pointer_mut("/settings/metadata/bytecodeHash")? = "ipfs".into();
+
+    // Release builds encode the version as three bytes. Prerelease builds encode the full version
+    // string, which can't be recovered from the metadata.
+    let version = metadata.pointer("/compiler/version")?.as_str()?;
+    let version = version.split('+').next()?;
+    if version.contains('-') {
+        return None
+    }
+    let version =
+        version.split('.').map(|part| part.parse::<u8>().ok()).collect::<Option<Vec<_>>>()?;
+    if version.len() != 3 {
+        return None
+    }
+
+    // solc hashes compact JSON with sorted keys, which is how serde_json serializes maps.
+    let ipfs = crate::ipfs::metadata_multihash(serde_json::to_string(&metadata).ok()?.as_bytes())?;
+    let cbor = Value::Map(vec![
+        (Value::Text("ipfs".to_string()), Value::Bytes(ipfs)),
+        (Value::Text("solc".to_string()), Value::Bytes(version)),
+    ]);
+    let mut metadata_hash = Vec::new();
+    ciborium::ser::into_writer(&cbor, &mut metadata_hash).ok()?;
+    metadata_hash.extend_from_slice(&(metadata_hash.len() as u16).to_be_bytes());
+
+    // Replace the metadata appended with `appendCBOR`, if any, with the IPFS metadata hash.
+    let (leading_code, _) = split_found_code(raw_code, settings);
+    Some([leading_code.as_ref(), &metadata_hash].concat().into())
+}
+
+/// Uses data from the found deployed code to structure the expected deployed code.
+pub fn structure_expected_deployed_code(
+    found: &FoundDeployedBytecode,
+    expected: &Bytes,
+) -> Result<ExpectedDeployedBytecode, Box<dyn Error>> {
+    if expected.len() < found.leading_code.len() {
+        return Err("Expected deployed bytecode is shorter than found deployed bytecode.".into())
+    }
+
+    let (leading_code, metadata) = split_expected_code(&found.raw_code, &found.metadata, expected);
+    Ok(ExpectedDeployedBytecode {
+        raw_code: expected.clone(),
+        leading_code,
+        metadata,
+        immutable_references: found.immutable_references.clone(),
+        link_references: found.link_references.clone(),
+    })
+}
+
+/// Splits found code into the leading code and the metadata hash, if the metadata settings
+/// indicate that a metadata hash is present.
+fn split_found_code(raw_code: &Bytes, settings: &SettingsMetadata) -> (Bytes, MetadataInfo) {
+    let bytecode_hash = settings.bytecode_hash.unwrap_or(BytecodeHash::None);
+    let append_cbor = settings.cbor_metadata.unwrap_or(false);
+
+    if bytecode_hash == BytecodeHash::None && !append_cbor {
+        // If `bytecodeHash = none` AND `appendCBOR = false`, there is no metadata, so everything
+        // we have is the leading code.
+        (raw_code.clone(), MetadataInfo::default())
+    } else {
+        // If bytecodeHash != none OR appendCBOR = true, some metadata hash is present, so we slice
+        // the bytes based on metadata length to get the leading code and metadata.
+        let metadata = parse_metadata(raw_code);
+        let (leading_code, _) = raw_code.split_at(metadata.start_index.unwrap_or(raw_code.len()));
+        (leading_code.to_vec().into(), metadata)
+    }
+}
+
+/// Splits expected code into the leading code and the metadata hash, using the location of the
+/// metadata hash in the found code.
+fn split_expected_code(
+    found_code: &Bytes,
+    found_metadata: &MetadataInfo,
+    expected: &Bytes,
+) -> (Bytes, MetadataInfo) {
+    // Leading code is everything up until the found's metadata hash start index.
+    let leading_code: Bytes =
+        expected.split_at(found_metadata.start_index.unwrap_or(found_code.len())).0.to_vec().into();
+
+    // Metadata hash is given by the found's metadata hash start and end indices, if they are
+    // present, otherwise it's None.
+    let metadata_hash: Option<Bytes> = if let (Some(start_index), Some(end_index)) =
+        (found_metadata.start_index, found_metadata.end_index)
+    {
+        // There may be cases where the found bytecode has a full metadata hash, but the expected
+        // bytecode only has CBOR or none. In those cases the `end_index` will be longer than the
+        // length of the expected bytecode, and slicing would panic since `end_index` would be out
+        // of bounds. To avoid this while still enabling verification for different metadata
+        // hashes, the end index is `min(end_index, expected.len())`.
+        let adjusted_end_index = std::cmp::min(end_index, expected.len());
+        Some(expected[start_index..adjusted_end_index].to_vec().into())
+    } else {
+        None
+    };
+
+    let metadata = MetadataInfo {
+        hash: metadata_hash,
+        start_index: found_metadata.start_index,
+        end_index: found_metadata.end_index,
+    };
+    (leading_code, metadata)
 }
 
 /// Checks for equality between found and expected creation bytecode and returns the type of match.
@@ -104,23 +553,69 @@ pub fn creation_code_equality_check(
     if found.raw_code == expected.raw_code {
         return MatchType::Full
     }
-    if found.leading_code == expected.leading_code {
+    // If the found code is a prefix of the expected code, the leading code and metadata hash both
+    // match and the remaining bytes are the constructor arguments.
+    if expected.raw_code.starts_with(&found.raw_code) {
+        return MatchType::FullWithConstructorArgs
+    }
+    if found.leading_code == expected.leading_code
+        && compiler_versions_compatible(&found.metadata, &expected.metadata)
+    {
         return MatchType::Partial
     }
 
     MatchType::None
 }
 
+/// Returns the number of trailing zero bytes of the expected deployed code that are padding beyond
+/// the end of the found code. This is zero if the expected code isn't longer than the found code,
+/// or if the extra bytes aren't all zero or exceed `max_padding`, since then they aren't padding.
+pub fn deployed_code_padding(
+    found: &FoundDeployedBytecode,
+    expected: &ExpectedDeployedBytecode,
+    max_padding: usize,
+) -> usize {
+    let padding = expected.raw_code.len().saturating_sub(found.raw_code.len());
+    if padding == 0 || padding > max_padding {
+        return 0
+    }
+    let is_zero = expected.raw_code[found.raw_code.len()..].iter().all(|byte| *byte == 0);
+    if is_zero {
+        padding
+    } else {
+        0
+    }
+}
+
 /// Checks for equality between found and expected deployed bytecode and returns the type of match.
+/// Some chains pad deployed code with trailing zero bytes, so up to `max_padding` trailing zero
+/// bytes of the expected code are ignored. This is zero for chains that don't pad code.
 pub fn deployed_code_equality_check(
     found: &FoundDeployedBytecode,
     expected: &ExpectedDeployedBytecode,
+    max_padding: usize,
 ) -> MatchType {
     // If bytecode is empty, we have an interface, and we can't match with an interface.
     if found.raw_code.is_empty() {
         return MatchType::None
     }
 
+    // Strip trailing zero padding from the expected code. The leading code and metadata of the
+    // expected code are located using the found code, so they don't include the padding.
+    let padded_expected;
+    let expected = if deployed_code_padding(found, expected, max_padding) > 0 {
+        padded_expected = ExpectedDeployedBytecode {
+            raw_code: expected.raw_code[..found.raw_code.len()].to_vec().into(),
+            leading_code: expected.leading_code.clone(),
+            metadata: expected.metadata.clone(),
+            immutable_references: expected.immutable_references.clone(),
+            link_references: expected.link_references.clone(),
+        };
+        &padded_expected
+    } else {
+        expected
+    };
+
     // Expected and found code must have the same length.
     if found.raw_code.len() != expected.raw_code.len() {
         return MatchType::None
@@ -131,41 +626,45 @@ pub fn deployed_code_equality_check(
         return MatchType::Full
     }
 
-    // Compare the leading code, but skip all chunks that contain immutables.
-    if found.immutable_references == expected.immutable_references {
-        // Flatten the map to just a vec of the references. Since we know found and expected have
-        // equal immutable references due to how the structs were constructed, we can just use the
-        // found ones.
+    // Compare the leading code, but skip all chunks that contain immutables or library addresses.
+    if found.immutable_references == expected.immutable_references
+        && found.link_references == expected.link_references
+    {
+        // Flatten the maps to just a vec of the references. Since we know found and expected have
+        // equal references due to how the structs were constructed, we can just use the found ones.
         let mut offsets: Vec<Offsets> = Vec::new();
         for new_offsets in found.immutable_references.values() {
             offsets.extend(new_offsets.iter().cloned());
         }
+        offsets.extend(link_offsets(&found.link_references).cloned());
 
         // The expected bytecode is deployed and therefore has real values for the immutables. The
         // found bytecode uses zeroes as placeholders for the immutables (this is how solc works).
-        // Therefore for each immutable reference in the expected bytecode, we can replace the
-        // bytecode with zeroes, then compare the found bytecode with the expected bytecode.
-        // It's likely the metadata hashes won't match, so we adjust both the raw and leading code
-        // so we only have to loop through the offsets once.
-        let mut adjusted_expected_raw_code = expected.raw_code.to_vec();
-        let mut adjusted_expected_leading_code = expected.leading_code.to_vec();
-        for offset in offsets {
-            let immutable_start: usize = offset.start.try_into().unwrap();
-            let immutable_length: usize = offset.length.try_into().unwrap();
-            let immutable_end = immutable_start + immutable_length;
-            for i in immutable_start..immutable_end {
-                adjusted_expected_raw_code[i] = 0;
-                adjusted_expected_leading_code[i] = 0;
+        // Libraries are similar: the expected bytecode has the addresses the libraries were linked
+        // to, while the found bytecode has placeholders, which are zeroed when the found bytecode
+        // is parsed. Therefore for each reference we replace the bytecode of both with zeroes,
+        // then compare the found bytecode with the expected bytecode. It's likely the metadata
+        // hashes won't match, so we adjust both the raw and leading code so we can check for a
+        // partial match too.
+        let zero_references = |code: &Bytes| -> Vec<u8> {
+            let mut code = code.to_vec();
+            for offset in &offsets {
+                let start: usize = offset.start.try_into().unwrap();
+                let length: usize = offset.length.try_into().unwrap();
+                code.iter_mut().skip(start).take(length).for_each(|byte| *byte = 0);
             }
-        }
+            code
+        };
 
         // This matched with the metadata hash, so it's a full match.
-        if adjusted_expected_raw_code == found.raw_code {
+        if zero_references(&expected.raw_code) == zero_references(&found.raw_code) {
             return MatchType::Full
         }
 
         // Had to remove the metadata hash, so it's a partial match.
-        if adjusted_expected_leading_code == found.leading_code {
+        if zero_references(&expected.leading_code) == zero_references(&found.leading_code)
+            && compiler_versions_compatible(&found.metadata, &expected.metadata)
+        {
             return MatchType::Partial
         }
     }
@@ -173,6 +672,82 @@ pub fn deployed_code_equality_check(
     MatchType::None
 }
 
+/// A range of bytes within code, from `start` inclusive to `end` exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ByteRange {
+    /// Index of the first byte in the range.
+    pub start: usize,
+    /// Index after the last byte in the range.
+    pub end: usize,
+}
+
+/// Describes where found and expected deployed code differ, so a partial match can be explained.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BytecodeDiff {
+    /// The ranges of bytes that differ, excluding immutables, library addresses, and the metadata
+    /// hash, which are expected to differ. Bytes beyond the end of the shorter code are included,
+    /// unless they're trailing zero padding of the expected code.
+    pub ranges: Vec<ByteRange>,
+    /// Whether the metadata hashes differ.
+    pub metadata_hash_differs: bool,
+    /// The length of the found code.
+    pub found_length: usize,
+    /// The length of the expected code.
+    pub expected_length: usize,
+}
+
+/// Compares found and expected deployed code byte by byte and returns where they differ. Regions
+/// that are expected to differ, i.e. immutables, library addresses, and the metadata hash, are
+/// skipped and the metadata hashes are compared separately.
+pub fn diff_report(
+    found: &FoundDeployedBytecode,
+    expected: &ExpectedDeployedBytecode,
+) -> BytecodeDiff {
+    // Mark each byte of the found code that is excluded from the comparison.
+    let mut excluded = vec![false; found.raw_code.len()];
+    let immutable_offsets = found.immutable_references.values().flatten();
+    for offset in immutable_offsets.chain(link_offsets(&found.link_references)) {
+        let start: usize = offset.start.try_into().unwrap();
+        let length: usize = offset.length.try_into().unwrap();
+        excluded.iter_mut().skip(start).take(length).for_each(|byte| *byte = true);
+    }
+    if let (Some(start), Some(end)) = (found.metadata.start_index, found.metadata.end_index) {
+        excluded.iter_mut().take(end).skip(start).for_each(|byte| *byte = true);
+    }
+
+    // Bytes past the end of the found code differ, unless they're all zero, since then they're
+    // padding added by the chain.
+    let common_len = found.raw_code.len().min(expected.raw_code.len());
+    let is_padding = expected.raw_code[common_len..].iter().all(|byte| *byte == 0);
+    let diff_len =
+        if is_padding { found.raw_code.len() } else { common_len.max(expected.raw_code.len()) };
+
+    // Bytes both codes have differ unless they're excluded, and every byte past them differs.
+    let differing = excluded
+        .iter()
+        .zip(found.raw_code.iter().zip(expected.raw_code.iter()))
+        .enumerate()
+        .filter(|(_, (excluded, (found, expected)))| !**excluded && found != expected)
+        .map(|(index, _)| index)
+        .chain(common_len..diff_len);
+
+    // Merge adjacent differing bytes into ranges.
+    let mut ranges: Vec<ByteRange> = Vec::new();
+    for index in differing {
+        match ranges.last_mut() {
+            Some(range) if range.end == index => range.end += 1,
+            _ => ranges.push(ByteRange { start: index, end: index + 1 }),
+        }
+    }
+
+    BytecodeDiff {
+        ranges,
+        metadata_hash_differs: found.metadata.hash != expected.metadata.hash,
+        found_length: found.raw_code.len(),
+        expected_length: expected.raw_code.len(),
+    }
+}
+
 /// Given code, infers and returns the metadata details.
 ///
 /// The implied length returned by this method, i.e. `end_index - start_index`, is the decimal value
@@ -204,13 +779,27 @@ fn split_at_metadata_hash(code: &Bytes) -> (Bytes, Option<Bytes>) {
     // the metadata hash that we want needs to include those two bytes.
     let split_index = code.len() - metadata_hash_length.unwrap() - 2;
     let (code_before, maybe_metadata_hash) = code.split_at(split_index);
-    if maybe_metadata_hash.is_empty() {
-        (code.to_vec().into(), None)
-    } else {
+    if is_cbor_metadata(maybe_metadata_hash) {
         (code_before.to_vec().into(), Some(maybe_metadata_hash.to_vec().into()))
+    } else {
+        (code.to_vec().into(), None)
     }
 }
 
+/// The CBOR encodings of the keys solc writes to the metadata, i.e. each key's text string header
+/// followed by the key itself.
+const CBOR_METADATA_KEYS: [&[u8]; 4] = [b"\x64ipfs", b"\x65bzzr0", b"\x65bzzr1", b"\x64solc"];
+
+/// Returns true if the bytes look like solc metadata, i.e. a CBOR map of one to three entries
+/// containing at least one key that solc writes. Code compiled without metadata can end in bytes
+/// that look like a metadata length, and this check stops that code from being truncated.
+fn is_cbor_metadata(metadata_hash: &[u8]) -> bool {
+    matches!(metadata_hash.first(), Some(0xa1..=0xa3))
+        && CBOR_METADATA_KEYS
+            .iter()
+            .any(|key| metadata_hash.windows(key.len()).any(|window| window == *key))
+}
+
 /// The length returned by this method is the decimal value of the last two bytes. The total length
 /// of the metadata hash is this value plus 2 bytes for the length itself.
 fn get_metadata_hash_length(code: &Bytes) -> Option<usize> {
@@ -238,41 +827,233 @@ mod tests {
 
     #[test]
     fn test_creation_code_equality_check() -> Result<(), Box<dyn std::error::Error>> {
-        let found_code = Bytes::from_str("60606040525b6102c05b60")?;
-        let partial_match_code = Bytes::from_str("60606040525b6102c05b600000000000")?;
-        let no_match_code = Bytes::from_str("ff")?;
-
+        let found_code = Bytes::from_str("60606040525b6102c05b60a165627a7a72305820aa0029")?;
+        let found_leading_code = Bytes::from_str("60606040525b6102c05b60")?;
+        let found_metadata = MetadataInfo {
+            hash: Some(Bytes::from_str("a165627a7a72305820aa0029")?),
+            start_index: Some(11),
+            end_index: Some(23),
+        };
         let found = FoundCreationBytecode {
             raw_code: found_code.clone(),
-            leading_code: found_code.clone(),
-            metadata: MetadataInfo::default(),
+            leading_code: found_leading_code.clone(),
+            metadata: found_metadata,
         };
 
-        let expected_full = ExpectedCreationBytecode {
-            raw_code: found_code.clone(),
-            leading_code: found_code.clone(),
+        // The expected code is structured from the on-chain code the same way the verifier does.
+        let expected =
+            |code: &str| structure_expected_creation_code(&found, &Bytes::from_str(code)?);
+
+        // Identical code with no constructor arguments is a full match.
+        let expected_full = expected("60606040525b6102c05b60a165627a7a72305820aa0029")?;
+        assert_eq!(expected_full.constructor_args, None);
+        assert_eq!(creation_code_equality_check(&found, &expected_full), MatchType::Full);
+
+        // Identical code and metadata hash with appended constructor arguments is also a full
+        // match, with the arguments noted.
+        let expected_with_args =
+            expected("60606040525b6102c05b60a165627a7a72305820aa00290000000001")?;
+        assert_eq!(expected_with_args.constructor_args, Some(Bytes::from_str("0000000001")?));
+        assert_eq!(
+            creation_code_equality_check(&found, &expected_with_args),
+            MatchType::FullWithConstructorArgs
+        );
+
+        // Matching leading code with a different metadata hash is a partial match, whether or not
+        // constructor arguments are appended.
+        let expected_partial = expected("60606040525b6102c05b60a165627a7a72305820bb0029")?;
+        assert_eq!(creation_code_equality_check(&found, &expected_partial), MatchType::Partial);
+        let expected_partial_with_args =
+            expected("60606040525b6102c05b60a165627a7a72305820bb00290000000001")?;
+        assert_eq!(
+            creation_code_equality_check(&found, &expected_partial_with_args),
+            MatchType::Partial
+        );
+
+        // Different leading code is not a match.
+        let expected_none = expected("60606040525b6102c05b61a165627a7a72305820aa0029")?;
+        assert_eq!(creation_code_equality_check(&found, &expected_none), MatchType::None);
+        let expected_too_short = ExpectedCreationBytecode {
+            raw_code: Bytes::from_str("ff")?,
+            leading_code: Bytes::from_str("ff")?,
             metadata: MetadataInfo::default(),
             constructor_args: None,
         };
+        assert_eq!(creation_code_equality_check(&found, &expected_too_short), MatchType::None);
 
-        let expected_partial = ExpectedCreationBytecode {
-            raw_code: partial_match_code.clone(),
-            leading_code: found_code.clone(),
-            metadata: MetadataInfo::default(),
-            constructor_args: None,
+        Ok(())
+    }
+
+    #[test]
+    fn test_structure_creation_code_with_cbor_but_no_metadata_hash(
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // Creation code built with `bytecodeHash = "none"` and `appendCBOR = true`, as with the
+        // `no_metadata_yes_cbor` profile. The deployed code at the end of the creation code ends
+        // with CBOR containing only the solc version.
+        let settings = SettingsMetadata::new(BytecodeHash::None, true);
+        let init_code = "608060405234801561001057600080fd5b50603f8061001f6000396000f3fe";
+        let runtime_code = "6080604052348015600f57600080fd5b50";
+        let cbor = "a164736f6c6343000813000a";
+        let found_code = Bytes::from_str(&format!("{init_code}{runtime_code}{cbor}"))?;
+        let found = structure_found_creation_code(found_code.clone(), &settings);
+
+        // The 12 byte CBOR trailer is split from the leading code.
+        assert_eq!(found.leading_code, Bytes::from_str(&format!("{init_code}{runtime_code}"))?);
+        assert_eq!(found.metadata.hash, Some(Bytes::from_str(cbor)?));
+        assert_eq!(found.metadata.start_index, Some(found_code.len() - 12));
+        assert_eq!(found.metadata.end_index, Some(found_code.len()));
+        assert_eq!(found.metadata.solc_version().as_deref(), Some("0.8.19"));
+
+        // Appended constructor arguments aren't mistaken for the trailer.
+        let onchain_code =
+            Bytes::from_str(&format!("{init_code}{runtime_code}{cbor}{}", "00".repeat(31) + "2a"))?;
+        let expected = structure_expected_creation_code(&found, &onchain_code)?;
+        assert_eq!(expected.metadata.hash, Some(Bytes::from_str(cbor)?));
+        assert_eq!(expected.constructor_args, Some(Bytes::from_str(&("00".repeat(31) + "2a"))?));
+        assert_eq!(
+            creation_code_equality_check(&found, &expected),
+            MatchType::FullWithConstructorArgs
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_deployed_code_equality_check_padding() -> Result<(), Box<dyn std::error::Error>> {
+        let settings = SettingsMetadata::new(BytecodeHash::Ipfs, true);
+        let metadata = format!("a2646970667358221220{}64736f6c63430008130033", "aa".repeat(32));
+        let found_code = Bytes::from_str(&format!("6080604052{metadata}"))?;
+        let found =
+            structure_found_deployed_code(found_code, BTreeMap::new(), BTreeMap::new(), &settings);
+        let check = |code: &str, max_padding| -> Result<MatchType, Box<dyn std::error::Error>> {
+            let expected = structure_expected_deployed_code(&found, &Bytes::from_str(code)?)?;
+            Ok(deployed_code_equality_check(&found, &expected, max_padding))
         };
 
-        let expected_none = ExpectedCreationBytecode {
-            raw_code: no_match_code.clone(),
-            leading_code: no_match_code.clone(),
-            metadata: MetadataInfo::default(),
-            constructor_args: None,
+        // Padding is only ignored when the chain allows it.
+        let padded = format!("6080604052{metadata}0000");
+        let expected = structure_expected_deployed_code(&found, &Bytes::from_str(&padded)?)?;
+        assert_eq!(deployed_code_padding(&found, &expected, 2), 2);
+        assert_eq!(deployed_code_padding(&found, &expected, 1), 0);
+        assert_eq!(check(&padded, 0)?, MatchType::None);
+        assert_eq!(check(&padded, 2)?, MatchType::Full);
+        assert_eq!(check(&padded, 1)?, MatchType::None);
+
+        // Trailing bytes that aren't zero are not padding.
+        let trailing = format!("6080604052{metadata}0001");
+        assert_eq!(check(&trailing, 2)?, MatchType::None);
+        let expected = structure_expected_deployed_code(&found, &Bytes::from_str(&trailing)?)?;
+        assert_eq!(deployed_code_padding(&found, &expected, 2), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_deployed_code_equality_check_linked_libraries() -> Result<(), Box<dyn std::error::Error>>
+    {
+        let settings = SettingsMetadata::new(BytecodeHash::Ipfs, true);
+        let metadata =
+            |byte: &str| format!("a2646970667358221220{}64736f6c63430008130033", byte.repeat(32));
+        let link_references: LinkReferences = serde_json::from_value(serde_json::json!({
+            "src/Lib.sol": { "Lib": [ { "start": 1, "length": 20 } ] },
+        }))?;
+        let unlinked = format!("73__$a1b2c3d4e5f6a1b2c3d4e5f6a1b2c3d4e5$__6000{}", metadata("aa"));
+        let found_code = parse_unlinked_code(&unlinked, &link_references)?;
+        let found =
+            structure_found_deployed_code(found_code, BTreeMap::new(), link_references, &settings);
+        let check = |code: &str| -> Result<MatchType, Box<dyn std::error::Error>> {
+            let expected = structure_expected_deployed_code(&found, &Bytes::from_str(code)?)?;
+            Ok(deployed_code_equality_check(&found, &expected, 0))
         };
 
-        assert_eq!(creation_code_equality_check(&found, &expected_none), MatchType::None);
-        assert_eq!(creation_code_equality_check(&found, &expected_full), MatchType::Full);
-        assert_eq!(creation_code_equality_check(&found, &expected_partial), MatchType::Partial);
+        // The library address deployed on-chain is ignored, but the rest of the code isn't.
+        let library = "11".repeat(20);
+        assert_eq!(check(&format!("73{library}6000{}", metadata("aa")))?, MatchType::Full);
+        assert_eq!(check(&format!("73{library}6000{}", metadata("bb")))?, MatchType::Partial);
+        assert_eq!(check(&format!("73{library}6001{}", metadata("aa")))?, MatchType::None);
+
+        // Placeholders without link references can't be parsed.
+        assert!(parse_unlinked_code(&unlinked, &BTreeMap::new()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff_report() -> Result<(), Box<dyn std::error::Error>> {
+        let settings = SettingsMetadata::new(BytecodeHash::Ipfs, true);
+        let metadata =
+            |byte: &str| format!("a2646970667358221220{}64736f6c63430008130033", byte.repeat(32));
+        let immutable_references: ImmutableReferences =
+            serde_json::from_value(serde_json::json!({
+                "7": [ { "start": 1, "length": 32 } ],
+            }))?;
+        let found_code =
+            Bytes::from_str(&format!("7f{}6000600160{}", "00".repeat(32), metadata("aa")))?;
+        let found = structure_found_deployed_code(
+            found_code,
+            immutable_references,
+            BTreeMap::new(),
+            &settings,
+        );
+        let diff = |code: &str| -> Result<BytecodeDiff, Box<dyn std::error::Error>> {
+            let expected = structure_expected_deployed_code(&found, &Bytes::from_str(code)?)?;
+            Ok(diff_report(&found, &expected))
+        };
+        let immutable = "11".repeat(32);
+
+        // Immutables are skipped, so only the metadata hash of a partial match differs.
+        let report = diff(&format!("7f{immutable}6000600160{}", metadata("bb")))?;
+        assert!(report.ranges.is_empty());
+        assert!(report.metadata_hash_differs);
+        assert_eq!((report.found_length, report.expected_length), (91, 91));
+
+        // Differing bytes outside of the skipped regions are merged into ranges.
+        let report = diff(&format!("7f{immutable}6101600260{}", metadata("aa")))?;
+        let expected_ranges =
+            vec![ByteRange { start: 33, end: 35 }, ByteRange { start: 36, end: 37 }];
+        assert_eq!(report.ranges, expected_ranges);
+        assert!(!report.metadata_hash_differs);
 
+        // Trailing zeroes are padding, but other trailing bytes differ.
+        let report = diff(&format!("7f{immutable}6000600160{}0000", metadata("aa")))?;
+        assert!(report.ranges.is_empty());
+        let report = diff(&format!("7f{immutable}6000600160{}0001", metadata("aa")))?;
+        assert_eq!(report.ranges, vec![ByteRange { start: 91, end: 93 }]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_reproduce_ipfs_metadata_hash() -> Result<(), Box<dyn std::error::Error>> {
+        // The metadata and deployed code of an empty contract built with `bytecodeHash: none`,
+        // where the code ends with only the CBOR-encoded solc version. The expected code ends with
+        // the metadata hash of the same metadata with `bytecodeHash: ipfs`, whose CIDv0 is
+        // `QmVBhtrT8FQ99E8iw2wesz5muTDCQk2xTnbfftPXjYWmEz`.
+        let raw_metadata = concat!(
+            r#"{"compiler":{"version":"0.8.19+commit.7dd6d404"},"language":"Solidity","#,
+            r#""output":{"abi":[],"devdoc":{"kind":"dev","methods":{},"version":1},"#,
+            r#""userdoc":{"kind":"user","methods":{},"version":1}},"#,
+            r#""settings":{"compilationTarget":{"src/Empty.sol":"Empty"},"evmVersion":"paris","#,
+            r#""libraries":{},"metadata":{"bytecodeHash":"none"},"#,
+            r#""optimizer":{"enabled":true,"runs":200},"remappings":[]},"#,
+            r#""sources":{"src/Empty.sol":{"keccak256":"#,
+            r#""0xc4807d172d67ef4f68da19252bceba398ea52d7952aa2a8e498b461e20663795","#,
+            r#""license":"MIT","#,
+            r#""urls":["dweb:/ipfs/QmWomchLNE3Uuk8NApWnEeH6iBLVtwfPb5BTgbKbPg4Pyq"]}},"#,
+            r#""version":1}"#,
+        );
+        let settings = SettingsMetadata::new(BytecodeHash::None, true);
+        let found_code = Bytes::from_str("6080604052600080fdfea164736f6c6343000813000a")?;
+        let expected_code = Bytes::from_str(concat!(
+            "6080604052600080fdfe",
+            "a2646970667358221220",
+            "65b72ecd564fcbb507e21adf66559d0170d56cc387ab98eb7e38c091eca8a693",
+            "64736f6c63430008130033",
+        ))?;
+        let reproduced = reproduce_ipfs_metadata_hash(&found_code, &settings, raw_metadata);
+        assert_eq!(reproduced, Some(expected_code));
+
+        // Code that already has an IPFS hash or was built by a prerelease compiler is not rebuilt.
+        let ipfs_settings = SettingsMetadata::new(BytecodeHash::Ipfs, true);
+        assert!(reproduce_ipfs_metadata_hash(&found_code, &ipfs_settings, raw_metadata).is_none());
+        let prerelease = raw_metadata.replace("0.8.19+", "0.8.20-nightly.2023.5.1+");
+        assert!(reproduce_ipfs_metadata_hash(&found_code, &settings, &prerelease).is_none());
         Ok(())
     }
 
@@ -282,15 +1063,68 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_detect_proxy() -> Result<(), Box<dyn std::error::Error>> {
+        let implementation = "bebebebebebebebebebebebebebebebebebebebe";
+        let minimal_proxy = Bytes::from_str(&format!(
+            "363d3d373d3d3d363d73{implementation}5af43d82803e903d91602b57fd5bf3"
+        ))?;
+        assert_eq!(
+            detect_proxy(&minimal_proxy),
+            Some(ProxyKind::MinimalProxy(Address::from_str(implementation)?))
+        );
+
+        // An EIP-1967 proxy pushes the implementation slot onto the stack to `sload` it.
+        let eip1967_proxy = Bytes::from_str(
+            "60806040527f360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc54",
+        )?;
+        assert_eq!(detect_proxy(&eip1967_proxy), Some(ProxyKind::Eip1967));
+
+        assert_eq!(detect_proxy(&Bytes::from_str("6080604052")?), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_minimal_proxy() -> Result<(), Box<dyn std::error::Error>> {
+        // A clone of the Uniswap V3 factory, as deployed by OpenZeppelin's `Clones` library.
+        let implementation = "1f98431c8ad98523631ae4a59f267346ea31f984";
+        let template = |implementation: &str| {
+            format!("363d3d373d3d3d363d73{implementation}5af43d82803e903d91602b57fd5bf3")
+        };
+        let minimal_proxy = Bytes::from_str(&template(implementation))?;
+        assert_eq!(is_minimal_proxy(&minimal_proxy), Some(Address::from_str(implementation)?));
+
+        // The template is matched exactly, so longer, shorter, or modified code isn't a proxy.
+        let not_proxies = [
+            template(implementation) + "00",
+            template(&implementation[2..]),
+            template(implementation).replace("5af4", "5af1"),
+            "6080604052".to_string(),
+        ];
+        for code in not_proxies {
+            assert_eq!(is_minimal_proxy(&Bytes::from_str(&code)?), None, "{code}");
+        }
+        Ok(())
+    }
+
     #[test]
     fn test_parse_metadata() -> Result<(), Box<dyn std::error::Error>> {
+        #[rustfmt::skip]
         let test_cases = vec![
-            (19, Bytes::from_str("ffffffffffffffffffffffffffffffffff0011")?),
-            (258, Bytes::from_str("ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff0100")?)
+            (Some(12), Bytes::from_str("57600080fd5b5056fea164736f6c6343000706000a")?),
+            (Some(53), Bytes::from_str("676e6174757265206c656e677468a2646970667358221220dceca8706b29e917dacf25fceef95acac8d90d765ac926663ce4096195952b6164736f6c634300060b0033")?),
+            // The last two bytes look like a length, but the code has no metadata.
+            (None, Bytes::from_str("ffffffffffffffffffffffffffffffffff0011")?),
+            // The region starts with a CBOR map header, but has no key that solc writes.
+            (None, Bytes::from_str("6080a16466616b6543000102000a")?),
+            // The region has a key that solc writes, but doesn't start with a CBOR map header.
+            (None, Bytes::from_str("6080ff64736f6c6343000706000a")?),
         ];
         for (expected_len, data) in test_cases {
             let metadata = super::parse_metadata(&data);
-            assert_eq!(expected_len, metadata.end_index.unwrap() - metadata.start_index.unwrap());
+            let len = metadata.end_index.zip(metadata.start_index).map(|(end, start)| end - start);
+            assert_eq!(len, expected_len, "{data}");
+            assert_eq!(metadata.hash.is_some(), expected_len.is_some());
         }
         Ok(())
     }
@@ -332,4 +1166,117 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn test_decode_cbor_metadata() -> Result<(), Box<dyn std::error::Error>> {
+        #[rustfmt::skip]
+        let test_cases = vec![
+            // ipfs + solc 0.8.19.
+            ("a2646970667358221220dceca8706b29e917dacf25fceef95acac8d90d765ac926663ce4096195952b6164736f6c63430008130033", Some("0.8.19")),
+            // solc 0.7.6 only.
+            ("a164736f6c6343000706000a", Some("0.7.6")),
+            // solc 0.0.0 placeholder.
+            ("a164736f6c6343000000000a", None),
+            // No solc key, only `experimental`.
+            ("a16c6578706572696d656e74616cf5000f", None),
+        ];
+
+        for (metadata_hash, expected_version) in test_cases {
+            let metadata = decode_cbor_metadata(&Bytes::from_str(metadata_hash)?)?;
+            assert_eq!(metadata.solc_version.as_deref(), expected_version);
+        }
+
+        let metadata =
+            decode_cbor_metadata(&Bytes::from_str("a16c6578706572696d656e74616cf5000f")?)?;
+        assert_eq!(metadata.experimental, Some(true));
+        assert_eq!(metadata.ipfs, None);
+
+        // The hashes are decoded too, with the IPFS hash as a CID.
+        let hash = "dceca8706b29e917dacf25fceef95acac8d90d765ac926663ce4096195952b61";
+        let metadata = decode_cbor_metadata(&Bytes::from_str(&format!(
+            "a2646970667358221220{hash}64736f6c634300060b0033"
+        ))?)?;
+        assert_eq!(
+            metadata.ipfs.as_deref(),
+            Some("bafybeig45suha2zj5el5vtzf7txpswwkzdmq25s2zetgmphebfqzlfjlme")
+        );
+        assert_eq!(metadata.solc_version.as_deref(), Some("0.6.11"));
+        let metadata =
+            decode_cbor_metadata(&Bytes::from_str(&format!("a165627a7a72305820{hash}0029"))?)?;
+        assert_eq!(metadata.bzzr0, Some(Bytes::from_str(hash)?));
+        assert_eq!(metadata.solc_version, None);
+        let metadata = decode_cbor_metadata(&Bytes::from_str(&format!(
+            "a265627a7a72315820{hash}64736f6c63430005100032"
+        ))?)?;
+        assert_eq!(metadata.bzzr1, Some(Bytes::from_str(hash)?));
+        assert_eq!(metadata.solc_version.as_deref(), Some("0.5.16"));
+
+        // Malformed metadata is an error rather than a panic.
+        for metadata_hash in ["", "00", "a2646970667358221220dcec0033", "ff0001", "010001"] {
+            assert!(decode_cbor_metadata(&Bytes::from_str(metadata_hash)?).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_metadata_hash_type() -> Result<(), Box<dyn std::error::Error>> {
+        #[rustfmt::skip]
+        let test_cases = vec![
+            ("6080604052a2646970667358221220dceca8706b29e917dacf25fceef95acac8d90d765ac926663ce4096195952b6164736f6c63430008130033", MetadataHashType::Ipfs),
+            ("60606040525b6102c05b60a165627a7a72305820dceca8706b29e917dacf25fceef95acac8d90d765ac926663ce4096195952b610029", MetadataHashType::Bzzr0),
+            ("6080604052a164736f6c6343000706000a", MetadataHashType::None),
+            ("6080604052", MetadataHashType::None),
+        ];
+        for (code, expected) in test_cases {
+            assert_eq!(metadata_hash_type(&Bytes::from_str(code)?), expected, "{code}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_constructor_args() -> Result<(), Box<dyn std::error::Error>> {
+        let abi: Abi = serde_json::from_str(
+            r#"[{"type":"constructor","stateMutability":"nonpayable","inputs":[{"name":"self","type":"address"},{"name":"count","type":"uint256"},{"name":"owners","type":"address[]"}]}]"#,
+        )?;
+        let address =
+            ethers::types::Address::from_str("0x8d56e3e001132d84488dbacdbb01afb8c3171242")?;
+        let encoded: Bytes = ethers::abi::encode(&[
+            Token::Address(address),
+            Token::Uint(5.into()),
+            Token::Array(vec![Token::Address(address)]),
+        ])
+        .into();
+
+        let args = decode_constructor_args(&abi, &encoded)?;
+        let checksummed = "0x8d56e3e001132d84488DbacDbB01AfB8C3171242";
+        assert_eq!(
+            args,
+            vec![
+                ConstructorArg {
+                    name: "self".to_string(),
+                    kind: "address".to_string(),
+                    value: checksummed.into(),
+                },
+                ConstructorArg {
+                    name: "count".to_string(),
+                    kind: "uint256".to_string(),
+                    value: "5".into(),
+                },
+                ConstructorArg {
+                    name: "owners".to_string(),
+                    kind: "address[]".to_string(),
+                    value: serde_json::json!([checksummed]),
+                },
+            ]
+        );
+
+        // Truncated arguments can't be decoded.
+        assert!(decode_constructor_args(&abi, &encoded[..40].to_vec().into()).is_err());
+
+        // Without a constructor there are no arguments, and any appended bytes are an error.
+        let abi: Abi = serde_json::from_str("[]")?;
+        assert_eq!(decode_constructor_args(&abi, &Bytes::default())?, Vec::new());
+        assert!(decode_constructor_args(&abi, &encoded).is_err());
+        Ok(())
+    }
 }
@@ -0,0 +1,128 @@
+use ethers::types::Chain;
+use std::{
+    collections::HashMap,
+    sync::{Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+/// Tracks consecutive RPC failures for each chain. Once a chain fails `failure_threshold` times in
+/// a row within `failure_window`, its circuit opens and the chain is skipped until `cooldown` has
+/// elapsed. After that, requests to the chain are allowed again to probe it: a success closes the
+/// circuit, and a failure reopens it for another cooldown.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    /// The number of consecutive failures that opens a chain's circuit.
+    failure_threshold: u32,
+    /// The window that consecutive failures must occur within to open a chain's circuit.
+    failure_window: Duration,
+    /// How long a chain's circuit stays open before the chain is probed again.
+    cooldown: Duration,
+    /// The circuit of each chain that has failed since its last success.
+    circuits: Mutex<HashMap<Chain, Circuit>>,
+}
+
+/// The state of a single chain's circuit.
+#[derive(Debug, Default)]
+struct Circuit {
+    /// The number of consecutive failures.
+    consecutive_failures: u32,
+    /// The time of the first of the consecutive failures.
+    first_failure: Option<Instant>,
+    /// If the circuit has opened, the time until which the chain is skipped.
+    open_until: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    /// Create a new `CircuitBreaker` with all circuits closed.
+    pub fn new(failure_threshold: u32, failure_window: Duration, cooldown: Duration) -> Self {
+        Self { failure_threshold, failure_window, cooldown, circuits: Mutex::new(HashMap::new()) }
+    }
+
+    /// Returns `false` if the chain's circuit is open, meaning the chain should not be queried.
+    pub fn is_available(&self, chain: Chain) -> bool {
+        match self.circuits().get(&chain).and_then(|circuit| circuit.open_until) {
+            Some(open_until) => Instant::now() >= open_until,
+            None => true,
+        }
+    }
+
+    /// Records a successful query of the chain, closing its circuit.
+    pub fn record_success(&self, chain: Chain) {
+        self.circuits().remove(&chain);
+    }
+
+    /// Records a failed query of the chain, opening its circuit if the failure threshold is
+    /// reached.
+    pub fn record_failure(&self, chain: Chain) {
+        let now = Instant::now();
+        let mut circuits = self.circuits();
+        let circuit = circuits.entry(chain).or_default();
+
+        // A failed probe of a chain whose circuit has opened reopens it immediately.
+        if circuit.open_until.is_some() {
+            circuit.open_until = Some(now + self.cooldown);
+            return
+        }
+
+        let within_window = matches!(
+            circuit.first_failure,
+            Some(first_failure) if now.duration_since(first_failure) <= self.failure_window
+        );
+        if !within_window {
+            circuit.consecutive_failures = 0;
+            circuit.first_failure = Some(now);
+        }
+        circuit.consecutive_failures += 1;
+        if circuit.consecutive_failures >= self.failure_threshold {
+            circuit.open_until = Some(now + self.cooldown);
+        }
+    }
+
+    /// Returns the circuits, recovering them if a thread panicked while holding the lock since the
+    /// circuit state is always valid.
+    fn circuits(&self) -> MutexGuard<'_, HashMap<Chain, Circuit>> {
+        self.circuits.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opens_after_consecutive_failures() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(60), Duration::from_secs(60));
+        assert!(breaker.is_available(Chain::Mainnet));
+
+        breaker.record_failure(Chain::Mainnet);
+        assert!(breaker.is_available(Chain::Mainnet));
+        breaker.record_failure(Chain::Mainnet);
+        assert!(!breaker.is_available(Chain::Mainnet));
+        assert!(breaker.is_available(Chain::Optimism));
+
+        breaker.record_success(Chain::Mainnet);
+        assert!(breaker.is_available(Chain::Mainnet));
+    }
+
+    #[test]
+    fn test_success_resets_failures() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(60), Duration::from_secs(60));
+        breaker.record_failure(Chain::Mainnet);
+        breaker.record_success(Chain::Mainnet);
+        breaker.record_failure(Chain::Mainnet);
+        assert!(breaker.is_available(Chain::Mainnet));
+    }
+
+    #[test]
+    fn test_probes_after_cooldown() {
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(60), Duration::from_millis(20));
+        breaker.record_failure(Chain::Mainnet);
+        assert!(!breaker.is_available(Chain::Mainnet));
+
+        // After the cooldown the chain is probed, and a failed probe reopens the circuit.
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(breaker.is_available(Chain::Mainnet));
+        breaker.record_failure(Chain::Mainnet);
+        assert!(!breaker.is_available(Chain::Mainnet));
+    }
+}
@@ -1,16 +1,41 @@
+use crate::retry::RetryPolicy;
 use config::{Config, ConfigError, File};
+use ethers::types::Chain;
+use ethers_solc::Solc;
 use serde::Deserialize;
+use std::{collections::HashMap, path::PathBuf, time::Duration};
 
-/// All settings for the server. Currently there are only application settings, but in the future
-/// there may be e.g. database settings.
-#[derive(Deserialize)]
+/// All settings for the server.
+#[derive(Deserialize, Clone)]
 pub struct Settings {
     /// Application settings.
     pub application: ApplicationSettings,
+    /// Verification settings.
+    pub verification: VerificationSettings,
+    /// Block explorer base URLs by chain, e.g. `https://etherscan.io` for mainnet. These are used to
+    /// return links to the verified contract. Chains without an explorer get no links.
+    #[serde(default)]
+    pub explorers: HashMap<Chain, String>,
+    /// RPC settings.
+    #[serde(default)]
+    pub rpc: RpcSettings,
+    /// Settings for the pool of workers that verification jobs run on.
+    #[serde(default)]
+    pub scheduler: SchedulerSettings,
+    /// The chains that are supported, keyed by chain ID, along with their RPC URLs. Contracts are
+    /// only looked up and verified on these chains.
+    #[serde(default, deserialize_with = "deserialize_chain_ids")]
+    pub chains: HashMap<u64, ChainSettings>,
+    /// Settings for storing successful verifications.
+    #[serde(default)]
+    pub database: DatabaseSettings,
+    /// Settings for logging verification requests.
+    #[serde(default)]
+    pub request_log: RequestLogSettings,
 }
 
 /// Application settings.
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone)]
 pub struct ApplicationSettings {
     /// The port number on which the application will listen.
     pub port: u16,
@@ -21,6 +46,296 @@ pub struct ApplicationSettings {
     /// accessible. This could be a hostname like "localhost" or an IP address like
     /// "127.0.0.1".
     pub host: String,
+
+    /// Optional, a shared secret that verification requests must be signed with. When set,
+    /// requests to the verify endpoints must include an HMAC-SHA256 signature of the body, or of
+    /// the path for requests to `/verify/status/{job_id}`, and when unset the endpoints are open.
+    #[serde(default)]
+    pub hmac_secret: Option<String>,
+
+    /// Whether verification responses are pretty-printed by default, which is easier to read when
+    /// debugging. Requests can override this with the `pretty` query parameter.
+    #[serde(default)]
+    pub pretty_json: bool,
+
+    /// The number of seconds the server waits on shutdown for in-flight requests and background
+    /// verifications to finish before aborting them. Defaults to 30 seconds.
+    #[serde(default = "default_shutdown_timeout_secs")]
+    pub shutdown_timeout_secs: u64,
+}
+
+/// Returns the shutdown timeout used when none is configured.
+fn default_shutdown_timeout_secs() -> u64 {
+    30
+}
+
+impl ApplicationSettings {
+    /// Returns how long the server waits on shutdown for verifications to finish.
+    pub fn shutdown_timeout(&self) -> Duration {
+        Duration::from_secs(self.shutdown_timeout_secs)
+    }
+}
+
+/// Settings that bound the work performed for a single verification request.
+#[derive(Deserialize, Clone)]
+pub struct VerificationSettings {
+    /// The maximum number of artifacts that will be compared against on-chain code for each build.
+    /// Repos that generate more artifacts than this must specify the contract name to verify,
+    /// which bounds the worst-case CPU usage of the public endpoint.
+    pub max_artifacts: usize,
+    /// The directory that repositories are cloned and built in. Defaults to the system temp
+    /// directory.
+    #[serde(default)]
+    pub temp_dir_root: Option<PathBuf>,
+    /// Whether to keep the build directory of a failed verification instead of deleting it, so the
+    /// build can be inspected. The path of a kept directory is logged.
+    #[serde(default)]
+    pub keep_on_failure: bool,
+    /// The forge versions that builds may use, matched against the semver or commit hash of
+    /// `forge --version`. Pinning versions keeps verifications reproducible, since different
+    /// forge versions can produce different artifacts. An empty list allows any version.
+    #[serde(default)]
+    pub allowed_forge_versions: Vec<String>,
+    /// The maximum number of trailing zero bytes ignored when comparing deployed code on each
+    /// chain. Some chains pad deployed code, so its length differs from the compiled code even
+    /// when the code matches. Chains that aren't listed must match the compiled length exactly.
+    #[serde(default)]
+    pub deployed_code_padding: HashMap<Chain, usize>,
+    /// The maximum total size in bytes of the sources returned inline in a verification response.
+    /// When exceeded, only the most-derived contract's source is returned inline, and all sources
+    /// are downloadable as a zip archive. Defaults to no limit.
+    #[serde(default)]
+    pub max_source_bytes: Option<usize>,
+    /// The directory that source archives of truncated responses are written to. Defaults to a
+    /// `cove-sources` directory in the system temp directory.
+    #[serde(default)]
+    pub source_archive_dir: Option<PathBuf>,
+    /// The maximum number of source archives kept in the source archive directory. When exceeded,
+    /// the oldest archives are removed. Defaults to 1000.
+    #[serde(default = "default_max_source_archives")]
+    pub max_source_archives: usize,
+    /// The directory that build output is cached in, keyed by repository, commit, and build
+    /// configuration, so verifying another contract from the same codebase skips compilation.
+    /// Cached builds are invalidated when the forge version changes. Caching is disabled if unset.
+    #[serde(default)]
+    pub artifact_cache_dir: Option<PathBuf>,
+    /// The hosts that repositories can be cloned from, e.g. `github.com`. Repositories are only
+    /// cloned over HTTPS, so requests can't make the server clone from internal hosts or read
+    /// local files. Defaults to GitHub and GitLab.
+    #[serde(default = "default_allowed_repo_hosts")]
+    pub allowed_repo_hosts: Vec<String>,
+    /// Whether repositories can also be cloned from absolute paths on the server. This is only
+    /// meant for local development and tests, since it exposes local repositories to requests.
+    #[serde(default)]
+    pub allow_local_repos: bool,
+    /// The number of seconds each build command can run for before it's killed. A build that times
+    /// out is treated as a failed build, so the next build command is tried. Defaults to 10
+    /// minutes.
+    #[serde(default = "default_build_timeout_secs")]
+    pub build_timeout_secs: u64,
+    /// The number of seconds cloning the repository, and separately checking out the commit, can
+    /// run for before it's killed and the verification fails. Defaults to 2 minutes.
+    #[serde(default = "default_clone_timeout_secs")]
+    pub clone_timeout_secs: u64,
+    /// The directory that solc binaries are installed to by svm, laid out as
+    /// `<version>/solc-<version>`, which is used to compile contracts verified from their
+    /// metadata. Defaults to svm's own data directory, which forge also installs compilers to.
+    #[serde(default)]
+    pub solc_dir: Option<PathBuf>,
+}
+
+/// Returns the hosts that repositories can be cloned from when none are configured.
+fn default_allowed_repo_hosts() -> Vec<String> {
+    vec!["github.com".to_string(), "gitlab.com".to_string()]
+}
+
+/// Returns the maximum number of source archives used when none is configured.
+fn default_max_source_archives() -> usize {
+    1000
+}
+
+/// Returns the build timeout used when none is configured.
+fn default_build_timeout_secs() -> u64 {
+    600
+}
+
+/// Returns the clone timeout used when none is configured.
+fn default_clone_timeout_secs() -> u64 {
+    120
+}
+
+impl VerificationSettings {
+    /// Returns the directory that source archives are written to and served from.
+    pub fn source_archive_dir(&self) -> PathBuf {
+        self.source_archive_dir.clone().unwrap_or_else(|| std::env::temp_dir().join("cove-sources"))
+    }
+
+    /// Returns how long each build command can run for.
+    pub fn build_timeout(&self) -> Duration {
+        Duration::from_secs(self.build_timeout_secs)
+    }
+
+    /// Returns how long cloning the repository, and checking out the commit, can each run for.
+    pub fn clone_timeout(&self) -> Duration {
+        Duration::from_secs(self.clone_timeout_secs)
+    }
+
+    /// Returns the directory that solc binaries are installed to, or `None` if none is configured
+    /// and svm's data directory can't be determined.
+    pub fn solc_dir(&self) -> Option<PathBuf> {
+        self.solc_dir.clone().or_else(Solc::svm_home)
+    }
+}
+
+/// Settings for the RPCs used to query each chain.
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct RpcSettings {
+    /// Chains whose RPCs support JSON-RPC batch requests. Requests for these chains are batched
+    /// where possible to reduce load on rate-limited RPCs.
+    pub batch_requests: Vec<Chain>,
+    /// The number of consecutive RPC failures after which a chain is temporarily skipped.
+    pub failure_threshold: u32,
+    /// The window, in seconds, that consecutive failures must occur within to skip a chain.
+    pub failure_window_secs: u64,
+    /// How long, in seconds, a failing chain is skipped before its RPC is queried again.
+    pub cooldown_secs: u64,
+    /// Whether transactions are traced to find creation code, which requires RPCs that support
+    /// the Parity or Geth tracing APIs. Disable this for RPCs that don't support tracing.
+    pub tracing_enabled: bool,
+    /// Chains whose RPCs support Otterscan's `ots_getContractCreator` method, e.g. Erigon and
+    /// Anvil nodes, which is used to discover the creation transaction of a contract when none is
+    /// provided. Other chains rely on the block search below.
+    pub contract_creator_lookup: Vec<Chain>,
+    /// Optional, the maximum number of blocks searched for the deployment block of a contract
+    /// whose creation transaction isn't provided and can't be found by tracing. The search
+    /// requires archive RPCs, so it's disabled when unset.
+    pub creation_search_max_blocks: Option<u64>,
+    /// The maximum number of times an RPC call is made when it fails transiently, e.g. because
+    /// the RPC rate limited it, including the first call. Set to 1 to disable retries.
+    pub retry_attempts: u32,
+    /// The delay, in milliseconds, before the first retry of a failed RPC call. Each subsequent
+    /// retry waits twice as long.
+    pub retry_base_delay_ms: u64,
+}
+
+impl RpcSettings {
+    /// Returns the policy for retrying RPC calls after transient failures.
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            attempts: self.retry_attempts,
+            base_delay: Duration::from_millis(self.retry_base_delay_ms),
+        }
+    }
+}
+
+impl Default for RpcSettings {
+    fn default() -> Self {
+        Self {
+            batch_requests: Vec::new(),
+            failure_threshold: 3,
+            failure_window_secs: 60,
+            cooldown_secs: 30,
+            tracing_enabled: true,
+            contract_creator_lookup: Vec::new(),
+            creation_search_max_blocks: None,
+            retry_attempts: 3,
+            retry_base_delay_ms: 200,
+        }
+    }
+}
+
+/// The RPC URLs of a supported chain. Each URL is either the URL itself or, if it starts with `$`,
+/// the name of the environment variable containing it, so URLs with API keys can be kept out of
+/// configuration files.
+#[derive(Deserialize, Clone, Debug)]
+pub struct ChainSettings {
+    /// The URL of the chain's RPC.
+    pub rpc_url: String,
+    /// Optional, the URL of an archive RPC, which is queried for deployed code when the RPC above
+    /// has none, e.g. because it has pruned the state. Ignored if empty.
+    #[serde(default)]
+    pub archive_rpc_url: Option<String>,
+}
+
+/// Deserializes the `chains` tables, whose keys are chain IDs. Table keys are always strings, so
+/// each key is parsed as a number.
+fn deserialize_chain_ids<'de, D>(deserializer: D) -> Result<HashMap<u64, ChainSettings>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    HashMap::<String, ChainSettings>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(key, settings)| {
+            let chain_id = key.parse().map_err(|_| {
+                serde::de::Error::custom(format!("Invalid chain ID '{key}' in the chains settings"))
+            })?;
+            Ok((chain_id, settings))
+        })
+        .collect()
+}
+
+/// Settings for storing successful verifications, so previously verified contracts can be looked
+/// up by the `/contract` route instead of being decompiled.
+#[derive(Deserialize, Clone, Default)]
+pub struct DatabaseSettings {
+    /// The directory verifications are stored in. Relative paths are relative to the server's
+    /// working directory. Verifications are not stored if unset.
+    pub dir: Option<PathBuf>,
+}
+
+/// Settings for logging verification requests and their results to an external store.
+#[derive(Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct RequestLogSettings {
+    /// Where requests are logged. Defaults to Airtable.
+    pub backend: RequestLogBackend,
+}
+
+/// The stores that verification requests can be logged to.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RequestLogBackend {
+    /// Requests are saved to the Airtable table given by the `AIRTABLE_BASE_ID`,
+    /// `AIRTABLE_TABLE_ID`, and `AIRTABLE_PAT` environment variables. Requests aren't logged if
+    /// any of them is unset.
+    #[default]
+    Airtable,
+    /// Requests aren't logged.
+    None,
+}
+
+/// Settings for the pool of workers that verification jobs run on. Each job clones and builds a
+/// repository, so these bound the number of concurrent builds.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct SchedulerSettings {
+    /// The maximum number of verifications that run at once. Must be at least 1, since no
+    /// verification would ever run otherwise.
+    #[serde(deserialize_with = "deserialize_workers")]
+    pub workers: usize,
+    /// The maximum number of verifications waiting for a worker. Requests received while the queue
+    /// is full are rejected with 503 Service Unavailable.
+    pub queue_capacity: usize,
+    /// The number of seconds clients are told to wait before retrying a rejected request.
+    pub retry_after_secs: u64,
+}
+
+impl Default for SchedulerSettings {
+    fn default() -> Self {
+        Self { workers: 4, queue_capacity: 16, retry_after_secs: 30 }
+    }
+}
+
+/// Deserializes the number of scheduler workers, rejecting 0.
+fn deserialize_workers<'de, D>(deserializer: D) -> Result<usize, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    match usize::deserialize(deserializer)? {
+        0 => Err(serde::de::Error::custom("The scheduler must have at least 1 worker")),
+        workers => Ok(workers),
+    }
 }
 
 /// Based on the `APP_ENVIRONMENT` environment variable, reads the corresponding configuration file
@@ -72,3 +387,21 @@ impl TryFrom<String> for Environment {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scheduler_workers() {
+        let settings = |workers: usize| {
+            serde_json::from_value::<SchedulerSettings>(serde_json::json!({ "workers": workers }))
+        };
+        assert_eq!(settings(2).unwrap().workers, 2);
+        assert!(settings(0).unwrap_err().to_string().contains("at least 1 worker"));
+
+        // The number of workers defaults to 4 when unset.
+        let defaults: SchedulerSettings = serde_json::from_value(serde_json::json!({})).unwrap();
+        assert_eq!(defaults.workers, 4);
+    }
+}
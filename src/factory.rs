@@ -0,0 +1,458 @@
+use ethers::{
+    abi::{self, ParamType, Token},
+    types::{Address, Bytes, H256, U256},
+    utils::{get_create2_address, id},
+};
+
+/// A contract whose calldata can be decoded to extract the creation code of the contracts it
+/// deploys, either because it's a CREATE2 factory or because it batches calls to one. Supporting
+/// another deployer only requires adding it to `KNOWN_DEPLOYERS` with a decoder for its calldata.
+struct KnownDeployer {
+    /// The address of the deployer, which is the same on every chain.
+    address: &'static str,
+    /// The name of the deployer, used to describe where creation code came from.
+    name: &'static str,
+    /// Decodes the calldata of a call to the deployer, returning `None` for calls to functions
+    /// that don't deploy or batch calls, or if the calldata is malformed.
+    decode: fn(&[u8]) -> Option<DecodedCall>,
+}
+
+/// The known deployers.
+static KNOWN_DEPLOYERS: [KnownDeployer; 10] = [
+    // See https://github.com/Arachnid/deterministic-deployment-proxy.
+    KnownDeployer {
+        address: "0x4e59b44847b379578588920cA78FbF26c0B4956C",
+        name: "Arachnid deterministic deployment proxy",
+        decode: decode_arachnid_call,
+    },
+    KnownDeployer {
+        address: "0x0000000000FFe8B47B3e2130213B802212439497",
+        name: "0age create2 factory",
+        decode: decode_zero_age_call,
+    },
+    // See https://github.com/pcaversaccio/createx.
+    KnownDeployer {
+        address: "0xba5Ed099633D3B313e4D5F7bdc1305d3c28ba5Ed",
+        name: "CreateX",
+        decode: decode_createx_call,
+    },
+    // CreateCall v1.3.0 and v1.4.1.
+    KnownDeployer {
+        address: "0x7cbB62EaA69F79e6873cD1ecB2392971036cFAa4",
+        name: "Gnosis Safe CreateCall",
+        decode: decode_create_call,
+    },
+    KnownDeployer {
+        address: "0x9b35Af71d77eaf8d7e40252370304687390A1A52",
+        name: "Gnosis Safe CreateCall",
+        decode: decode_create_call,
+    },
+    // MultiSend and MultiSendCallOnly v1.3.0.
+    KnownDeployer {
+        address: "0xA238CBeb142c10Ef7Ad8442C6D1f9E89e07e7761",
+        name: "Gnosis Safe MultiSend",
+        decode: decode_multisend,
+    },
+    KnownDeployer {
+        address: "0x40A2aCCbd92BCA938b02010E17A5b8929b49130D",
+        name: "Gnosis Safe MultiSend",
+        decode: decode_multisend,
+    },
+    // MultiSend and MultiSendCallOnly v1.4.1.
+    KnownDeployer {
+        address: "0x38869bf66a61cF6bDB996A6aE40D5853Fd43B526",
+        name: "Gnosis Safe MultiSend",
+        decode: decode_multisend,
+    },
+    KnownDeployer {
+        address: "0x9641d764fc13c8B624c04430C7356C1C7C8102e2",
+        name: "Gnosis Safe MultiSend",
+        decode: decode_multisend,
+    },
+    KnownDeployer {
+        address: "0xcA11bde05977b3631167028862bE2a173976CA11",
+        name: "Multicall3",
+        decode: decode_multicall,
+    },
+];
+
+impl KnownDeployer {
+    /// Returns the known deployer at `address`, if any.
+    fn at(address: Address) -> Option<&'static Self> {
+        KNOWN_DEPLOYERS.iter().find(|known| known.address.parse::<Address>().ok() == Some(address))
+    }
+}
+
+/// The result of decoding a call to a known deployer.
+enum DecodedCall {
+    /// The deployer is a factory, and the call deploys a contract.
+    Deployment(Deployment),
+    /// The deployer batches calls, and the call makes each of these calls, given as the target
+    /// and calldata.
+    Batch(Vec<(Address, Bytes)>),
+}
+
+/// A deployment decoded from the calldata of a call to a factory.
+struct Deployment {
+    /// The salt passed to the factory, or `None` if the deployed address can't be derived from it,
+    /// e.g. because it deploys with CREATE.
+    salt: Option<H256>,
+    /// The creation code passed to the factory.
+    creation_code: Bytes,
+}
+
+/// Extracts the creation code of the contract at `address` from the calldata of a transaction
+/// sent to `to`, returning the creation code and a description of where it came from. If `to` is
+/// a known factory, the creation code it was called with is returned. If `to` batches calls, each
+/// call to a known factory is decoded, and the one whose CREATE2 address is `address` is used.
+/// Returns `None` if `to` is not a known deployer or no deployment of `address` is found.
+pub fn known_deployer_creation_code(
+    to: Address,
+    input: &[u8],
+    address: Address,
+) -> Option<(Bytes, String)> {
+    let deployer = KnownDeployer::at(to)?;
+    let calls = match (deployer.decode)(input)? {
+        DecodedCall::Deployment(deployment) => {
+            return Some((deployment.creation_code, deployer.name.to_string()))
+        }
+        DecodedCall::Batch(calls) => calls,
+    };
+
+    calls.into_iter().find_map(|(target, data)| {
+        let factory = KnownDeployer::at(target)?;
+        // Batches of batches aren't decoded.
+        let DecodedCall::Deployment(deployment) = (factory.decode)(&data)? else { return None };
+        // The address of a CREATE deployment depends on the factory's nonce, which isn't known
+        // from calldata, so only CREATE2 deployments can be matched to `address`.
+        if get_create2_address(target, deployment.salt?, &deployment.creation_code) != address {
+            return None
+        }
+        let source = format!("{} via {}", factory.name, deployer.name);
+        Some((deployment.creation_code, source))
+    })
+}
+
+/// Decodes a call to Arachnid's deterministic deployment proxy, whose calldata is a salt followed
+/// by the creation code.
+fn decode_arachnid_call(input: &[u8]) -> Option<DecodedCall> {
+    let salt = H256::from_slice(input.get(..32)?);
+    let creation_code = input[32..].to_vec().into();
+    Some(DecodedCall::Deployment(Deployment { salt: Some(salt), creation_code }))
+}
+
+/// Decodes a call to 0age's create2 factory, whose only function is
+/// `safeCreate2(bytes32 salt, bytes calldata initializationCode)`.
+fn decode_zero_age_call(input: &[u8]) -> Option<DecodedCall> {
+    let params = [ParamType::FixedBytes(32), ParamType::Bytes];
+    let mut args = decode_call(input, "safeCreate2(bytes32,bytes)", &params)?.into_iter();
+    let salt = H256::from_slice(&args.next()?.into_fixed_bytes()?);
+    let creation_code = args.next()?.into_bytes()?.into();
+    Some(DecodedCall::Deployment(Deployment { salt: Some(salt), creation_code }))
+}
+
+/// Decodes a call to Gnosis Safe's `CreateCall`, whose `performCreate(uint256 value, bytes
+/// deploymentData)` and `performCreate2(uint256 value, bytes deploymentData, bytes32 salt)` forward
+/// `value` to the constructor of the deployed contract. The value doesn't affect the creation code
+/// or the deployed address. Arguments are ABI decoded rather than sliced at fixed offsets, so the
+/// value preceding the creation code doesn't shift where it's read from.
+fn decode_create_call(input: &[u8]) -> Option<DecodedCall> {
+    let create2 = [ParamType::Uint(256), ParamType::Bytes, ParamType::FixedBytes(32)];
+    if let Some(args) = decode_call(input, "performCreate2(uint256,bytes,bytes32)", &create2) {
+        let mut args = args.into_iter().skip(1);
+        let creation_code = args.next()?.into_bytes()?.into();
+        let salt = H256::from_slice(&args.next()?.into_fixed_bytes()?);
+        return Some(DecodedCall::Deployment(Deployment { salt: Some(salt), creation_code }))
+    }
+    let create = [ParamType::Uint(256), ParamType::Bytes];
+    let args = decode_call(input, "performCreate(uint256,bytes)", &create)?;
+    let creation_code = args.into_iter().nth(1)?.into_bytes()?.into();
+    Some(DecodedCall::Deployment(Deployment { salt: None, creation_code }))
+}
+
+/// Decodes a call to one of CreateX's `deployCreate*` functions, which deploy with CREATE, CREATE2,
+/// or CREATE3. The creation code is the first `bytes` argument of each of them, preceded by the
+/// salt for CREATE2 and CREATE3 deployments. The `AndInit` variants also take calldata for an
+/// initialization call, the values sent with the deployment and that call, and optionally a refund
+/// address. CreateX guards salts by hashing them with the sender or chain ID, depending on flags
+/// in the salt, and CREATE3 deploys through an intermediate proxy, so the deployed address can't
+/// be derived from the calldata salt.
+fn decode_createx_call(input: &[u8]) -> Option<DecodedCall> {
+    let salt = ParamType::FixedBytes(32);
+    let init = [
+        ParamType::Bytes,
+        ParamType::Bytes,
+        ParamType::Tuple(vec![ParamType::Uint(256), ParamType::Uint(256)]),
+    ];
+    let init_with_refund = [&init[..], &[ParamType::Address]].concat();
+
+    let mut functions = vec![
+        ("deployCreate(bytes)".to_string(), vec![ParamType::Bytes]),
+        ("deployCreateAndInit(bytes,bytes,(uint256,uint256))".to_string(), init.to_vec()),
+        (
+            "deployCreateAndInit(bytes,bytes,(uint256,uint256),address)".to_string(),
+            init_with_refund.clone(),
+        ),
+    ];
+    for kind in ["Create2", "Create3"] {
+        for (prefix, salt) in [("bytes32,", vec![salt.clone()]), ("", vec![])] {
+            functions.extend([
+                (format!("deploy{kind}({prefix}bytes)"), [&salt[..], &[ParamType::Bytes]].concat()),
+                (
+                    format!("deploy{kind}AndInit({prefix}bytes,bytes,(uint256,uint256))"),
+                    [&salt[..], &init[..]].concat(),
+                ),
+                (
+                    format!("deploy{kind}AndInit({prefix}bytes,bytes,(uint256,uint256),address)"),
+                    [&salt[..], &init_with_refund[..]].concat(),
+                ),
+            ]);
+        }
+    }
+
+    functions.into_iter().find_map(|(signature, params)| {
+        let args = decode_call(input, &signature, &params)?;
+        let creation_code = args.into_iter().find_map(|arg| arg.into_bytes())?.into();
+        Some(DecodedCall::Deployment(Deployment { salt: None, creation_code }))
+    })
+}
+
+/// Decodes the arguments of a call to the function with the given signature, returning `None` if
+/// the calldata is for a different function or is malformed.
+fn decode_call(input: &[u8], signature: &str, params: &[ParamType]) -> Option<Vec<Token>> {
+    if input.get(..4)? != &id(signature)[..] {
+        return None
+    }
+    abi::decode(params, &input[4..]).ok()
+}
+
+/// Decodes a call to Gnosis Safe's `MultiSend` or `MultiSendCallOnly`, returning the target and
+/// calldata of each call batched by `multiSend(bytes transactions)`. Each transaction is packed as
+/// a 1 byte operation, 20 byte target, 32 byte value, 32 byte data length, and the data. Delegate
+/// calls run the target's code in the context of the `MultiSend` contract rather than the target,
+/// so they can't be a factory deployment and are skipped.
+fn decode_multisend(input: &[u8]) -> Option<DecodedCall> {
+    let transactions = decode_call(input, "multiSend(bytes)", &[ParamType::Bytes])?;
+    let packed = transactions.into_iter().next()?.into_bytes()?;
+
+    let mut calls = Vec::new();
+    let mut offset = 0;
+    while offset < packed.len() {
+        let operation = *packed.get(offset)?;
+        let target = Address::from_slice(packed.get(offset + 1..offset + 21)?);
+        let len = U256::from_big_endian(packed.get(offset + 53..offset + 85)?);
+        if len > U256::from(packed.len()) {
+            return None
+        }
+        let data_end = offset + 85 + len.as_usize();
+        let data = packed.get(offset + 85..data_end)?;
+        if operation == 0 {
+            calls.push((target, data.to_vec().into()));
+        }
+        offset = data_end;
+    }
+    Some(DecodedCall::Batch(calls))
+}
+
+/// Decodes a call to Multicall3, returning the target and calldata of each call batched by
+/// `aggregate`, `aggregate3`, or `aggregate3Value`.
+fn decode_multicall(input: &[u8]) -> Option<DecodedCall> {
+    let selector = input.get(..4)?;
+    let call_fields = [
+        ("aggregate((address,bytes)[])", vec![ParamType::Address, ParamType::Bytes]),
+        (
+            "aggregate3((address,bool,bytes)[])",
+            vec![ParamType::Address, ParamType::Bool, ParamType::Bytes],
+        ),
+        (
+            "aggregate3Value((address,bool,uint256,bytes)[])",
+            vec![ParamType::Address, ParamType::Bool, ParamType::Uint(256), ParamType::Bytes],
+        ),
+    ]
+    .into_iter()
+    .find(|(signature, _)| selector == &id(signature)[..])
+    .map(|(_, fields)| fields)?;
+
+    let call_type = ParamType::Array(Box::new(ParamType::Tuple(call_fields)));
+    let tokens = abi::decode(&[call_type], &input[4..]).ok()?;
+    let Token::Array(calls) = tokens.into_iter().next()? else { return None };
+    let calls = calls
+        .into_iter()
+        .map(|call| {
+            // Every call starts with the target and ends with the calldata.
+            let Token::Tuple(fields) = call else { return None };
+            let target = fields.first()?.clone().into_address()?;
+            let data = fields.last()?.clone().into_bytes()?;
+            Some((target, data.into()))
+        })
+        .collect::<Option<_>>()?;
+    Some(DecodedCall::Batch(calls))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    /// Returns calldata for Arachnid's deterministic deployment proxy and the address it deploys.
+    fn arachnid_call(salt: u8, creation_code: &[u8]) -> (Address, Vec<u8>, Address) {
+        let factory = Address::from_str(KNOWN_DEPLOYERS[0].address).unwrap();
+        let salt = H256::repeat_byte(salt);
+        let calldata = [salt.as_bytes(), creation_code].concat();
+        (factory, calldata, get_create2_address(factory, salt, creation_code))
+    }
+
+    #[test]
+    fn test_known_factory_creation_code() {
+        let (factory, calldata, address) = arachnid_call(1, &[0x60, 0x80]);
+        let (creation_code, source) =
+            known_deployer_creation_code(factory, &calldata, address).unwrap();
+        assert_eq!(creation_code, Bytes::from(vec![0x60, 0x80]));
+        assert_eq!(source, "Arachnid deterministic deployment proxy");
+
+        // Unknown contracts can't be decoded.
+        assert!(known_deployer_creation_code(Address::zero(), &calldata, address).is_none());
+    }
+
+    #[test]
+    fn test_payable_constructor_creation_code() {
+        // A contract with a payable constructor deployed through CreateCall with 1 ETH.
+        let create_call = Address::from_str("0x9b35Af71d77eaf8d7e40252370304687390A1A52").unwrap();
+        let creation_code = vec![0x60, 0x80, 0x60, 0x40, 0x52];
+        let value = U256::exp10(18);
+        let salt = H256::repeat_byte(7);
+        let args = [
+            Token::Uint(value),
+            Token::Bytes(creation_code.clone()),
+            Token::FixedBytes(salt.as_bytes().to_vec()),
+        ];
+        let input =
+            [&id("performCreate2(uint256,bytes,bytes32)")[..], &abi::encode(&args)].concat();
+        let address = get_create2_address(create_call, salt, &creation_code);
+        let (found, source) = known_deployer_creation_code(create_call, &input, address).unwrap();
+        assert_eq!(found, Bytes::from(creation_code.clone()));
+        assert_eq!(source, "Gnosis Safe CreateCall");
+
+        // The same deployment batched through MultiSend, forwarding the value to CreateCall.
+        let mut packed = vec![0];
+        packed.extend_from_slice(create_call.as_bytes());
+        let mut encoded_value = [0; 32];
+        value.to_big_endian(&mut encoded_value);
+        packed.extend_from_slice(&encoded_value);
+        packed.extend_from_slice(&H256::from_low_u64_be(input.len() as u64).0);
+        packed.extend_from_slice(&input);
+        let batch = [&id("multiSend(bytes)")[..], &abi::encode(&[Token::Bytes(packed)])].concat();
+        let multisend = Address::from_str("0x38869bf66a61cF6bDB996A6aE40D5853Fd43B526").unwrap();
+        let (found, source) = known_deployer_creation_code(multisend, &batch, address).unwrap();
+        assert_eq!(found, Bytes::from(creation_code.clone()));
+        assert_eq!(source, "Gnosis Safe CreateCall via Gnosis Safe MultiSend");
+
+        // CREATE deployments are found when sent directly to CreateCall.
+        let args = [Token::Uint(value), Token::Bytes(creation_code.clone())];
+        let input = [&id("performCreate(uint256,bytes)")[..], &abi::encode(&args)].concat();
+        let (found, _) = known_deployer_creation_code(create_call, &input, address).unwrap();
+        assert_eq!(found, Bytes::from(creation_code));
+    }
+
+    #[test]
+    fn test_known_deployers() {
+        // Every deployer's address is valid and registered once.
+        let mut addresses = KNOWN_DEPLOYERS
+            .iter()
+            .map(|deployer| Address::from_str(deployer.address).unwrap())
+            .collect::<Vec<_>>();
+        addresses.sort();
+        addresses.dedup();
+        assert_eq!(addresses.len(), KNOWN_DEPLOYERS.len());
+
+        let createx = Address::from_str("0xba5Ed099633D3B313e4D5F7bdc1305d3c28ba5Ed").unwrap();
+        assert_eq!(KnownDeployer::at(createx).map(|deployer| deployer.name), Some("CreateX"));
+        assert!(KnownDeployer::at(Address::zero()).is_none());
+    }
+
+    #[test]
+    fn test_createx_creation_code() {
+        let createx = Address::from_str("0xba5Ed099633D3B313e4D5F7bdc1305d3c28ba5Ed").unwrap();
+        let creation_code = Bytes::from_str("0x6080604052348015600f57600080fd5b50").unwrap();
+
+        // The complete calldata of transactions sent to CreateX, whose salt is guarded by the
+        // sender's address.
+        #[rustfmt::skip]
+        let calldata = [
+            // deployCreate2(bytes32,bytes)
+            "0x26307668ba5ed099633d3b313e4d5f7bdc1305d3c28ba5ed00b2e6b3fa3fb01dd8d1b1a5000000000000000000000000000000000000000000000000000000000000004000000000000000000000000000000000000000000000000000000000000000116080604052348015600f57600080fd5b50000000000000000000000000000000",
+            // deployCreate3(bytes32,bytes)
+            "0x9c36a286ba5ed099633d3b313e4d5f7bdc1305d3c28ba5ed00b2e6b3fa3fb01dd8d1b1a5000000000000000000000000000000000000000000000000000000000000004000000000000000000000000000000000000000000000000000000000000000116080604052348015600f57600080fd5b50000000000000000000000000000000",
+            // deployCreate2(bytes), which derives the salt from the block and sender.
+            "0x26a32fc7000000000000000000000000000000000000000000000000000000000000002000000000000000000000000000000000000000000000000000000000000000116080604052348015600f57600080fd5b50000000000000000000000000000000",
+            // deployCreate(bytes)
+            "0x27fe1822000000000000000000000000000000000000000000000000000000000000002000000000000000000000000000000000000000000000000000000000000000116080604052348015600f57600080fd5b50000000000000000000000000000000",
+            // deployCreate2AndInit(bytes32,bytes,bytes,(uint256,uint256),address), calling
+            // `initialize()` and refunding 0x1111111111111111111111111111111111111111.
+            "0xa7db93f2ba5ed099633d3b313e4d5f7bdc1305d3c28ba5ed00b2e6b3fa3fb01dd8d1b1a500000000000000000000000000000000000000000000000000000000000000c0000000000000000000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000111111111111111111111111111111111111111100000000000000000000000000000000000000000000000000000000000000116080604052348015600f57600080fd5b5000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000048129fc1c00000000000000000000000000000000000000000000000000000000",
+        ];
+        for input in calldata {
+            let input = Bytes::from_str(input).unwrap();
+            let (found, source) =
+                known_deployer_creation_code(createx, &input, Address::zero()).unwrap();
+            assert_eq!(found, creation_code);
+            assert_eq!(source, "CreateX");
+        }
+
+        // Other CreateX functions, such as computeCreate2Address(bytes32,bytes32), can't be
+        // decoded.
+        let input = concat!(
+            "0x890c283b",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "0000000000000000000000000000000000000000000000000000000000000002",
+        );
+        let input = Bytes::from_str(input).unwrap();
+        assert!(known_deployer_creation_code(createx, &input, Address::zero()).is_none());
+    }
+
+    #[test]
+    fn test_multisend_creation_code() {
+        let (factory, first_calldata, _) = arachnid_call(1, &[0x60, 0x80]);
+        let (_, second_calldata, address) = arachnid_call(2, &[0x60, 0x40]);
+
+        // Pack a delegate call and two calls to the factory, where only the last deploys `address`.
+        let mut packed = Vec::new();
+        for (operation, calldata) in
+            [(1u8, &second_calldata), (0, &first_calldata), (0, &second_calldata)]
+        {
+            packed.push(operation);
+            packed.extend_from_slice(factory.as_bytes());
+            packed.extend_from_slice(&[0; 32]);
+            packed.extend_from_slice(&H256::from_low_u64_be(calldata.len() as u64).0);
+            packed.extend_from_slice(calldata);
+        }
+        let input = [&id("multiSend(bytes)")[..], &abi::encode(&[Token::Bytes(packed)])].concat();
+
+        let multisend = Address::from_str("0x40A2aCCbd92BCA938b02010E17A5b8929b49130D").unwrap();
+        let (creation_code, source) =
+            known_deployer_creation_code(multisend, &input, address).unwrap();
+        assert_eq!(creation_code, Bytes::from(vec![0x60, 0x40]));
+        assert_eq!(source, "Arachnid deterministic deployment proxy via Gnosis Safe MultiSend");
+
+        // No call deploys a different address.
+        assert!(known_deployer_creation_code(multisend, &input, Address::zero()).is_none());
+    }
+
+    #[test]
+    fn test_multicall_creation_code() {
+        let (factory, calldata, address) = arachnid_call(1, &[0x60, 0x80]);
+        let call =
+            Token::Tuple(vec![Token::Address(factory), Token::Bool(false), Token::Bytes(calldata)]);
+        let input = [
+            &id("aggregate3((address,bool,bytes)[])")[..],
+            &abi::encode(&[Token::Array(vec![call])]),
+        ]
+        .concat();
+
+        let multicall = Address::from_str("0xcA11bde05977b3631167028862bE2a173976CA11").unwrap();
+        let (creation_code, source) =
+            known_deployer_creation_code(multicall, &input, address).unwrap();
+        assert_eq!(creation_code, Bytes::from(vec![0x60, 0x80]));
+        assert_eq!(source, "Arachnid deterministic deployment proxy via Multicall3");
+    }
+}
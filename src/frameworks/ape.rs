@@ -0,0 +1,557 @@
+use super::framework::{ArtifactSources, BuildCommand, BuildVariant, Framework};
+
+use crate::bytecode::{
+    structure_expected_creation_code, structure_expected_deployed_code,
+    structure_found_creation_code, structure_found_deployed_code, ExpectedCreationBytecode,
+    ExpectedDeployedBytecode, FoundCreationBytecode, FoundDeployedBytecode, ImmutableReferences,
+    LinkReferences,
+};
+use ethers::{
+    types::{Bytes, H256},
+    utils::keccak256,
+};
+use ethers_solc::{
+    artifacts::{BytecodeHash, LosslessAbi, SettingsMetadata},
+    ConfigurableContractArtifact,
+};
+use serde_json::{json, Value};
+use std::{
+    collections::BTreeSet,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    result::Result,
+    str::FromStr,
+};
+
+/// The name of the config file that identifies an Ape project.
+const CONFIG_FILE: &str = "ape-config.yaml";
+
+/// The name of the manifest, in Ape's `.build` directory, that compiled contracts are written to.
+const MANIFEST_FILE: &str = "__local__.json";
+
+/// The directory, in Ape's `.build` directory, that each contract type is extracted to.
+const CONTRACT_TYPES_DIR: &str = "cove_contract_types";
+
+/// The prefix of the source IDs of dependencies, which Ape copies into the contracts folder's
+/// `.cache` directory to compile them alongside the project.
+const DEPENDENCY_SOURCE_PREFIX: &str = ".cache/";
+
+/// Implementation of the `Framework` trait for Ape projects.
+///
+/// Ape writes an ethPM v3 manifest to `.build/__local__.json` which contains every compiled
+/// contract type, keyed by contract name, along with the project's sources and the settings of each
+/// compiler. Ape compiles both Solidity and Vyper, and records each compiler's output the same way.
+/// Since artifacts are addressed by path, each contract type is extracted to
+/// `.build/cove_contract_types/<contract>.json`, and the manifest is read for its compiler and
+/// sources. Contract types don't record immutable or link references, so only contracts without
+/// immutables or linked libraries can match on deployed code.
+pub struct Ape {
+    /// Path to the project.
+    path: PathBuf,
+}
+
+impl Ape {
+    /// Returns the manifest that the given extracted contract type was compiled into.
+    fn manifest(artifact: &Path) -> Result<Value, Box<dyn Error>> {
+        let build_dir = artifact.parent().and_then(Path::parent).ok_or_else(|| {
+            format!("Artifact is not in a build directory: {}", artifact.display())
+        })?;
+        Ok(serde_json::from_str(&fs::read_to_string(build_dir.join(MANIFEST_FILE))?)?)
+    }
+
+    /// Returns the contract type of the given artifact.
+    fn contract_type(artifact: &Path) -> Result<Value, Box<dyn Error>> {
+        Ok(serde_json::from_str(&fs::read_to_string(artifact)?)?)
+    }
+
+    /// Returns the manifest's entry for the compiler that compiled the given contract, which
+    /// contains the compiler's name, version, and settings.
+    fn compiler(manifest: &Value, contract_name: &str) -> Result<Value, Box<dyn Error>> {
+        manifest["compilers"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|compiler| {
+                compiler["contractTypes"]
+                    .as_array()
+                    .map_or(false, |names| names.iter().any(|name| name == contract_name))
+            })
+            .cloned()
+            .ok_or_else(|| format!("No compiler found for contract {contract_name}").into())
+    }
+
+    /// Returns the name of the contract of the given contract type.
+    fn contract_name(contract_type: &Value, artifact: &Path) -> Result<String, Box<dyn Error>> {
+        let name = contract_type["contractName"].as_str().ok_or_else(|| {
+            format!("Missing 'contractName' field in contract type: {}", artifact.display())
+        })?;
+        Ok(name.to_string())
+    }
+
+    /// Parses the `0x` prefixed hex encoded code of an ethPM bytecode object. Code with unlinked
+    /// library placeholders isn't valid hex, so contracts that use external libraries can't be
+    /// compared.
+    fn parse_code(bytecode: &Value, artifact: &Path) -> Result<Bytes, Box<dyn Error>> {
+        let code = bytecode["bytecode"]
+            .as_str()
+            .ok_or_else(|| format!("Missing 'bytecode' field for {}", artifact.display()))?;
+        Bytes::from_str(code).map_err(|_| "Linked libraries not yet supported".into())
+    }
+
+    /// Returns the ID of the given source and of every source it imports, directly or indirectly,
+    /// according to the manifest. Sources without recorded imports only include themselves.
+    fn source_ids(manifest: &Value, source_id: &str) -> BTreeSet<String> {
+        let mut source_ids = BTreeSet::new();
+        let mut pending = vec![source_id.to_string()];
+        while let Some(id) = pending.pop() {
+            if manifest["sources"].get(&id).is_none() || !source_ids.insert(id.clone()) {
+                continue
+            }
+            let imports = manifest["sources"][&id]["imports"].as_array().into_iter().flatten();
+            pending.extend(imports.filter_map(|import| import.as_str().map(str::to_string)));
+        }
+        source_ids
+    }
+
+    /// Returns the compiler metadata of the given contract in the format of solc's metadata, which
+    /// is what forge artifacts contain. Solc settings are returned as configured, while Vyper has
+    /// no optimizer settings in the same format, so only its compilation target is returned.
+    fn metadata(
+        manifest: &Value,
+        contract_type: &Value,
+        contract_name: &str,
+    ) -> Result<Value, Box<dyn Error>> {
+        let compiler = Self::compiler(manifest, contract_name)?;
+        let source_id = contract_type["sourceId"].as_str().unwrap_or_default();
+
+        let mut settings = compiler["settings"].clone();
+        if !settings.is_object() {
+            settings = json!({});
+        }
+        if settings.get("optimizer").is_none() {
+            settings["optimizer"] = json!({});
+        }
+        settings["compilationTarget"] = json!({ source_id: contract_name });
+
+        // Metadata identifies sources by the keccak256 hash of their content.
+        let sources: serde_json::Map<String, Value> = Self::source_ids(manifest, source_id)
+            .into_iter()
+            .filter_map(|id| {
+                let content = manifest["sources"][&id]["content"].as_str()?;
+                let hash = format!("{:?}", H256::from(keccak256(content.as_bytes())));
+                Some((id, json!({ "keccak256": hash, "urls": [] })))
+            })
+            .collect();
+
+        let language = match compiler["name"].as_str().unwrap_or_default() {
+            name if name.eq_ignore_ascii_case("vyper") => "Vyper",
+            _ => "Solidity",
+        };
+        Ok(json!({
+            "compiler": { "version": compiler["version"] },
+            "language": language,
+            "output": {
+                "abi": contract_type["abi"],
+                "devdoc": contract_type.get("devdoc"),
+                "userdoc": contract_type.get("userdoc"),
+            },
+            "settings": settings,
+            "sources": sources,
+            "version": 1,
+        }))
+    }
+}
+
+impl Framework for Ape {
+    fn new(path: &Path) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        if !Self::is_supported(path) {
+            return Err("Not an ape project.".into())
+        }
+        Ok(Self { path: path.to_path_buf() })
+    }
+
+    fn is_supported(path: &Path) -> bool {
+        path.join(CONFIG_FILE).is_file()
+    }
+
+    fn build_commands(
+        &self,
+        _hint: Option<String>,
+        toggle_optimizer: bool,
+    ) -> Result<Vec<BuildCommand>, Box<dyn Error>> {
+        // Ape always writes to the same manifest, so builds with different settings would overwrite
+        // each other's output.
+        if toggle_optimizer {
+            return Err("Toggling the optimizer is not supported for ape projects.".into())
+        }
+
+        // Ape has no build profiles, so there is a single build with the project's config. The
+        // build is forced so contracts are never skipped because of a cached manifest.
+        let mut command = Command::new("ape");
+        command.current_dir(&self.path).arg("compile").arg("--force");
+        let variant = BuildVariant { profile: "default".to_string(), optimizer_override: None };
+        Ok(vec![BuildCommand { command, variant, out_dir: self.path.join(".build") }])
+    }
+
+    fn get_artifacts(
+        &self,
+        _variant: &BuildVariant,
+        out_dir: &Path,
+    ) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        let manifest_path = out_dir.join(MANIFEST_FILE);
+        let manifest: Value = serde_json::from_str(&fs::read_to_string(&manifest_path)?)?;
+        let contract_types = manifest["contractTypes"].as_object().ok_or_else(|| {
+            format!("Missing 'contractTypes' field in manifest {}", manifest_path.display())
+        })?;
+
+        // Dependencies and contracts without code, e.g. interfaces, can't be the most-derived
+        // contract the user is interested in, so they aren't extracted.
+        let contract_types_dir = out_dir.join(CONTRACT_TYPES_DIR);
+        fs::create_dir_all(&contract_types_dir)?;
+        let mut artifacts = Vec::new();
+        for (name, contract_type) in contract_types {
+            let source_id = contract_type["sourceId"].as_str().unwrap_or_default();
+            let code = contract_type["deploymentBytecode"]["bytecode"].as_str().unwrap_or_default();
+            if source_id.starts_with(DEPENDENCY_SOURCE_PREFIX)
+                || code.trim_start_matches("0x").is_empty()
+            {
+                continue
+            }
+            let artifact = contract_types_dir.join(format!("{name}.json"));
+            fs::write(&artifact, contract_type.to_string())?;
+            artifacts.push(artifact);
+        }
+        Ok(artifacts)
+    }
+
+    fn structure_found_creation_code(
+        &self,
+        artifact: &Path,
+    ) -> Result<FoundCreationBytecode, Box<dyn Error>> {
+        let metadata_settings = Self::get_artifact_metadata_settings(artifact)?;
+        let raw_code = Self::get_artifact_creation_code(artifact)?;
+        Ok(structure_found_creation_code(raw_code, &metadata_settings))
+    }
+
+    fn structure_expected_creation_code(
+        &self,
+        _artifact: &Path,
+        found: &FoundCreationBytecode,
+        expected: &Bytes,
+    ) -> Result<ExpectedCreationBytecode, Box<dyn Error>> {
+        structure_expected_creation_code(found, expected)
+    }
+
+    fn structure_found_deployed_code(
+        &self,
+        artifact: &Path,
+    ) -> Result<FoundDeployedBytecode, Box<dyn Error>> {
+        let metadata_settings = Self::get_artifact_metadata_settings(artifact)?;
+        let (raw_code, immutable_references, link_references) =
+            Self::get_artifact_deployed_code(artifact)?;
+        Ok(structure_found_deployed_code(
+            raw_code,
+            immutable_references,
+            link_references,
+            &metadata_settings,
+        ))
+    }
+
+    fn structure_expected_deployed_code(
+        &self,
+        found: &FoundDeployedBytecode,
+        expected: &Bytes,
+    ) -> Result<ExpectedDeployedBytecode, Box<dyn Error>> {
+        structure_expected_deployed_code(found, expected)
+    }
+
+    fn get_artifact_abi(artifact: &Path) -> Result<LosslessAbi, Box<dyn Error>> {
+        let contract_type = Self::contract_type(artifact)?;
+        let abi_value = contract_type.get("abi").ok_or_else(|| {
+            format!("Missing 'abi' field in contract type: {}", artifact.display())
+        })?;
+        Ok(serde_json::from_value(abi_value.clone())?)
+    }
+
+    fn get_artifact_creation_code(artifact: &Path) -> Result<Bytes, Box<dyn Error>> {
+        let contract_type = Self::contract_type(artifact)?;
+        Self::parse_code(&contract_type["deploymentBytecode"], artifact)
+    }
+
+    fn get_artifact_deployed_code(
+        artifact: &Path,
+    ) -> Result<(Bytes, ImmutableReferences, LinkReferences), Box<dyn Error>> {
+        let contract_type = Self::contract_type(artifact)?;
+        let deployed_code = Self::parse_code(&contract_type["runtimeBytecode"], artifact)?;
+        Ok((deployed_code, ImmutableReferences::new(), LinkReferences::new()))
+    }
+
+    fn get_artifact_metadata_settings(artifact: &Path) -> Result<SettingsMetadata, Box<dyn Error>> {
+        let contract_type = Self::contract_type(artifact)?;
+        let contract_name = Self::contract_name(&contract_type, artifact)?;
+        let compiler = Self::compiler(&Self::manifest(artifact)?, &contract_name)?;
+
+        // Vyper's metadata isn't a solc metadata hash, so its entire code is compared exactly. Solc
+        // appends a CBOR-encoded IPFS hash unless the settings say otherwise.
+        if !compiler["name"].as_str().map_or(false, |name| name.eq_ignore_ascii_case("solidity")) {
+            return Ok(SettingsMetadata::new(BytecodeHash::None, false))
+        }
+        let Some(settings) = compiler["settings"].get("metadata") else {
+            return Ok(SettingsMetadata::new(BytecodeHash::Ipfs, true))
+        };
+        let settings: SettingsMetadata = serde_json::from_value(settings.clone())?;
+        Ok(SettingsMetadata::new(
+            settings.bytecode_hash.unwrap_or(BytecodeHash::Ipfs),
+            settings.cbor_metadata.unwrap_or(true),
+        ))
+    }
+
+    fn get_verified_artifact(
+        &self,
+        artifact: &Path,
+    ) -> Result<ConfigurableContractArtifact, Box<dyn Error>> {
+        let contract_type = Self::contract_type(artifact)?;
+        let contract_name = Self::contract_name(&contract_type, artifact)?;
+        let metadata = Self::metadata(&Self::manifest(artifact)?, &contract_type, &contract_name)?;
+        let forge_artifact = json!({
+            "abi": contract_type["abi"],
+            "bytecode": { "object": contract_type["deploymentBytecode"]["bytecode"] },
+            "deployedBytecode": { "object": contract_type["runtimeBytecode"]["bytecode"] },
+            "metadata": metadata,
+        });
+        Ok(serde_json::from_value(forge_artifact)?)
+    }
+
+    fn get_artifact_sources(&self, artifact: &Path) -> Result<ArtifactSources, Box<dyn Error>> {
+        let manifest = Self::manifest(artifact)?;
+        let contents = manifest["sources"]
+            .as_object()
+            .into_iter()
+            .flatten()
+            .filter_map(|(id, source)| {
+                Some((PathBuf::from(id), source["content"].as_str()?.into()))
+            })
+            .collect();
+        Ok(ArtifactSources { contents, standard_json_input: None })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bytecode::MetadataInfo;
+    use tempfile::TempDir;
+
+    /// Writes an Ape project whose `.build/__local__.json` manifest contains a Solidity `Counter`
+    /// compiled with the given settings, a Solidity interface, a dependency, and a Vyper `Token`.
+    /// Returns the project.
+    fn create_test_project(solc_settings: Value) -> Result<TempDir, Box<dyn Error>> {
+        let project = TempDir::new()?;
+        fs::write(project.path().join(CONFIG_FILE), "name: counter\n")?;
+        let manifest = json!({
+            "manifest": "ethpm/3",
+            "sources": {
+                "Counter.sol": {
+                    "checksum": { "algorithm": "md5", "hash": "0x1b2c" },
+                    "content": "import \"./ICounter.sol\";\ncontract Counter is ICounter {}",
+                    "imports": ["ICounter.sol"],
+                },
+                "ICounter.sol": { "content": "interface ICounter {}" },
+                "Token.vy": { "content": "# @version 0.3.9\n" },
+                ".cache/openzeppelin/4.9.0/access/Ownable.sol": { "content": "contract Ownable {}" },
+            },
+            "contractTypes": {
+                "Counter": {
+                    "contractName": "Counter",
+                    "sourceId": "Counter.sol",
+                    "deploymentBytecode": { "bytecode": "0x1234a164736f6c6343000706000a" },
+                    "runtimeBytecode": { "bytecode": "0x5678a164736f6c6343000706000a" },
+                    "abi": [
+                        { "type": "constructor", "stateMutability": "nonpayable", "inputs": [ { "name": "initialNumber", "type": "uint256", "internalType": "uint256" } ] },
+                        { "type": "function", "name": "increment", "stateMutability": "nonpayable", "inputs": [], "outputs": [] },
+                    ],
+                    "userdoc": { "kind": "user", "methods": {}, "version": 1 },
+                    "devdoc": { "kind": "dev", "methods": {}, "version": 1 },
+                },
+                "ICounter": {
+                    "contractName": "ICounter",
+                    "sourceId": "ICounter.sol",
+                    "deploymentBytecode": { "bytecode": "0x" },
+                    "runtimeBytecode": { "bytecode": "0x" },
+                    "abi": [],
+                },
+                "Ownable": {
+                    "contractName": "Ownable",
+                    "sourceId": ".cache/openzeppelin/4.9.0/access/Ownable.sol",
+                    "deploymentBytecode": { "bytecode": "0x6080" },
+                    "runtimeBytecode": { "bytecode": "0x6080" },
+                    "abi": [],
+                },
+                "Token": {
+                    "contractName": "Token",
+                    "sourceId": "Token.vy",
+                    "deploymentBytecode": { "bytecode": "0x61000f61001161000039" },
+                    "runtimeBytecode": { "bytecode": "0x6003361161000c57" },
+                    "abi": [ { "type": "function", "name": "totalSupply", "stateMutability": "view", "inputs": [], "outputs": [ { "name": "", "type": "uint256" } ] } ],
+                },
+            },
+            "compilers": [
+                {
+                    "name": "solidity",
+                    "version": "0.8.19+commit.7dd6d404",
+                    "settings": solc_settings,
+                    "contractTypes": ["Counter", "ICounter", "Ownable"],
+                },
+                {
+                    "name": "vyper",
+                    "version": "0.3.9+commit.66b96705",
+                    "settings": { "optimize": true },
+                    "contractTypes": ["Token"],
+                },
+            ],
+        });
+        fs::create_dir_all(project.path().join(".build"))?;
+        fs::write(project.path().join(".build").join(MANIFEST_FILE), manifest.to_string())?;
+        Ok(project)
+    }
+
+    /// Returns the settings Ape-Solidity compiles with by default.
+    fn default_solc_settings() -> Value {
+        json!({ "optimizer": { "enabled": true, "runs": 200 }, "evmVersion": "paris" })
+    }
+
+    /// Extracts the artifacts of the test project and returns the path of the named artifact.
+    fn artifact(project: &TempDir, name: &str) -> Result<PathBuf, Box<dyn Error>> {
+        let ape = Ape::new(project.path()).unwrap();
+        let variant = BuildVariant { profile: "default".to_string(), optimizer_override: None };
+        ape.get_artifacts(&variant, &project.path().join(".build"))?;
+        Ok(project.path().join(".build").join(CONTRACT_TYPES_DIR).join(format!("{name}.json")))
+    }
+
+    #[test]
+    fn test_is_supported() -> Result<(), Box<dyn Error>> {
+        let project = TempDir::new()?;
+        assert!(!Ape::is_supported(project.path()));
+        fs::write(project.path().join(CONFIG_FILE), "")?;
+        assert!(Ape::is_supported(project.path()));
+
+        // A directory with the config file's name isn't a config file.
+        let project = TempDir::new()?;
+        fs::create_dir(project.path().join(CONFIG_FILE))?;
+        assert!(!Ape::is_supported(project.path()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_commands() -> Result<(), Box<dyn Error>> {
+        let project = create_test_project(default_solc_settings())?;
+        let ape = Ape::new(project.path()).unwrap();
+
+        let commands = ape.build_commands(None, false)?;
+        assert_eq!(commands.len(), 1);
+        assert_eq!(commands[0].out_dir, project.path().join(".build"));
+        assert!(ape.build_commands(None, true).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_artifacts() -> Result<(), Box<dyn Error>> {
+        let project = create_test_project(default_solc_settings())?;
+        let ape = Ape::new(project.path()).unwrap();
+
+        // Interfaces have no code, and dependencies are compiled from the `.cache` directory.
+        let variant = BuildVariant { profile: "default".to_string(), optimizer_override: None };
+        let mut artifacts = ape.get_artifacts(&variant, &project.path().join(".build"))?;
+        artifacts.sort();
+        let contract_types_dir = project.path().join(".build").join(CONTRACT_TYPES_DIR);
+        let expected =
+            vec![contract_types_dir.join("Counter.json"), contract_types_dir.join("Token.json")];
+        assert_eq!(artifacts, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_artifact_code() -> Result<(), Box<dyn Error>> {
+        let project = create_test_project(default_solc_settings())?;
+        let counter = artifact(&project, "Counter")?;
+
+        let abi = Ape::get_artifact_abi(&counter)?;
+        assert_eq!(abi.abi.functions.len(), 1);
+        assert!(abi.abi.constructor.is_some());
+        let creation_code = Ape::get_artifact_creation_code(&counter)?;
+        assert_eq!(creation_code, Bytes::from_str("0x1234a164736f6c6343000706000a")?);
+        let (deployed_code, immutable_references, link_references) =
+            Ape::get_artifact_deployed_code(&counter)?;
+        assert_eq!(deployed_code, Bytes::from_str("0x5678a164736f6c6343000706000a")?);
+        assert!(immutable_references.is_empty() && link_references.is_empty());
+
+        // The metadata hash is split from the code unless the settings disable it.
+        let ape = Ape::new(project.path()).unwrap();
+        let found = ape.structure_found_creation_code(&counter)?;
+        assert_eq!(found.leading_code, Bytes::from_str("0x1234")?);
+        assert_eq!(
+            found.metadata,
+            MetadataInfo {
+                hash: Some(Bytes::from_str("0xa164736f6c6343000706000a")?),
+                start_index: Some(2),
+                end_index: Some(14),
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_artifact_metadata_settings() -> Result<(), Box<dyn Error>> {
+        #[rustfmt::skip]
+        let test_cases = vec![
+            // (solc settings, contract, expected metadata settings)
+            (default_solc_settings(), "Counter", SettingsMetadata::new(BytecodeHash::Ipfs, true)),
+            (json!({ "metadata": { "bytecodeHash": "none" } }), "Counter", SettingsMetadata::new(BytecodeHash::None, true)),
+            (json!({ "metadata": { "bytecodeHash": "none", "appendCBOR": false } }), "Counter", SettingsMetadata::new(BytecodeHash::None, false)),
+            // Vyper code is compared exactly.
+            (default_solc_settings(), "Token", SettingsMetadata::new(BytecodeHash::None, false)),
+        ];
+
+        for (solc_settings, name, expected) in test_cases {
+            let project = create_test_project(solc_settings.clone())?;
+            let metadata_settings =
+                Ape::get_artifact_metadata_settings(&artifact(&project, name)?)?;
+            assert_eq!(metadata_settings, expected, "{name}: {solc_settings}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_verified_artifact() -> Result<(), Box<dyn Error>> {
+        let project = create_test_project(default_solc_settings())?;
+        let ape = Ape::new(project.path()).unwrap();
+
+        let counter = artifact(&project, "Counter")?;
+        let verified = ape.get_verified_artifact(&counter)?;
+        assert!(verified.abi.is_some() && verified.ast.is_none());
+        let metadata = verified.metadata.unwrap();
+        assert_eq!(metadata.compiler.version, "0.8.19+commit.7dd6d404");
+        assert_eq!(metadata.language, "Solidity");
+        assert_eq!(metadata.settings.optimizer.runs, Some(200));
+        assert_eq!(metadata.settings.compilation_target["Counter.sol"], "Counter");
+        // The contract's sources are the source that defines it and the sources it imports.
+        let source_ids: Vec<_> = metadata.sources.inner.keys().collect();
+        assert_eq!(source_ids, vec!["Counter.sol", "ICounter.sol"]);
+        let content = "import \"./ICounter.sol\";\ncontract Counter is ICounter {}";
+        let hash = format!("{:?}", H256::from(keccak256(content)));
+        assert_eq!(metadata.sources.inner["Counter.sol"].keccak256, hash);
+
+        let token = ape.get_verified_artifact(&artifact(&project, "Token")?)?;
+        let metadata = token.metadata.unwrap();
+        assert_eq!(metadata.language, "Vyper");
+        assert_eq!(metadata.settings.compilation_target["Token.vy"], "Token");
+
+        let sources = ape.get_artifact_sources(&counter)?;
+        assert_eq!(sources.contents[Path::new("Counter.sol")], content);
+        assert_eq!(sources.contents.len(), 4);
+        assert!(sources.standard_json_input.is_none());
+        Ok(())
+    }
+}
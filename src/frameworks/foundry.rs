@@ -1,34 +1,189 @@
-use super::framework::Framework;
+use super::framework::{ArtifactSources, BuildCommand, BuildVariant, Framework};
 
 use crate::bytecode::{
-    parse_metadata, ExpectedCreationBytecode, ExpectedDeployedBytecode, FoundCreationBytecode,
-    FoundDeployedBytecode, ImmutableReferences, MetadataInfo,
+    parse_unlinked_code, reproduce_ipfs_metadata_hash, structure_expected_creation_code,
+    structure_expected_deployed_code, structure_found_creation_code, structure_found_deployed_code,
+    ExpectedCreationBytecode, ExpectedDeployedBytecode, FoundCreationBytecode,
+    FoundDeployedBytecode, ImmutableReferences, LinkReferences,
 };
 use ethers::types::Bytes;
 use ethers_solc::{
     artifacts::{BytecodeHash, BytecodeObject, LosslessAbi, SettingsMetadata},
+    buildinfo::BuildInfo,
     ConfigurableContractArtifact,
 };
 use std::{
     error::Error,
     fs,
-    path::{Path, PathBuf},
+    path::{Component, Path, PathBuf},
     process::Command,
     result::Result,
 };
 use walkdir::WalkDir;
 
+/// The metadata settings of a profile in the `foundry.toml` file, which determine the metadata
+/// appended to the compiled code. Settings that aren't configured use forge's defaults.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct ProfileMetadataSettings {
+    /// The `bytecode_hash` setting, e.g. `ipfs` or `none`.
+    bytecode_hash: Option<String>,
+    /// The `cbor_metadata` setting, i.e. whether the CBOR-encoded metadata is appended.
+    cbor_metadata: Option<bool>,
+}
+
+/// The directories of a profile in the `foundry.toml` file, which determine which compiled
+/// contracts are the project's own and which are dependencies.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ProfileDirs {
+    /// The `src` setting, i.e. the directory of the project's contracts.
+    src: String,
+    /// The `libs` setting, i.e. the directories dependencies are installed in.
+    libs: Vec<String>,
+}
+
+impl Default for ProfileDirs {
+    fn default() -> Self {
+        Self { src: "src".to_string(), libs: vec!["lib".to_string()] }
+    }
+}
+
+/// A project's parsed `foundry.toml` file, which the settings of each profile are read from.
+/// Profiles inherit each setting they don't configure from the default profile.
+struct FoundryConfig {
+    /// The contents of the `foundry.toml` file.
+    data: toml::Value,
+}
+
+impl FoundryConfig {
+    /// Reads and parses the `foundry.toml` file.
+    fn load(config_file: &Path) -> Result<Self, Box<dyn Error>> {
+        let contents = fs::read_to_string(config_file)?;
+        let data = contents
+            .parse::<toml::Value>()
+            .map_err(|_| "Unable to parse foundry.toml file".to_string())?;
+        Ok(Self { data })
+    }
+
+    /// Returns the value of a setting of the given profile, falling back to the default profile.
+    fn setting(&self, profile_name: &str, key: &str) -> Option<&toml::Value> {
+        let profile_setting = |profile: &str| {
+            self.data
+                .get("profile")
+                .and_then(|profiles| profiles.get(profile))
+                .and_then(|profile| profile.get(key))
+        };
+        profile_setting(profile_name).or_else(|| profile_setting("default"))
+    }
+
+    /// Returns the names of all profiles. The `default` profile is always included, since forge
+    /// falls back to it when a config has no `[profile]` table.
+    fn profiles(&self) -> Vec<String> {
+        let mut profiles: Vec<String> = self
+            .data
+            .get("profile")
+            .and_then(|v| v.as_table())
+            .map(|profiles_table| profiles_table.keys().cloned().collect())
+            .unwrap_or_default();
+
+        if !profiles.contains(&"default".to_string()) {
+            profiles.push("default".to_string());
+        }
+        profiles
+    }
+
+    /// Returns the source and dependency directories of the given profile. Forge defaults to
+    /// `src` and `lib`.
+    fn profile_dirs(&self, profile_name: &str) -> ProfileDirs {
+        let mut dirs = ProfileDirs::default();
+        if let Some(src) = self.setting(profile_name, "src").and_then(|v| v.as_str()) {
+            dirs.src = src.to_string();
+        }
+        if let Some(libs) = self.setting(profile_name, "libs").and_then(|v| v.as_array()) {
+            dirs.libs = libs.iter().filter_map(|lib| lib.as_str().map(str::to_string)).collect();
+        }
+        dirs
+    }
+
+    /// Returns whether the optimizer is enabled for the given profile. Forge enables the
+    /// optimizer when it's not configured.
+    fn profile_optimizer_enabled(&self, profile_name: &str) -> bool {
+        self.setting(profile_name, "optimizer").and_then(|v| v.as_bool()).unwrap_or(true)
+    }
+
+    /// Returns the metadata settings of the given profile.
+    fn profile_metadata_settings(&self, profile_name: &str) -> ProfileMetadataSettings {
+        ProfileMetadataSettings {
+            bytecode_hash: self
+                .setting(profile_name, "bytecode_hash")
+                .and_then(|v| v.as_str().map(str::to_string)),
+            cbor_metadata: self.setting(profile_name, "cbor_metadata").and_then(|v| v.as_bool()),
+        }
+    }
+}
+
 /// Implementation of the `Framework` trait for Foundry projects.
 pub struct Foundry {
     /// Path to the project.
     path: PathBuf,
+    /// Whether deployed code compiled with `bytecodeHash: none` is rebuilt with the IPFS metadata
+    /// hash it would have had, so it can fully match deployments whose metadata uses `ipfs`.
+    reproduce_metadata_hash: bool,
 }
 
 impl Foundry {
+    /// Sets whether deployed code compiled with `bytecodeHash: none` is compared as if it was
+    /// compiled with `bytecodeHash: ipfs`, by recomputing the metadata hash from the artifact's
+    /// metadata.
+    pub fn with_metadata_hash_reproduction(mut self, enabled: bool) -> Self {
+        self.reproduce_metadata_hash = enabled;
+        self
+    }
+
+    /// Returns an error if the remapping isn't formatted as `context:prefix=target`, where the
+    /// context is optional. The target must be a relative path within the project, so remappings
+    /// can't import files from elsewhere on the server.
+    pub fn validate_remapping(remapping: &str) -> Result<(), String> {
+        let invalid = |reason: &str| Err(format!("Invalid remapping '{remapping}': {reason}."));
+        let Some((context_and_prefix, target)) = remapping.split_once('=') else {
+            return invalid("expected `prefix=path`")
+        };
+        let prefix = context_and_prefix.split_once(':').map_or(context_and_prefix, |(_, p)| p);
+        if prefix.trim().is_empty() || target.trim().is_empty() {
+            return invalid("expected `prefix=path`")
+        }
+        if remapping.chars().any(char::is_control) {
+            return invalid("remappings must be a single line")
+        }
+        let target = Path::new(target);
+        if target.is_absolute() || target.components().any(|c| c == Component::ParentDir) {
+            return invalid("the path must be relative to the project and within it")
+        }
+        Ok(())
+    }
+
+    /// Writes the remappings to the project's `remappings.txt`, replacing any existing remappings
+    /// file, so projects whose remappings aren't committed can be built.
+    pub fn write_remappings(&self, remappings: &[String]) -> Result<(), Box<dyn Error>> {
+        for remapping in remappings {
+            Self::validate_remapping(remapping)?;
+        }
+        let contents =
+            remappings.iter().map(|remapping| format!("{remapping}\n")).collect::<String>();
+        fs::write(self.path.join("remappings.txt"), contents)?;
+        Ok(())
+    }
+
+    /// Returns the metadata JSON output by the compiler for the given artifact.
+    fn get_artifact_raw_metadata(artifact: &Path) -> Result<String, Box<dyn Error>> {
+        let file_content = fs::read_to_string(artifact)?;
+        let artifact: ConfigurableContractArtifact = serde_json::from_str(&file_content)?;
+        Ok(artifact.raw_metadata.ok_or("No rawMetadata found")?)
+    }
+
     /// Given all artifacts that were generated by compilation, filter out contracts that cannot be
     /// the most-derived contract the user is interested in. For now this just filters out contracts
-    /// where all sources are in the `lib/` directory.
-    fn filter_artifacts(artifacts: Vec<PathBuf>) -> Vec<PathBuf> {
+    /// where all sources are in a dependency directory.
+    fn filter_artifacts(artifacts: Vec<PathBuf>, dirs: &ProfileDirs) -> Vec<PathBuf> {
         artifacts
             .into_iter()
             .filter(|a| {
@@ -36,38 +191,167 @@ impl Foundry {
                 let json: serde_json::Value = serde_json::from_str(&content).unwrap();
                 if let Some(metadata) = json.get("metadata") {
                     if let Some(sources) = metadata.get("sources") {
+                        let remappings = metadata
+                            .pointer("/settings/remappings")
+                            .and_then(|r| r.as_array())
+                            .map(|r| r.iter().filter_map(|r| r.as_str()).collect::<Vec<_>>())
+                            .unwrap_or_default();
+                        let dependency_dirs = Self::dependency_dirs(&remappings, dirs);
                         let sources_obj = sources.as_object().unwrap();
-                        let all_sources_are_libs =
-                            sources_obj.keys().all(|key| key.starts_with("lib/"));
+                        let all_sources_are_libs = sources_obj.keys().all(|key| {
+                            dependency_dirs.iter().any(|dir| key.starts_with(dir.as_str()))
+                        });
                         return !all_sources_are_libs
                     }
                 }
-                false // If metadata and sources are missing, this can't be the right contract.
+                // Yul and assembly contracts have no metadata, so keep them if they have code.
+                // Otherwise, if metadata and sources are missing, this can't be the right contract.
+                json.pointer("/bytecode/object")
+                    .and_then(|code| code.as_str())
+                    .map_or(false, |code| !code.trim_start_matches("0x").is_empty())
             })
             .collect::<Vec<_>>()
     }
 
-    /// Returns the names of all profiles found in the `foundry.toml` file.
-    fn foundry_profiles(config_file: &PathBuf) -> Result<Vec<String>, Box<dyn Error>> {
-        let contents = fs::read_to_string(config_file).unwrap();
-        let data = contents.parse::<toml::Value>();
-        if data.is_err() {
-            return Err("Unable to parse foundry.toml file".into())
+    /// Returns the directories dependencies are installed in, based on where the project's
+    /// remappings point. For example, `solmate/=dependencies/solmate/src/` means dependencies are
+    /// installed in `dependencies/`. The profile's `libs` directories are always included since
+    /// they're where forge installs dependencies, and remappings into the project's source
+    /// directory are ignored.
+    fn dependency_dirs(remappings: &[&str], profile_dirs: &ProfileDirs) -> Vec<String> {
+        let normalize =
+            |dir: &str| format!("{}/", dir.trim_start_matches("./").trim_end_matches('/'));
+        let src_dir = normalize(&profile_dirs.src);
+        let mut dirs: Vec<String> = Vec::new();
+        for lib in &profile_dirs.libs {
+            let lib = normalize(lib);
+            if !dirs.contains(&lib) {
+                dirs.push(lib);
+            }
+        }
+        for remapping in remappings {
+            // Remappings are formatted as `context:prefix=target`, where the context is optional.
+            let Some((_, target)) = remapping.split_once('=') else { continue };
+            let target = target.trim_start_matches("./");
+            // A dependency directory contains one directory per dependency, so a target must be
+            // nested at least one level below it. Targets like `src/` are project sources.
+            let Some((dir, rest)) = target.split_once('/') else { continue };
+            let dir = format!("{dir}/");
+            if rest.is_empty() || dir == src_dir || dir == "../" || dirs.contains(&dir) {
+                continue
+            }
+            dirs.push(dir);
         }
+        dirs
+    }
 
-        let mut profiles = Vec::new();
-        if let Some(profiles_table) =
-            data.unwrap().as_table().unwrap().get("profile").and_then(|v| v.as_table())
-        {
-            for key in profiles_table.keys() {
-                profiles.push(key.to_string());
+    /// Returns the directory that builds of the given profile and optimizer override write their
+    /// artifacts to. This replaces the profile's `out` setting, so artifacts are found wherever
+    /// the project's config puts them, and builds never overwrite or mix with each other's output.
+    fn out_dir(&self, profile_name: &str, optimizer_override: Option<bool>) -> PathBuf {
+        let out_dir_name = match optimizer_override {
+            Some(optimizer) => format!("{profile_name}-optimizer-{optimizer}"),
+            None => profile_name.to_string(),
+        };
+        self.path.join("cove_out").join(out_dir_name)
+    }
+
+    /// Returns the directory that the build writing artifacts to `out_dir` writes its build info
+    /// to. Each build has its own build info, so an artifact's sources are read from the build
+    /// that produced it rather than from another profile's or optimizer setting's build.
+    fn build_info_dir(out_dir: &Path) -> PathBuf {
+        out_dir.join("build-info")
+    }
+
+    /// Returns the build info of the build that produced the artifact. Forge writes a build info
+    /// file for each compiler run, so this is the file whose output contains the artifact's
+    /// compilation target. Artifacts without a compilation target, such as Yul contracts, can only
+    /// be attributed to a build with a single build info file.
+    fn get_artifact_build_info(&self, artifact: &Path) -> Result<BuildInfo, Box<dyn Error>> {
+        let cove_out = self.path.join("cove_out");
+        let out_dir_name = artifact
+            .strip_prefix(&cove_out)
+            .ok()
+            .and_then(|path| path.components().next())
+            .ok_or_else(|| {
+                format!("Artifact is not in a build's output: {}", artifact.display())
+            })?;
+        let build_info_dir = Self::build_info_dir(&cove_out.join(out_dir_name));
+
+        let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(artifact)?)?;
+        let compilation_target = json
+            .pointer("/metadata/settings/compilationTarget")
+            .and_then(|target| target.as_object())
+            .and_then(|target| target.iter().next())
+            .and_then(|(source, name)| Some((source.clone(), name.as_str()?.to_string())));
+
+        let mut build_infos = Vec::new();
+        let entries = fs::read_dir(&build_info_dir).map_err(|err| {
+            format!("No build info found for artifact {}: {err}", artifact.display())
+        })?;
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().map_or(false, |ext| ext == "json") {
+                build_infos.push(serde_json::from_str::<BuildInfo>(&fs::read_to_string(path)?)?);
             }
         }
 
-        if !profiles.contains(&"default".to_string()) {
-            profiles.push("default".to_string());
+        let build_info = match compilation_target {
+            Some((source, name)) => build_infos.into_iter().find(|build_info| {
+                build_info
+                    .output
+                    .contracts
+                    .get(&source)
+                    .map_or(false, |contracts| contracts.contains_key(&name))
+            }),
+            None if build_infos.len() == 1 => build_infos.pop(),
+            None => None,
+        };
+        Ok(build_info
+            .ok_or_else(|| format!("No build info found for artifact: {}", artifact.display()))?)
+    }
+
+    /// Returns the command that builds the project with the given profile, optionally overriding
+    /// the profile's optimizer setting. Artifacts are written to the directory returned by
+    /// `out_dir` rather than the profile's configured `out` directory.
+    /// The profile's metadata settings are passed through explicitly, so the metadata matches the
+    /// deployed code's even if the server's environment sets them.
+    fn build_command(
+        &self,
+        profile_name: &str,
+        optimizer_override: Option<bool>,
+        metadata_settings: &ProfileMetadataSettings,
+    ) -> BuildCommand {
+        let out_dir = self.out_dir(profile_name, optimizer_override);
+
+        // The command runs in the project directory rather than the server's working directory,
+        // which is shared by concurrent verifications.
+        let mut command = Command::new("forge");
+        command
+            .current_dir(&self.path)
+            .arg("build")
+            .arg("--skip")
+            .arg("test")
+            .arg("script")
+            .arg("--build-info")
+            .arg("--build-info-path")
+            .arg(Self::build_info_dir(&out_dir))
+            .env("FOUNDRY_PROFILE", profile_name)
+            .env("FOUNDRY_OUT", &out_dir);
+        if let Some(optimizer) = optimizer_override {
+            command.env("FOUNDRY_OPTIMIZER", optimizer.to_string());
         }
-        Ok(profiles)
+        match &metadata_settings.bytecode_hash {
+            Some(bytecode_hash) => command.env("FOUNDRY_BYTECODE_HASH", bytecode_hash),
+            None => command.env_remove("FOUNDRY_BYTECODE_HASH"),
+        };
+        match metadata_settings.cbor_metadata {
+            Some(cbor_metadata) => command.env("FOUNDRY_CBOR_METADATA", cbor_metadata.to_string()),
+            None => command.env_remove("FOUNDRY_CBOR_METADATA"),
+        };
+
+        let variant = BuildVariant { profile: profile_name.to_string(), optimizer_override };
+        BuildCommand { command, variant, out_dir }
     }
 }
 
@@ -76,7 +360,7 @@ impl Framework for Foundry {
         if !Self::is_supported(path) {
             return Err("Not a foundry project.".into())
         }
-        Ok(Self { path: path.to_path_buf() })
+        Ok(Self { path: path.to_path_buf(), reproduce_metadata_hash: false })
     }
 
     fn is_supported(path: &Path) -> bool {
@@ -84,12 +368,16 @@ impl Framework for Foundry {
         file.exists() && file.is_file()
     }
 
-    fn build_commands(&self, hint: Option<String>) -> Result<Vec<Command>, Box<dyn Error>> {
+    fn build_commands(
+        &self,
+        hint: Option<String>,
+        toggle_optimizer: bool,
+    ) -> Result<Vec<BuildCommand>, Box<dyn Error>> {
         // For forge projects, the hint is expected to be the profile name.
         let maybe_profile_name = hint;
 
-        let config_file = self.path.join("foundry.toml");
-        let mut profile_names = Self::foundry_profiles(&config_file)?;
+        let config = FoundryConfig::load(&self.path.join("foundry.toml"))?;
+        let mut profile_names = config.profiles();
         println!("  Found profiles: {:?}", profile_names);
 
         // If we are given a profile name, only build that profile, otherwise build all profiles.
@@ -102,48 +390,45 @@ impl Framework for Foundry {
             return Err("Currently a profile name must be provided for forge projects.".into())
         }
 
-        // Generate the build commands.
-        let commands = profile_names
-            .into_iter()
-            .map(|profile_name| {
-                let mut command = Command::new("forge");
-                command
-                    .arg("build")
-                    .arg("--skip")
-                    .arg("test")
-                    .arg("script")
-                    .arg("--build-info")
-                    .arg("--build-info-path")
-                    .arg("build_info")
-                    .env("FOUNDRY_PROFILE", profile_name);
-                command
-            })
-            .collect::<Vec<Command>>();
+        // Generate the build commands. When toggling the optimizer, each profile is built as
+        // configured first, then again with the opposite optimizer setting.
+        let mut commands = Vec::new();
+        for profile_name in profile_names {
+            let metadata_settings = config.profile_metadata_settings(&profile_name);
+            commands.push(self.build_command(&profile_name, None, &metadata_settings));
+            if toggle_optimizer {
+                let optimizer = config.profile_optimizer_enabled(&profile_name);
+                commands.push(self.build_command(
+                    &profile_name,
+                    Some(!optimizer),
+                    &metadata_settings,
+                ));
+            }
+        }
         Ok(commands)
     }
 
-    fn get_artifacts(&self) -> Result<Vec<PathBuf>, Box<dyn Error>> {
-        let mut artifacts = Vec::new();
-
-        let out_dirs =
-            WalkDir::new(&self.path).min_depth(1).max_depth(1).into_iter().filter_entry(|entry| {
-                entry.file_type().is_dir()
-                    && entry.file_name().to_string_lossy().to_lowercase().contains("out")
-            });
-
-        for entry in out_dirs.into_iter().filter_map(Result::ok) {
-            if entry.path().is_dir() {
-                for inner_entry in WalkDir::new(entry.path()).into_iter().filter_map(Result::ok) {
-                    if inner_entry.file_type().is_file()
-                        && inner_entry.path().extension().map_or(false, |ext| ext == "json")
-                    {
-                        artifacts.push(inner_entry.into_path());
-                    }
-                }
-            }
-        }
+    fn get_artifacts(
+        &self,
+        variant: &BuildVariant,
+        out_dir: &Path,
+    ) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        let build_info_dir = Self::build_info_dir(out_dir);
+        let artifacts = WalkDir::new(out_dir)
+            .into_iter()
+            .filter_entry(|entry| entry.path() != build_info_dir)
+            .filter_map(Result::ok)
+            .filter(|entry| {
+                entry.file_type().is_file()
+                    && entry.path().extension().map_or(false, |ext| ext == "json")
+            })
+            .map(|entry| entry.into_path())
+            .collect::<Vec<_>>();
 
-        Ok(Self::filter_artifacts(artifacts))
+        // The profile that was built determines which artifacts are dependencies.
+        let config = FoundryConfig::load(&self.path.join("foundry.toml"))?;
+        let dirs = config.profile_dirs(&variant.profile);
+        Ok(Self::filter_artifacts(artifacts, &dirs))
     }
 
     fn structure_found_creation_code(
@@ -152,23 +437,7 @@ impl Framework for Foundry {
     ) -> Result<FoundCreationBytecode, Box<dyn Error>> {
         let metadata_settings = Self::get_artifact_metadata_settings(artifact)?;
         let raw_code = Self::get_artifact_creation_code(artifact)?;
-        let bytecode_hash = metadata_settings.bytecode_hash.unwrap_or(BytecodeHash::None);
-        let append_cbor = metadata_settings.cbor_metadata.unwrap_or(false);
-
-        let (leading_code, metadata) = if bytecode_hash == BytecodeHash::None && !append_cbor {
-            // If `bytecodeHash = none` AND `appendCBOR = false`, there is no metadata, so
-            // everything we have is the leading code.
-            (raw_code.clone(), MetadataInfo::default())
-        } else {
-            // If bytecodeHash != none OR appendCBOR = true, some metadata hash is present,
-            // so we slice the bytes based on metadata length to get the leading code and metadata.
-            let metadata = parse_metadata(&raw_code);
-            let (leading_code, _) =
-                raw_code.split_at(metadata.start_index.unwrap_or(raw_code.len()));
-            (leading_code.to_vec().into(), metadata)
-        };
-
-        Ok(FoundCreationBytecode { raw_code, leading_code, metadata })
+        Ok(structure_found_creation_code(raw_code, &metadata_settings))
     }
 
     fn structure_expected_creation_code(
@@ -177,54 +446,7 @@ impl Framework for Foundry {
         found: &FoundCreationBytecode,
         expected: &Bytes,
     ) -> Result<ExpectedCreationBytecode, Box<dyn Error>> {
-        if expected.len() < found.leading_code.len() {
-            return Err("Expected creation bytecode is shorter than found creation bytecode.".into())
-        }
-
-        // Leading code is everything up until the found's metadata hash start index.
-        let raw_code_len = found.raw_code.len();
-        let leading_code: Bytes =
-            expected.split_at(found.metadata.start_index.unwrap_or(raw_code_len)).0.to_vec().into();
-
-        // Metadata hash is given by the found's metadata hash start and end indices, if they are
-        // present, otherwise it's None.
-        let metadata_hash: Option<Bytes> = if let (Some(start_index), Some(end_index)) =
-            (found.metadata.start_index, found.metadata.end_index)
-        {
-            // There may be cases where the found bytecode has a full metadata hash, but the
-            // expected bytecode only has CBOR or none. In those cases the `end_index` will be
-            // longer than the length of the expected bytecode, and this line will panic since
-            // `end_index` will be out of bounds. To avoid this while still enabling verification
-            // for different metadata hashes, the end index is `min(end_index, expected.len())`.
-            // let end_index = std::cmp::min(end_index, expected.len());
-            let adjusted_end_index = std::cmp::min(end_index, expected.len());
-            Some(expected[start_index..adjusted_end_index].to_vec().into())
-        } else {
-            None
-        };
-
-        // The encoded constructor arguments are everything that's left.
-        let accumulated_len =
-            leading_code.len() + metadata_hash.as_ref().map_or(0, |hash| hash.len());
-        let encoded_constructor_args: Option<Bytes> = if expected.len() > accumulated_len {
-            // The remaining bytes are the encoded constructor arguments.
-            Some(expected.split_at(accumulated_len).1.to_vec().into())
-        } else {
-            None
-        };
-
-        let metadata = MetadataInfo {
-            hash: metadata_hash,
-            start_index: found.metadata.start_index,
-            end_index: found.metadata.end_index,
-        };
-
-        Ok(ExpectedCreationBytecode {
-            raw_code: expected.clone(),
-            leading_code,
-            metadata,
-            constructor_args: encoded_constructor_args,
-        })
+        structure_expected_creation_code(found, expected)
     }
 
     fn structure_found_deployed_code(
@@ -232,24 +454,28 @@ impl Framework for Foundry {
         artifact: &Path,
     ) -> Result<FoundDeployedBytecode, Box<dyn Error>> {
         let metadata_settings = Self::get_artifact_metadata_settings(artifact)?;
-        let (raw_code, immutable_references) = Self::get_artifact_deployed_code(artifact)?;
-        let bytecode_hash = metadata_settings.bytecode_hash.unwrap_or(BytecodeHash::None);
-        let append_cbor = metadata_settings.cbor_metadata.unwrap_or(false);
-
-        let (leading_code, metadata) = if bytecode_hash == BytecodeHash::None && !append_cbor {
-            // If `bytecodeHash = none` AND `appendCBOR = false`, there is no metadata, so
-            // everything we have is the leading code.
-            (raw_code.clone(), MetadataInfo::default())
-        } else {
-            // If bytecodeHash != none OR appendCBOR = true, some metadata hash is present,
-            // so we slice the bytes based on metadata length to get the leading code and metadata.
-            let metadata = parse_metadata(&raw_code);
-            let (leading_code, _) =
-                raw_code.split_at(metadata.start_index.unwrap_or(raw_code.len()));
-            (leading_code.to_vec().into(), metadata)
-        };
-
-        Ok(FoundDeployedBytecode { raw_code, leading_code, metadata, immutable_references })
+        let (raw_code, immutable_references, link_references) =
+            Self::get_artifact_deployed_code(artifact)?;
+        if self.reproduces_metadata_hash(artifact) {
+            let raw_metadata = Self::get_artifact_raw_metadata(artifact)?;
+            if let Some(code) =
+                reproduce_ipfs_metadata_hash(&raw_code, &metadata_settings, &raw_metadata)
+            {
+                let settings = SettingsMetadata::new(BytecodeHash::Ipfs, true);
+                return Ok(structure_found_deployed_code(
+                    code,
+                    immutable_references,
+                    link_references,
+                    &settings,
+                ))
+            }
+        }
+        Ok(structure_found_deployed_code(
+            raw_code,
+            immutable_references,
+            link_references,
+            &metadata_settings,
+        ))
     }
 
     fn structure_expected_deployed_code(
@@ -257,44 +483,15 @@ impl Framework for Foundry {
         found: &FoundDeployedBytecode,
         expected: &Bytes,
     ) -> Result<ExpectedDeployedBytecode, Box<dyn Error>> {
-        if expected.len() < found.leading_code.len() {
-            return Err("Expected deployed bytecode is shorter than found deployed bytecode.".into())
-        }
-
-        // Leading code is everything up until the found's metadata hash start index.
-        let raw_code_len = found.raw_code.len();
-        let leading_code: Bytes =
-            expected.split_at(found.metadata.start_index.unwrap_or(raw_code_len)).0.to_vec().into();
-
-        // Metadata hash is given by the found's metadata hash start and end indices, if they are
-        // present, otherwise it's None.
-        let metadata_hash: Option<Bytes> = if let (Some(start_index), Some(end_index)) =
-            (found.metadata.start_index, found.metadata.end_index)
-        {
-            // There may be cases where the found bytecode has a full metadata hash, but the
-            // expected bytecode only has CBOR or none. In those cases the `end_index` will be
-            // longer than the length of the expected bytecode, and this line will panic since
-            // `end_index` will be out of bounds. To avoid this while still enabling verification
-            // for different metadata hashes, the end index is `min(end_index, expected.len())`.
-            // let end_index = std::cmp::min(end_index, expected.len());
-            let adjusted_end_index = std::cmp::min(end_index, expected.len());
-            Some(expected[start_index..adjusted_end_index].to_vec().into())
-        } else {
-            None
-        };
-
-        let metadata = MetadataInfo {
-            hash: metadata_hash,
-            start_index: found.metadata.start_index,
-            end_index: found.metadata.end_index,
-        };
+        structure_expected_deployed_code(found, expected)
+    }
 
-        Ok(ExpectedDeployedBytecode {
-            raw_code: expected.clone(),
-            leading_code,
-            metadata,
-            immutable_references: found.immutable_references.clone(),
-        })
+    /// The metadata hash is reproduced if reproduction is enabled and the artifact was compiled
+    /// with `bytecodeHash: none`.
+    fn reproduces_metadata_hash(&self, artifact: &Path) -> bool {
+        self.reproduce_metadata_hash
+            && Self::get_artifact_metadata_settings(artifact)
+                .map_or(false, |settings| settings.bytecode_hash == Some(BytecodeHash::None))
     }
 
     fn get_artifact_abi(artifact: &Path) -> Result<LosslessAbi, Box<dyn Error>> {
@@ -324,30 +521,31 @@ impl Framework for Foundry {
 
     fn get_artifact_deployed_code(
         artifact: &Path,
-    ) -> Result<(Bytes, ImmutableReferences), Box<dyn Error>> {
+    ) -> Result<(Bytes, ImmutableReferences, LinkReferences), Box<dyn Error>> {
         let file_content = fs::read_to_string(artifact)?;
         let artifact: ConfigurableContractArtifact = serde_json::from_str(&file_content)?;
 
         let deployed_code_object = artifact.deployed_bytecode.ok_or("No deployedBytecode found")?;
-        let deployed_code =
-            match deployed_code_object.bytecode.ok_or("No bytecode object found")?.object {
-                BytecodeObject::Bytecode(bytes) => bytes,
-                BytecodeObject::Unlinked(_) => {
-                    return Err("Linked libraries not yet supported".into())
-                }
-            };
+        let bytecode = deployed_code_object.bytecode.ok_or("No bytecode object found")?;
+        let link_references = bytecode.link_references;
+        let deployed_code = match bytecode.object {
+            BytecodeObject::Bytecode(bytes) => bytes,
+            BytecodeObject::Unlinked(code) => parse_unlinked_code(&code, &link_references)?,
+        };
         let immutable_references = deployed_code_object.immutable_references;
-        Ok((deployed_code, immutable_references))
+        Ok((deployed_code, immutable_references, link_references))
     }
 
     fn get_artifact_metadata_settings(artifact: &Path) -> Result<SettingsMetadata, Box<dyn Error>> {
         let file_content = fs::read_to_string(artifact)?;
         let json_content: serde_json::Value = serde_json::from_str(&file_content)?;
-        let settings_value = json_content
-            .get("metadata")
-            .ok_or_else(|| {
-                format!("Missing 'metadata' field in artifact JSON: {}", artifact.display())
-            })?
+
+        // Yul and assembly contracts have no metadata, so their code has no metadata hash and the
+        // entire code is compared exactly.
+        let Some(metadata) = json_content.get("metadata") else {
+            return Ok(SettingsMetadata::new(BytecodeHash::None, false))
+        };
+        let settings_value = metadata
             .get("settings")
             .ok_or_else(|| {
                 format!("Missing 'metdata.settings' field in metadata JSON: {}", artifact.display())
@@ -362,14 +560,35 @@ impl Framework for Foundry {
         let settings_metadata: SettingsMetadata = serde_json::from_value(settings_value.clone())?;
         Ok(settings_metadata)
     }
+
+    fn get_verified_artifact(
+        &self,
+        artifact: &Path,
+    ) -> Result<ConfigurableContractArtifact, Box<dyn Error>> {
+        Ok(serde_json::from_str(&fs::read_to_string(artifact)?)?)
+    }
+
+    fn get_artifact_sources(&self, artifact: &Path) -> Result<ArtifactSources, Box<dyn Error>> {
+        // The build info file has all the source code already stringified.
+        let build_info = self.get_artifact_build_info(artifact)?;
+        let contents = build_info
+            .input
+            .sources
+            .iter()
+            .map(|(path, source)| (path.clone(), source.content.to_string()))
+            .collect();
+        let standard_json_input = Some(serde_json::to_value(&build_info.input)?);
+        Ok(ArtifactSources { contents, standard_json_input })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ethers_solc::artifacts::{BytecodeHash, SettingsMetadata};
+    use crate::bytecode::MetadataInfo;
+    use ethers_solc::artifacts::SettingsMetadata;
     use serde_json::json;
-    use std::{error::Error, fs::File, io::Write, path::PathBuf, str::FromStr};
+    use std::{error::Error, ffi::OsStr, fs::File, io::Write, path::PathBuf, str::FromStr};
     use tempfile::NamedTempFile;
 
     // Helper function to create a temporary file with the given JSON content.
@@ -407,22 +626,22 @@ mod tests {
             // Test case 2: BytecodeHash::Ipfs and appendCBOR = true
             TestCase {
                 content: json!({
-                    "bytecode": { "object": "0x1234567890abcdef0002" },
+                    "bytecode": { "object": "0x1234a164736f6c6343000706000a" },
                     "metadata": { "settings":{ "metadata": { "bytecodeHash": "ipfs", "appendCBOR": true } }},
                 }),
                 expected: FoundCreationBytecode {
-                    raw_code: Bytes::from_str("0x1234567890abcdef0002")?,
-                    leading_code: Bytes::from_str("0x1234567890ab")?,
+                    raw_code: Bytes::from_str("0x1234a164736f6c6343000706000a")?,
+                    leading_code: Bytes::from_str("0x1234")?,
                     metadata: MetadataInfo {
-                        hash: Some(Bytes::from_str("0xcdef0002")?),
-                        start_index: Some(6),
-                        end_index: Some(10),
+                        hash: Some(Bytes::from_str("0xa164736f6c6343000706000a")?),
+                        start_index: Some(2),
+                        end_index: Some(14),
                     },
                 },
             },
         ];
 
-        let foundry = Foundry { path: PathBuf::new() };
+        let foundry = Foundry { path: PathBuf::new(), reproduce_metadata_hash: false };
         let artifact_path = tempfile::NamedTempFile::new()?;
         for test_case in test_cases {
             let artifact = create_test_artifact(&artifact_path, &test_case.content)?;
@@ -435,7 +654,7 @@ mod tests {
 
     #[test]
     fn test_structure_expected_creation_code() -> Result<(), Box<dyn Error>> {
-        let foundry = Foundry { path: PathBuf::new() };
+        let foundry = Foundry { path: PathBuf::new(), reproduce_metadata_hash: false };
 
         // First test the case where expected code is too short to structure.
         struct FailureTestCase {
@@ -656,6 +875,7 @@ mod tests {
         struct TestCase {
             content: serde_json::Value,
             expected: Bytes,
+            linked_files: usize,
         }
 
         let test_cases = vec![
@@ -663,19 +883,33 @@ mod tests {
             TestCase {
                 content: json!({ "deployedBytecode": { "object": "0x1234" }}),
                 expected: Bytes::from_str("0x1234")?,
+                linked_files: 0,
             },
             // Test case 2: Deployed code is missing.
             TestCase {
                 content: json!({ "deployedBytecode": { "object": "" }}),
                 expected: Bytes::from_str("")?,
+                linked_files: 0,
+            },
+            // Test case 3: Deployed code has an unlinked library placeholder, which is zeroed.
+            TestCase {
+                content: json!({ "deployedBytecode": {
+                    "object": "0x73__$a1b2c3d4e5f6a1b2c3d4e5f6a1b2c3d4e5$__6000",
+                    "linkReferences": {
+                        "src/Lib.sol": { "Lib": [ { "start": 1, "length": 20 } ] },
+                    },
+                }}),
+                expected: Bytes::from_str(&format!("0x73{}6000", "00".repeat(20)))?,
+                linked_files: 1,
             },
         ];
 
         for test_case in test_cases {
             let artifact = NamedTempFile::new()?;
             let path = create_test_artifact(&artifact, &test_case.content)?;
-            let (creation_code, _) = Foundry::get_artifact_deployed_code(&path)?;
+            let (creation_code, _, link_references) = Foundry::get_artifact_deployed_code(&path)?;
             assert_eq!(creation_code, test_case.expected);
+            assert_eq!(link_references.len(), test_case.linked_files);
         }
 
         Ok(())
@@ -736,4 +970,351 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_profile_optimizer_enabled() -> Result<(), Box<dyn Error>> {
+        #[rustfmt::skip]
+        let test_cases = vec![
+            // (foundry.toml contents, profile, expected optimizer setting)
+            ("[profile.default]", "default", true),
+            ("[profile.default]\noptimizer = false", "default", false),
+            ("[profile.default]\noptimizer = false\n[profile.ci]\noptimizer = true", "ci", true),
+            ("[profile.default]\noptimizer = false\n[profile.ci]\nfuzz_runs = 10", "ci", false),
+        ];
+
+        for (contents, profile, expected) in test_cases {
+            let config_file = NamedTempFile::new()?;
+            fs::write(config_file.path(), contents)?;
+            let enabled =
+                FoundryConfig::load(config_file.path())?.profile_optimizer_enabled(profile);
+            assert_eq!(enabled, expected, "{contents}");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_profile_metadata_settings() -> Result<(), Box<dyn Error>> {
+        let settings = |bytecode_hash: Option<&str>, cbor_metadata| ProfileMetadataSettings {
+            bytecode_hash: bytecode_hash.map(str::to_string),
+            cbor_metadata,
+        };
+        #[rustfmt::skip]
+        let test_cases = vec![
+            // (foundry.toml contents, profile, expected metadata settings)
+            ("[profile.default]", "default", settings(None, None)),
+            ("[profile.default]\nbytecode_hash = \"ipfs\"", "default", settings(Some("ipfs"), None)),
+            ("[profile.default]\nbytecode_hash = \"none\"\ncbor_metadata = false", "default", settings(Some("none"), Some(false))),
+            ("[profile.default]\nbytecode_hash = \"none\"\n[profile.ci]\nbytecode_hash = \"ipfs\"", "ci", settings(Some("ipfs"), None)),
+            ("[profile.default]\ncbor_metadata = false\n[profile.ci]\nfuzz_runs = 10", "ci", settings(None, Some(false))),
+        ];
+
+        for (contents, profile, expected) in test_cases {
+            let config_file = NamedTempFile::new()?;
+            fs::write(config_file.path(), contents)?;
+            let metadata_settings =
+                FoundryConfig::load(config_file.path())?.profile_metadata_settings(profile);
+            assert_eq!(metadata_settings, expected, "{contents}");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_profile_dirs() -> Result<(), Box<dyn Error>> {
+        let dirs = |src: &str, libs: &[&str]| ProfileDirs {
+            src: src.to_string(),
+            libs: libs.iter().map(|lib| lib.to_string()).collect(),
+        };
+        #[rustfmt::skip]
+        let test_cases = vec![
+            // (foundry.toml contents, profile, expected directories)
+            ("[profile.default]", "default", dirs("src", &["lib"])),
+            ("[profile.default]\nsrc = \"contracts\"\nlibs = [\"deps\", \"lib\"]", "default", dirs("contracts", &["deps", "lib"])),
+            ("[profile.default]\nsrc = \"contracts\"\n[profile.ci]\nlibs = [\"deps\"]", "ci", dirs("contracts", &["deps"])),
+            ("[profile.default]\nsrc = \"contracts\"\n[profile.ci]\nsrc = \"ci\"", "ci", dirs("ci", &["lib"])),
+        ];
+
+        for (contents, profile, expected) in test_cases {
+            let config_file = NamedTempFile::new()?;
+            fs::write(config_file.path(), contents)?;
+            let profile_dirs = FoundryConfig::load(config_file.path())?.profile_dirs(profile);
+            assert_eq!(profile_dirs, expected, "{contents}");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_foundry_profiles() -> Result<(), Box<dyn Error>> {
+        #[rustfmt::skip]
+        let test_cases = vec![
+            // (foundry.toml contents, expected profiles)
+            ("", vec!["default"]),
+            ("[fmt]\nline_length = 100", vec!["default"]),
+            ("profile = 1", vec!["default"]),
+            ("[profile.default]", vec!["default"]),
+            ("[profile.ci]\nfuzz_runs = 10", vec!["ci", "default"]),
+            ("[profile.ci]\n[profile.default]", vec!["ci", "default"]),
+        ];
+
+        for (contents, expected) in test_cases {
+            let config_file = NamedTempFile::new()?;
+            fs::write(config_file.path(), contents)?;
+            let profiles = FoundryConfig::load(config_file.path())?.profiles();
+            assert_eq!(profiles, expected, "{contents}");
+        }
+
+        assert!(FoundryConfig::load(Path::new("/nonexistent/foundry.toml")).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_remappings() -> Result<(), Box<dyn Error>> {
+        for remapping in
+            ["solmate/=lib/solmate/src/", "src:@oz/=lib/openzeppelin/", "ds-test=lib/ds"]
+        {
+            assert!(Foundry::validate_remapping(remapping).is_ok(), "{remapping}");
+        }
+        for remapping in [
+            "lib/solmate/src/",
+            "=lib/solmate/src/",
+            "solmate/=",
+            "src:=lib/solmate/",
+            "solmate/=/etc/",
+            "solmate/=lib/../../",
+            "solmate/=lib/\nforge-std/=lib/",
+        ] {
+            assert!(Foundry::validate_remapping(remapping).is_err(), "{remapping}");
+        }
+
+        let project_dir = tempfile::tempdir()?;
+        fs::write(project_dir.path().join("foundry.toml"), "")?;
+        fs::write(project_dir.path().join("remappings.txt"), "old/=lib/old/\n")?;
+        let foundry = Foundry::new(project_dir.path()).unwrap();
+        let remappings =
+            vec!["solmate/=lib/solmate/src/".to_string(), "ds-test=lib/ds".to_string()];
+        foundry.write_remappings(&remappings)?;
+        assert_eq!(
+            fs::read_to_string(project_dir.path().join("remappings.txt"))?,
+            "solmate/=lib/solmate/src/\nds-test=lib/ds\n"
+        );
+        assert!(foundry.write_remappings(&["bad".to_string()]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_commands_toggle_optimizer() -> Result<(), Box<dyn Error>> {
+        let project_dir = tempfile::tempdir()?;
+        fs::write(project_dir.path().join("foundry.toml"), "[profile.default]\noptimizer = false")?;
+        let foundry = Foundry::new(project_dir.path()).unwrap();
+
+        let commands = foundry.build_commands(Some("default".to_string()), false)?;
+        assert_eq!(commands.len(), 1);
+        assert_eq!(commands[0].variant.optimizer_override, None);
+
+        // Metadata settings the profile doesn't configure are removed from the environment, so
+        // forge's defaults apply rather than the server's environment.
+        let envs: Vec<_> = commands[0].command.get_envs().collect();
+        assert!(envs.contains(&(OsStr::new("FOUNDRY_BYTECODE_HASH"), None)));
+        assert!(envs.contains(&(OsStr::new("FOUNDRY_CBOR_METADATA"), None)));
+
+        let commands = foundry.build_commands(Some("default".to_string()), true)?;
+        assert_eq!(commands.len(), 2);
+        assert_eq!(commands[0].variant.optimizer_override, None);
+        assert_eq!(commands[1].variant.optimizer_override, Some(true));
+        assert_ne!(commands[0].out_dir, commands[1].out_dir);
+
+        // Configured metadata settings are passed through unchanged.
+        fs::write(
+            project_dir.path().join("foundry.toml"),
+            "[profile.default]\nbytecode_hash = \"ipfs\"",
+        )?;
+        let commands = foundry.build_commands(Some("default".to_string()), false)?;
+        let envs: Vec<_> = commands[0].command.get_envs().collect();
+        assert!(envs.contains(&(OsStr::new("FOUNDRY_BYTECODE_HASH"), Some(OsStr::new("ipfs")))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_artifacts_scoped_to_out_dir() -> Result<(), Box<dyn Error>> {
+        let project_dir = tempfile::tempdir()?;
+        fs::write(project_dir.path().join("foundry.toml"), "[profile.default]")?;
+        let foundry = Foundry::new(project_dir.path()).unwrap();
+
+        let artifact = r#"{"metadata":{"sources":{"src/Counter.sol":{}}}}"#;
+        for profile in ["default", "other"] {
+            let dir = project_dir.path().join("cove_out").join(profile).join("Counter.sol");
+            fs::create_dir_all(&dir)?;
+            fs::write(dir.join("Counter.json"), artifact)?;
+        }
+
+        let out_dir = project_dir.path().join("cove_out").join("default");
+        let variant = BuildVariant { profile: "default".to_string(), optimizer_override: None };
+        let artifacts = foundry.get_artifacts(&variant, &out_dir)?;
+        assert_eq!(artifacts, vec![out_dir.join("Counter.sol").join("Counter.json")]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_artifacts_with_custom_dirs() -> Result<(), Box<dyn Error>> {
+        let project_dir = tempfile::tempdir()?;
+        let config = "[profile.default]\nout = \"artifacts\"\n[profile.ci]\nsrc = \"contracts\"\nlibs = [\"deps\"]";
+        fs::write(project_dir.path().join("foundry.toml"), config)?;
+        let foundry = Foundry::new(project_dir.path()).unwrap();
+
+        // Builds write to their own output directory instead of the configured `out` directory.
+        let commands = foundry.build_commands(Some("ci".to_string()), false)?;
+        let out_dir = project_dir.path().join("cove_out").join("ci");
+        assert_eq!(commands[0].out_dir, out_dir);
+        let envs: Vec<_> = commands[0].command.get_envs().collect();
+        assert!(envs.contains(&(OsStr::new("FOUNDRY_OUT"), Some(out_dir.as_os_str()))));
+
+        // Artifacts are only found in the build's output directory, and artifacts whose sources are
+        // all in the profile's `libs` directories are dependencies.
+        let write_artifact = |dir: &Path, name: &str, source: &str| -> std::io::Result<PathBuf> {
+            fs::create_dir_all(dir)?;
+            let artifact = dir.join(format!("{name}.json"));
+            let json = json!({ "metadata": { "sources": { source: {} } } });
+            fs::write(&artifact, json.to_string())?;
+            Ok(artifact)
+        };
+        let artifacts_dir = project_dir.path().join("artifacts");
+        write_artifact(&artifacts_dir, "Stale", "contracts/Stale.sol")?;
+        let counter = write_artifact(&out_dir, "Counter", "contracts/Counter.sol")?;
+        write_artifact(&out_dir, "ERC20", "deps/solmate/src/tokens/ERC20.sol")?;
+
+        assert_eq!(foundry.get_artifacts(&commands[0].variant, &out_dir)?, vec![counter]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_dependency_dirs() {
+        #[rustfmt::skip]
+        let test_cases = vec![
+            // (remappings, expected dependency directories)
+            (vec![], vec!["lib/"]),
+            (vec!["forge-std/=lib/forge-std/src/"], vec!["lib/"]),
+            (vec!["solmate/=dependencies/solmate/src/"], vec!["lib/", "dependencies/"]),
+            (vec!["@openzeppelin/=node_modules/@openzeppelin/"], vec!["lib/", "node_modules/"]),
+            (vec!["src/:solmate/=./dependencies/solmate-6.2.0/src/"], vec!["lib/", "dependencies/"]),
+            (vec!["@/=src/", "@utils/=src/utils/"], vec!["lib/"]),
+            (vec!["ds-test/=../ds-test/src/"], vec!["lib/"]),
+        ];
+
+        for (remappings, expected) in test_cases {
+            assert_eq!(
+                Foundry::dependency_dirs(&remappings, &ProfileDirs::default()),
+                expected,
+                "{remappings:?}"
+            );
+        }
+
+        // Dependencies are also installed in the profile's `libs` directories.
+        let dirs = ProfileDirs { src: "contracts".to_string(), libs: vec!["./deps".to_string()] };
+        let remappings = ["@/=contracts/", "solmate/=lib/solmate/src/"];
+        assert_eq!(Foundry::dependency_dirs(&remappings, &dirs), vec!["deps/", "lib/"]);
+    }
+
+    #[test]
+    fn test_filter_artifacts_with_dependencies_remapping() -> Result<(), Box<dyn Error>> {
+        let dir = tempfile::tempdir()?;
+        let remappings = ["solmate/=dependencies/solmate/src/", "forge-std/=lib/forge-std/src/"];
+        #[rustfmt::skip]
+        let test_cases = vec![
+            // (artifact name, sources, expected to be kept)
+            ("Counter", vec!["src/Counter.sol", "dependencies/solmate/src/tokens/ERC20.sol"], true),
+            ("ERC20", vec!["dependencies/solmate/src/tokens/ERC20.sol"], false),
+            ("Test", vec!["lib/forge-std/src/Test.sol", "dependencies/solmate/src/auth/Owned.sol"], false),
+            ("Script", vec!["script/Deploy.s.sol", "lib/forge-std/src/Script.sol"], true),
+        ];
+
+        let mut artifacts = Vec::new();
+        let mut expected = Vec::new();
+        for (name, sources, keep) in test_cases {
+            let sources: serde_json::Map<_, _> =
+                sources.into_iter().map(|s| (s.to_string(), serde_json::json!({}))).collect();
+            let artifact_json = serde_json::json!({
+                "metadata": { "sources": sources, "settings": { "remappings": remappings } },
+            });
+            let artifact = dir.path().join(format!("{name}.json"));
+            fs::write(&artifact, artifact_json.to_string())?;
+            if keep {
+                expected.push(artifact.clone());
+            }
+            artifacts.push(artifact);
+        }
+
+        assert_eq!(Foundry::filter_artifacts(artifacts, &ProfileDirs::default()), expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_filter_artifacts_keeps_yul() -> Result<(), Box<dyn Error>> {
+        let dir = tempfile::tempdir()?;
+        let yul = dir.path().join("Yul.json");
+        fs::write(&yul, json!({ "bytecode": { "object": "0x600160005260206000f3" } }).to_string())?;
+        let empty = dir.path().join("Empty.json");
+        fs::write(&empty, json!({ "bytecode": { "object": "0x" } }).to_string())?;
+
+        assert_eq!(
+            Foundry::filter_artifacts(vec![yul.clone(), empty], &ProfileDirs::default()),
+            vec![yul]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_artifact_sources_from_own_build() -> Result<(), Box<dyn Error>> {
+        let project_dir = tempfile::tempdir()?;
+        fs::write(project_dir.path().join("foundry.toml"), "[profile.default]")?;
+        let foundry = Foundry::new(project_dir.path()).unwrap();
+
+        // Each build writes build info for the sources it compiled to its own directory.
+        let write_build = |out_dir_name: &str, content: &str| -> std::io::Result<PathBuf> {
+            let out_dir = project_dir.path().join("cove_out").join(out_dir_name);
+            let artifact_dir = out_dir.join("Counter.sol");
+            fs::create_dir_all(&artifact_dir)?;
+            fs::create_dir_all(Foundry::build_info_dir(&out_dir))?;
+            let build_info = json!({
+                "id": "abc",
+                "_format": "ethers-rs-sol-build-info-1",
+                "solcVersion": "0.8.19",
+                "solcLongVersion": "0.8.19+commit.7dd6d404",
+                "input": {
+                    "language": "Solidity",
+                    "sources": { "src/Counter.sol": { "content": content } },
+                    "settings": {}
+                },
+                "output": { "contracts": { "src/Counter.sol": { "Counter": {} } } }
+            });
+            fs::write(Foundry::build_info_dir(&out_dir).join("abc.json"), build_info.to_string())?;
+            let artifact = artifact_dir.join("Counter.json");
+            let target = json!({ "src/Counter.sol": "Counter" });
+            let json = json!({ "metadata": { "settings": { "compilationTarget": target } } });
+            fs::write(&artifact, json.to_string())?;
+            Ok(artifact)
+        };
+        let default_artifact = write_build("default", "contract Counter {}")?;
+        let optimized_artifact = write_build("default-optimizer-true", "contract Counter { }")?;
+
+        let sources = foundry.get_artifact_sources(&default_artifact)?;
+        assert_eq!(sources.contents[Path::new("src/Counter.sol")], "contract Counter {}");
+        let sources = foundry.get_artifact_sources(&optimized_artifact)?;
+        assert_eq!(sources.contents[Path::new("src/Counter.sol")], "contract Counter { }");
+
+        // Build info that doesn't contain the artifact's contract isn't used.
+        let other_target = json!({ "src/Other.sol": "Other" });
+        let json = json!({ "metadata": { "settings": { "compilationTarget": other_target } } });
+        fs::write(&default_artifact, json.to_string())?;
+        assert!(foundry.get_artifact_sources(&default_artifact).is_err());
+
+        // A build without build info is an error rather than a panic.
+        let optimized_out_dir = project_dir.path().join("cove_out").join("default-optimizer-true");
+        fs::remove_dir_all(Foundry::build_info_dir(&optimized_out_dir))?;
+        assert!(foundry.get_artifact_sources(&optimized_artifact).is_err());
+        Ok(())
+    }
 }
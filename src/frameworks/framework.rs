@@ -1,17 +1,55 @@
 /// Module for abstracting framework-specific logic.
 use crate::bytecode::{
     ExpectedCreationBytecode, ExpectedDeployedBytecode, FoundCreationBytecode,
-    FoundDeployedBytecode, ImmutableReferences,
+    FoundDeployedBytecode, ImmutableReferences, LinkReferences,
 };
 use ethers::types::Bytes;
-use ethers_solc::artifacts::{LosslessAbi, SettingsMetadata};
+use ethers_solc::{
+    artifacts::{LosslessAbi, SettingsMetadata},
+    ConfigurableContractArtifact,
+};
+use serde::{Deserialize, Serialize};
 use std::{
+    collections::BTreeMap,
     error::Error,
     path::{Path, PathBuf},
     process::Command,
     result::Result,
 };
 
+/// Describes the configuration that a build command compiles the project with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildVariant {
+    /// The framework-specific name of the build configuration. For Foundry this is the profile.
+    pub profile: String,
+    /// If present, the optimizer setting used instead of the one configured for the profile.
+    pub optimizer_override: Option<bool>,
+}
+
+/// A command that compiles the project, along with the configuration it compiles with.
+#[derive(Debug)]
+pub struct BuildCommand {
+    /// The command to run.
+    pub command: Command,
+    /// The configuration the command compiles with.
+    pub variant: BuildVariant,
+    /// The directory the command writes artifacts to. Each build command has its own output
+    /// directory so artifacts from different configurations are never mixed.
+    pub out_dir: PathBuf,
+}
+
+/// The sources that an artifact was compiled from, which are returned along with a verified
+/// artifact.
+#[derive(Debug, Default)]
+pub struct ArtifactSources {
+    /// The content of each source compiled alongside the artifact, keyed by the path the compiler
+    /// was given. This may include sources the artifact doesn't depend on.
+    pub contents: BTreeMap<PathBuf, String>,
+    /// The standard JSON input the compiler was given, if the framework records it.
+    pub standard_json_input: Option<serde_json::Value>,
+}
+
 /// Trait for abstracting framework-specific logic, such as how to build the project and parse
 /// compilation artifacts.
 pub trait Framework {
@@ -23,13 +61,25 @@ pub trait Framework {
         Self: Sized;
 
     /// Verifies this is the expected project type and returns true if so.
-    fn is_supported(path: &Path) -> bool;
+    fn is_supported(path: &Path) -> bool
+    where
+        Self: Sized;
 
-    /// Returns the build commands to compile the repo.
-    fn build_commands(&self, hint: Option<String>) -> Result<Vec<Command>, Box<dyn Error>>;
+    /// Returns the build commands to compile the repo. If `toggle_optimizer` is true, each build
+    /// configuration is also compiled with its optimizer setting flipped, to cover deployments
+    /// whose optimizer setting isn't captured in the project's configuration.
+    fn build_commands(
+        &self,
+        hint: Option<String>,
+        toggle_optimizer: bool,
+    ) -> Result<Vec<BuildCommand>, Box<dyn Error>>;
 
-    /// Returns the artifacts generated by compilation.
-    fn get_artifacts(&self) -> Result<Vec<PathBuf>, Box<dyn Error>>;
+    /// Returns the artifacts generated by the build of `variant` that wrote to `out_dir`.
+    fn get_artifacts(
+        &self,
+        variant: &BuildVariant,
+        out_dir: &Path,
+    ) -> Result<Vec<PathBuf>, Box<dyn Error>>;
 
     // -------- Bytecode Structuring --------
 
@@ -64,19 +114,44 @@ pub trait Framework {
         expected: &Bytes,
     ) -> Result<ExpectedDeployedBytecode, Box<dyn Error>>;
 
+    /// Returns true if the metadata hash of the artifact's deployed code was reproduced, rather
+    /// than read from the artifact, when structuring it.
+    fn reproduces_metadata_hash(&self, _artifact: &Path) -> bool {
+        false
+    }
+
     // -------- Artifact parsing --------
 
     /// Returns the ABI of the given artifact.
-    fn get_artifact_abi(artifact: &Path) -> Result<LosslessAbi, Box<dyn Error>>;
+    fn get_artifact_abi(artifact: &Path) -> Result<LosslessAbi, Box<dyn Error>>
+    where
+        Self: Sized;
 
     /// Returns the creation code of the given artifact.
-    fn get_artifact_creation_code(artifact: &Path) -> Result<Bytes, Box<dyn Error>>;
+    fn get_artifact_creation_code(artifact: &Path) -> Result<Bytes, Box<dyn Error>>
+    where
+        Self: Sized;
 
-    /// Returns the deployed code of the given artifact.
+    /// Returns the deployed code of the given artifact, with any library placeholders zeroed, along
+    /// with its immutable references and link references.
     fn get_artifact_deployed_code(
         artifact: &Path,
-    ) -> Result<(Bytes, ImmutableReferences), Box<dyn Error>>;
+    ) -> Result<(Bytes, ImmutableReferences, LinkReferences), Box<dyn Error>>
+    where
+        Self: Sized;
 
     /// Returns the metadata settings of the given artifact.
-    fn get_artifact_metadata_settings(artifact: &Path) -> Result<SettingsMetadata, Box<dyn Error>>;
+    fn get_artifact_metadata_settings(artifact: &Path) -> Result<SettingsMetadata, Box<dyn Error>>
+    where
+        Self: Sized;
+
+    /// Returns the compiler output for the given artifact in the format of a forge artifact, which
+    /// is how verified contracts are returned. Output the framework doesn't record is omitted.
+    fn get_verified_artifact(
+        &self,
+        artifact: &Path,
+    ) -> Result<ConfigurableContractArtifact, Box<dyn Error>>;
+
+    /// Returns the sources the given artifact was compiled from.
+    fn get_artifact_sources(&self, artifact: &Path) -> Result<ArtifactSources, Box<dyn Error>>;
 }
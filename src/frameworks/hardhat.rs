@@ -0,0 +1,631 @@
+use super::framework::{ArtifactSources, BuildCommand, BuildVariant, Framework};
+
+use crate::bytecode::{
+    parse_unlinked_code, structure_expected_creation_code, structure_expected_deployed_code,
+    structure_found_creation_code, structure_found_deployed_code, ExpectedCreationBytecode,
+    ExpectedDeployedBytecode, FoundCreationBytecode, FoundDeployedBytecode, ImmutableReferences,
+    LinkReferences,
+};
+use ethers::types::Bytes;
+use ethers_solc::{
+    artifacts::{BytecodeHash, LosslessAbi, SettingsMetadata},
+    ConfigurableContractArtifact,
+};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    result::Result,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+use walkdir::WalkDir;
+
+/// The names of the config files that identify a Hardhat project. Projects using ES modules name
+/// their JavaScript config `hardhat.config.cjs`.
+const CONFIG_FILES: [&str; 3] = ["hardhat.config.js", "hardhat.config.ts", "hardhat.config.cjs"];
+
+/// Implementation of the `Framework` trait for Hardhat projects.
+///
+/// Hardhat writes one artifact per contract to `artifacts/<source>/<contract>.json`, alongside a
+/// `<contract>.dbg.json` file that points to the build info in `artifacts/build-info/`. The
+/// per-contract artifacts only contain the ABI and code, so the compiler output for each contract
+/// is read from the build info instead, where it's nested under
+/// `output.contracts[sourceName][contractName]` exactly as solc returns it.
+pub struct Hardhat {
+    /// Path to the project.
+    path: PathBuf,
+    /// The build infos that have been read, keyed by path. A build info contains the compiler
+    /// input and output of every contract in the build, so it's parsed once and shared by all
+    /// artifacts that point to it.
+    build_infos: Mutex<HashMap<PathBuf, Arc<serde_json::Value>>>,
+}
+
+impl Hardhat {
+    /// Returns the path of the build info that the debug file of the given per-contract artifact
+    /// points to.
+    fn build_info_path(artifact: &Path) -> Result<PathBuf, Box<dyn Error>> {
+        // The debug file's build info path is relative to the debug file.
+        let dbg_file = artifact.with_extension("dbg.json");
+        let dbg: serde_json::Value = serde_json::from_str(&fs::read_to_string(&dbg_file)?)?;
+        let build_info_path = dbg["buildInfo"]
+            .as_str()
+            .ok_or_else(|| format!("Missing 'buildInfo' field in {}", dbg_file.display()))?;
+        Ok(dbg_file.parent().unwrap_or(Path::new("")).join(build_info_path))
+    }
+
+    /// Returns the build info of the given per-contract artifact, along with its path. The build
+    /// info is only parsed the first time one of its artifacts is read.
+    fn build_info(
+        &self,
+        artifact: &Path,
+    ) -> Result<(Arc<serde_json::Value>, PathBuf), Box<dyn Error>> {
+        let build_info_path = Self::build_info_path(artifact)?;
+        // The lock is held while parsing so concurrent comparisons don't parse the same file.
+        let mut build_infos = self.build_infos.lock().unwrap_or_else(|err| err.into_inner());
+        let build_info = match build_infos.get(&build_info_path) {
+            Some(build_info) => build_info.clone(),
+            None => {
+                let build_info: serde_json::Value =
+                    serde_json::from_str(&fs::read_to_string(&build_info_path)?)?;
+                let build_info = Arc::new(build_info);
+                build_infos.insert(build_info_path.clone(), build_info.clone());
+                build_info
+            }
+        };
+        Ok((build_info, build_info_path))
+    }
+
+    /// Returns the solc output for the contract of the given per-contract artifact, read from the
+    /// build info its debug file points to.
+    fn contract_output(&self, artifact: &Path) -> Result<serde_json::Value, Box<dyn Error>> {
+        let (build_info, build_info_path) = self.build_info(artifact)?;
+        Self::find_contract_output(&build_info, &build_info_path, artifact)
+    }
+
+    /// Returns the solc output for the contract of the given per-contract artifact without a
+    /// `Hardhat` instance, so the build info is parsed for each call. This is only used by the
+    /// framework's associated functions.
+    fn read_contract_output(artifact: &Path) -> Result<serde_json::Value, Box<dyn Error>> {
+        let build_info_path = Self::build_info_path(artifact)?;
+        let build_info = serde_json::from_str(&fs::read_to_string(&build_info_path)?)?;
+        Self::find_contract_output(&build_info, &build_info_path, artifact)
+    }
+
+    /// Returns the solc output for the contract of the given per-contract artifact from its build
+    /// info.
+    fn find_contract_output(
+        build_info: &serde_json::Value,
+        build_info_path: &Path,
+        artifact: &Path,
+    ) -> Result<serde_json::Value, Box<dyn Error>> {
+        let content: serde_json::Value = serde_json::from_str(&fs::read_to_string(artifact)?)?;
+        let source_name = content["sourceName"].as_str().ok_or_else(|| {
+            format!("Missing 'sourceName' field in artifact: {}", artifact.display())
+        })?;
+        let contract_name = content["contractName"].as_str().ok_or_else(|| {
+            format!("Missing 'contractName' field in artifact: {}", artifact.display())
+        })?;
+
+        let output = &build_info["output"]["contracts"][source_name][contract_name];
+        if output.is_null() {
+            let msg = format!(
+                "Contract {source_name}:{contract_name} not found in build info {}",
+                build_info_path.display()
+            );
+            return Err(msg.into())
+        }
+        Ok(output.clone())
+    }
+
+    /// Returns the ABI from a contract's solc output.
+    fn parse_abi(
+        output: &serde_json::Value,
+        artifact: &Path,
+    ) -> Result<LosslessAbi, Box<dyn Error>> {
+        let abi_value = output.get("abi").ok_or_else(|| {
+            format!("Missing 'abi' field in build info for {}", artifact.display())
+        })?;
+        Ok(serde_json::from_value(abi_value.clone())?)
+    }
+
+    /// Returns the deployed code, with any library placeholders zeroed, along with its immutable
+    /// references and link references from a contract's solc output.
+    fn parse_deployed_code(
+        output: &serde_json::Value,
+        artifact: &Path,
+    ) -> Result<(Bytes, ImmutableReferences, LinkReferences), Box<dyn Error>> {
+        let deployed_bytecode = &output["evm"]["deployedBytecode"];
+        let immutable_references = match &deployed_bytecode["immutableReferences"] {
+            serde_json::Value::Null => ImmutableReferences::new(),
+            references => serde_json::from_value(references.clone())?,
+        };
+        let link_references = match &deployed_bytecode["linkReferences"] {
+            serde_json::Value::Null => LinkReferences::new(),
+            references => serde_json::from_value(references.clone())?,
+        };
+        let object = deployed_bytecode["object"].as_str().ok_or_else(|| {
+            format!("Missing deployedBytecode 'object' field for {}", artifact.display())
+        })?;
+        let deployed_code = parse_unlinked_code(object, &link_references)?;
+        Ok((deployed_code, immutable_references, link_references))
+    }
+
+    /// Returns the metadata settings from a contract's solc output.
+    fn parse_metadata_settings(
+        output: &serde_json::Value,
+        artifact: &Path,
+    ) -> Result<SettingsMetadata, Box<dyn Error>> {
+        // Solc returns the metadata as a JSON string. Yul contracts have no metadata, so their code
+        // has no metadata hash and the entire code is compared exactly.
+        let Some(metadata) = output["metadata"].as_str().filter(|metadata| !metadata.is_empty())
+        else {
+            return Ok(SettingsMetadata::new(BytecodeHash::None, false))
+        };
+        let metadata: serde_json::Value = serde_json::from_str(metadata)?;
+        let settings_value = metadata.pointer("/settings/metadata").ok_or_else(|| {
+            format!("Missing 'settings.metadata' field in metadata for {}", artifact.display())
+        })?;
+        Ok(serde_json::from_value(settings_value.clone())?)
+    }
+
+    /// Returns the command that installs the project's npm dependencies, which include Hardhat
+    /// itself, or `None` if the project has no `package.json` or its dependencies are already
+    /// installed. Install scripts aren't run, since they aren't needed to compile.
+    pub fn install_command(&self) -> Option<Command> {
+        if !self.path.join("package.json").is_file() || self.path.join("node_modules").is_dir() {
+            return None
+        }
+        // A lockfile pins the versions the project was compiled with, including solc plugins.
+        let mut command = Command::new("npm");
+        let install = if self.path.join("package-lock.json").is_file() { "ci" } else { "install" };
+        command.current_dir(&self.path).arg(install).arg("--ignore-scripts").arg("--no-audit");
+        Some(command)
+    }
+
+    /// Parses hex encoded code from solc output, which has no `0x` prefix. Code with unlinked
+    /// library placeholders isn't valid hex, so contracts that use external libraries are only
+    /// compared by their deployed code, which is parsed with the placeholders zeroed.
+    fn parse_code(object: &serde_json::Value, artifact: &Path) -> Result<Bytes, Box<dyn Error>> {
+        let object = object
+            .as_str()
+            .ok_or_else(|| format!("Missing bytecode 'object' field for {}", artifact.display()))?;
+        Bytes::from_str(object).map_err(|_| "Linked libraries not yet supported".into())
+    }
+
+    /// Given all artifacts that were generated by compilation, filter out contracts that cannot be
+    /// the most-derived contract the user is interested in. Dependencies are installed in
+    /// `node_modules`, but their source names omit it, e.g. `@openzeppelin/contracts/...`, so only
+    /// contracts whose source is in the project are kept.
+    fn filter_artifacts(&self, artifacts: Vec<PathBuf>) -> Vec<PathBuf> {
+        artifacts
+            .into_iter()
+            .filter(|artifact| {
+                let Ok(content) = fs::read_to_string(artifact) else { return false };
+                let Ok(json) = serde_json::from_str::<serde_json::Value>(&content) else {
+                    return false
+                };
+                json["sourceName"].as_str().map_or(false, |source| self.path.join(source).is_file())
+            })
+            .collect()
+    }
+}
+
+impl Framework for Hardhat {
+    fn new(path: &Path) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        if !Self::is_supported(path) {
+            return Err("Not a hardhat project.".into())
+        }
+        Ok(Self { path: path.to_path_buf(), build_infos: Mutex::new(HashMap::new()) })
+    }
+
+    fn is_supported(path: &Path) -> bool {
+        CONFIG_FILES.iter().any(|name| path.join(name).is_file())
+    }
+
+    fn build_commands(
+        &self,
+        _hint: Option<String>,
+        toggle_optimizer: bool,
+    ) -> Result<Vec<BuildCommand>, Box<dyn Error>> {
+        // The optimizer is configured in the config file, which is code, so it can't be
+        // overridden for a single build.
+        if toggle_optimizer {
+            return Err("Toggling the optimizer is not supported for hardhat projects.".into())
+        }
+
+        // Hardhat has no build profiles, so there is a single build with the project's config.
+        let mut command = Command::new("npx");
+        command.current_dir(&self.path).arg("hardhat").arg("compile").arg("--quiet");
+        let variant = BuildVariant { profile: "default".to_string(), optimizer_override: None };
+        Ok(vec![BuildCommand { command, variant, out_dir: self.path.join("artifacts") }])
+    }
+
+    fn get_artifacts(
+        &self,
+        _variant: &BuildVariant,
+        out_dir: &Path,
+    ) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        let build_info_dir = out_dir.join("build-info");
+        let artifacts = WalkDir::new(out_dir)
+            .into_iter()
+            .filter_entry(|entry| entry.path() != build_info_dir)
+            .filter_map(Result::ok)
+            .filter(|entry| {
+                let name = entry.file_name().to_string_lossy();
+                entry.file_type().is_file()
+                    && name.ends_with(".json")
+                    && !name.ends_with(".dbg.json")
+            })
+            .map(|entry| entry.into_path())
+            .collect::<Vec<_>>();
+        Ok(self.filter_artifacts(artifacts))
+    }
+
+    fn structure_found_creation_code(
+        &self,
+        artifact: &Path,
+    ) -> Result<FoundCreationBytecode, Box<dyn Error>> {
+        let output = self.contract_output(artifact)?;
+        let metadata_settings = Self::parse_metadata_settings(&output, artifact)?;
+        let raw_code = Self::parse_code(&output["evm"]["bytecode"]["object"], artifact)?;
+        Ok(structure_found_creation_code(raw_code, &metadata_settings))
+    }
+
+    fn structure_expected_creation_code(
+        &self,
+        _artifact: &Path,
+        found: &FoundCreationBytecode,
+        expected: &Bytes,
+    ) -> Result<ExpectedCreationBytecode, Box<dyn Error>> {
+        structure_expected_creation_code(found, expected)
+    }
+
+    fn structure_found_deployed_code(
+        &self,
+        artifact: &Path,
+    ) -> Result<FoundDeployedBytecode, Box<dyn Error>> {
+        let output = self.contract_output(artifact)?;
+        let metadata_settings = Self::parse_metadata_settings(&output, artifact)?;
+        let (raw_code, immutable_references, link_references) =
+            Self::parse_deployed_code(&output, artifact)?;
+        Ok(structure_found_deployed_code(
+            raw_code,
+            immutable_references,
+            link_references,
+            &metadata_settings,
+        ))
+    }
+
+    fn structure_expected_deployed_code(
+        &self,
+        found: &FoundDeployedBytecode,
+        expected: &Bytes,
+    ) -> Result<ExpectedDeployedBytecode, Box<dyn Error>> {
+        structure_expected_deployed_code(found, expected)
+    }
+
+    fn get_artifact_abi(artifact: &Path) -> Result<LosslessAbi, Box<dyn Error>> {
+        Self::parse_abi(&Self::read_contract_output(artifact)?, artifact)
+    }
+
+    fn get_artifact_creation_code(artifact: &Path) -> Result<Bytes, Box<dyn Error>> {
+        let output = Self::read_contract_output(artifact)?;
+        Self::parse_code(&output["evm"]["bytecode"]["object"], artifact)
+    }
+
+    fn get_artifact_deployed_code(
+        artifact: &Path,
+    ) -> Result<(Bytes, ImmutableReferences, LinkReferences), Box<dyn Error>> {
+        Self::parse_deployed_code(&Self::read_contract_output(artifact)?, artifact)
+    }
+
+    fn get_artifact_metadata_settings(artifact: &Path) -> Result<SettingsMetadata, Box<dyn Error>> {
+        Self::parse_metadata_settings(&Self::read_contract_output(artifact)?, artifact)
+    }
+
+    fn get_verified_artifact(
+        &self,
+        artifact: &Path,
+    ) -> Result<ConfigurableContractArtifact, Box<dyn Error>> {
+        // Solc returns the metadata as a JSON string, while forge artifacts also contain it parsed.
+        let output = self.contract_output(artifact)?;
+        let raw_metadata = output["metadata"].as_str().filter(|metadata| !metadata.is_empty());
+        let metadata = raw_metadata.map(serde_json::from_str::<serde_json::Value>).transpose()?;
+        let forge_artifact = serde_json::json!({
+            "abi": output["abi"],
+            "bytecode": output["evm"]["bytecode"],
+            "deployedBytecode": output["evm"]["deployedBytecode"],
+            "rawMetadata": raw_metadata,
+            "metadata": metadata,
+        });
+        Ok(serde_json::from_value(forge_artifact)?)
+    }
+
+    fn get_artifact_sources(&self, artifact: &Path) -> Result<ArtifactSources, Box<dyn Error>> {
+        let (build_info, build_info_path) = self.build_info(artifact)?;
+        let input = build_info["input"].clone();
+        let sources = input["sources"].as_object().ok_or_else(|| {
+            format!("Missing 'input.sources' field in build info {}", build_info_path.display())
+        })?;
+        let contents = sources
+            .iter()
+            .filter_map(|(path, source)| {
+                Some((PathBuf::from(path), source["content"].as_str()?.to_string()))
+            })
+            .collect();
+        Ok(ArtifactSources { contents, standard_json_input: Some(input) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bytecode::MetadataInfo;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    /// Writes a Hardhat project containing `contracts/Counter.sol`, compiled to the given solc
+    /// output for `Counter`, and returns the project and the path to the `Counter` artifact.
+    fn create_test_project(
+        contract_output: &serde_json::Value,
+    ) -> Result<(TempDir, PathBuf), Box<dyn Error>> {
+        let project = TempDir::new()?;
+        fs::write(project.path().join("hardhat.config.ts"), "export default {};")?;
+        fs::create_dir_all(project.path().join("contracts"))?;
+        fs::write(project.path().join("contracts/Counter.sol"), "contract Counter {}")?;
+
+        let artifacts_dir = project.path().join("artifacts");
+        let build_info_dir = artifacts_dir.join("build-info");
+        fs::create_dir_all(&build_info_dir)?;
+        let build_info = json!({
+            "_format": "hh-sol-build-info-1",
+            "solcVersion": "0.8.19",
+            "input": { "language": "Solidity", "sources": {} },
+            "output": { "contracts": { "contracts/Counter.sol": { "Counter": contract_output } } },
+        });
+        fs::write(build_info_dir.join("abc123.json"), build_info.to_string())?;
+
+        let contract_dir = artifacts_dir.join("contracts/Counter.sol");
+        fs::create_dir_all(&contract_dir)?;
+        let artifact = json!({
+            "_format": "hh-sol-artifact-1",
+            "contractName": "Counter",
+            "sourceName": "contracts/Counter.sol",
+            "abi": [],
+            "bytecode": "0x",
+            "deployedBytecode": "0x",
+        });
+        let artifact_path = contract_dir.join("Counter.json");
+        fs::write(&artifact_path, artifact.to_string())?;
+        let dbg = json!({ "_format": "hh-sol-dbg-1", "buildInfo": "../../build-info/abc123.json" });
+        fs::write(contract_dir.join("Counter.dbg.json"), dbg.to_string())?;
+        Ok((project, artifact_path))
+    }
+
+    #[test]
+    fn test_is_supported() -> Result<(), Box<dyn Error>> {
+        for config_file in CONFIG_FILES {
+            let project = TempDir::new()?;
+            assert!(!Hardhat::is_supported(project.path()));
+            fs::write(project.path().join(config_file), "")?;
+            assert!(Hardhat::is_supported(project.path()), "{config_file}");
+        }
+
+        // A directory with a config file's name isn't a config file.
+        let project = TempDir::new()?;
+        fs::create_dir(project.path().join("hardhat.config.js"))?;
+        assert!(!Hardhat::is_supported(project.path()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_structure_found_creation_code() -> Result<(), Box<dyn Error>> {
+        struct TestCase {
+            content: serde_json::Value,
+            expected: FoundCreationBytecode,
+        }
+
+        let test_cases = vec![
+            // Test case 1: BytecodeHash::None and appendCBOR = false
+            TestCase {
+                content: json!({
+                    "evm": { "bytecode": { "object": "1234" } },
+                    "metadata": r#"{"settings":{"metadata":{"bytecodeHash":"none","appendCBOR":false}}}"#,
+                }),
+                expected: FoundCreationBytecode {
+                    raw_code: Bytes::from_str("0x1234")?,
+                    leading_code: Bytes::from_str("0x1234")?,
+                    metadata: MetadataInfo::default(),
+                },
+            },
+            // Test case 2: BytecodeHash::Ipfs and appendCBOR = true
+            TestCase {
+                content: json!({
+                    "evm": { "bytecode": { "object": "1234a164736f6c6343000706000a" } },
+                    "metadata": r#"{"settings":{"metadata":{"bytecodeHash":"ipfs","appendCBOR":true}}}"#,
+                }),
+                expected: FoundCreationBytecode {
+                    raw_code: Bytes::from_str("0x1234a164736f6c6343000706000a")?,
+                    leading_code: Bytes::from_str("0x1234")?,
+                    metadata: MetadataInfo {
+                        hash: Some(Bytes::from_str("0xa164736f6c6343000706000a")?),
+                        start_index: Some(2),
+                        end_index: Some(14),
+                    },
+                },
+            },
+        ];
+
+        for test_case in test_cases {
+            let (project, artifact) = create_test_project(&test_case.content)?;
+            let hardhat = Hardhat::new(project.path()).unwrap();
+            let result = hardhat.structure_found_creation_code(&artifact)?;
+            assert_eq!(result, test_case.expected);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_artifact_abi() -> Result<(), Box<dyn Error>> {
+        let content = json!({ "abi": [ { "inputs": [ { "internalType": "uint256", "name": "initialNumber", "type": "uint256" } ], "stateMutability": "nonpayable", "type": "constructor" }, { "inputs": [], "name": "increment", "outputs": [], "stateMutability": "nonpayable", "type": "function" } ] });
+        let (_project, artifact) = create_test_project(&content)?;
+        let abi = Hardhat::get_artifact_abi(&artifact)?;
+        assert_eq!(abi.abi.functions.len(), 1);
+        assert!(abi.abi.constructor.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_artifact_code() -> Result<(), Box<dyn Error>> {
+        let content = json!({
+            "evm": {
+                "bytecode": { "object": "60806040" },
+                "deployedBytecode": {
+                    "object": "6080604052",
+                    "immutableReferences": { "3": [ { "start": 1, "length": 32 } ] },
+                },
+            },
+        });
+        let (_project, artifact) = create_test_project(&content)?;
+        assert_eq!(Hardhat::get_artifact_creation_code(&artifact)?, Bytes::from_str("0x60806040")?);
+        let (deployed_code, immutable_references, _) =
+            Hardhat::get_artifact_deployed_code(&artifact)?;
+        assert_eq!(deployed_code, Bytes::from_str("0x6080604052")?);
+        assert_eq!(immutable_references["3"].len(), 1);
+
+        // Code with unlinked library placeholders can't be compared.
+        let content = json!({
+            "evm": { "bytecode": { "object": "73__$a1b2c3d4e5f6a1b2c3d4e5f6a1b2c3d4e5$__" } },
+        });
+        let (_project, artifact) = create_test_project(&content)?;
+        assert!(Hardhat::get_artifact_creation_code(&artifact).is_err());
+
+        // Deployed code with unlinked library placeholders is parsed with the placeholders zeroed.
+        let content = json!({
+            "evm": {
+                "deployedBytecode": {
+                    "object": "73__$a1b2c3d4e5f6a1b2c3d4e5f6a1b2c3d4e5$__6000",
+                    "linkReferences": {
+                        "contracts/Lib.sol": { "Lib": [ { "start": 1, "length": 20 } ] },
+                    },
+                },
+            },
+        });
+        let (_project, artifact) = create_test_project(&content)?;
+        let (deployed_code, _, link_references) = Hardhat::get_artifact_deployed_code(&artifact)?;
+        assert_eq!(deployed_code, Bytes::from_str(&format!("0x73{}6000", "00".repeat(20)))?);
+        assert_eq!(link_references["contracts/Lib.sol"]["Lib"].len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_artifact_metadata_settings() -> Result<(), Box<dyn Error>> {
+        struct TestCase {
+            content: serde_json::Value,
+            expected: SettingsMetadata,
+        }
+
+        let test_cases = vec![
+            // Test case 1: Both `bytecodeHash` and `appendCBOR` fields are present.
+            TestCase {
+                content: json!({ "metadata": r#"{"settings":{"metadata":{"bytecodeHash":"ipfs","appendCBOR":true}}}"# }),
+                expected: SettingsMetadata {
+                    use_literal_content: None,
+                    bytecode_hash: Some(BytecodeHash::Ipfs),
+                    cbor_metadata: Some(true),
+                },
+            },
+            // Test case 2: `bytecodeHash` field is present, `appendCBOR` field is missing.
+            TestCase {
+                content: json!({ "metadata": r#"{"settings":{"metadata":{"bytecodeHash":"bzzr1"}}}"# }),
+                expected: SettingsMetadata {
+                    use_literal_content: None,
+                    bytecode_hash: Some(BytecodeHash::Bzzr1),
+                    cbor_metadata: None,
+                },
+            },
+            // Test case 3: Yul contracts have no metadata.
+            TestCase {
+                content: json!({ "metadata": "" }),
+                expected: SettingsMetadata::new(BytecodeHash::None, false),
+            },
+        ];
+
+        for test_case in test_cases {
+            let (_project, artifact) = create_test_project(&test_case.content)?;
+            let result = Hardhat::get_artifact_metadata_settings(&artifact)?;
+            assert_eq!(result, test_case.expected);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_artifacts() -> Result<(), Box<dyn Error>> {
+        let (project, artifact) = create_test_project(&json!({}))?;
+        let artifacts_dir = project.path().join("artifacts");
+
+        // Dependencies are compiled from `node_modules`, so their sources aren't in the project.
+        let dependency_dir = artifacts_dir.join("@openzeppelin/contracts/access/Ownable.sol");
+        fs::create_dir_all(&dependency_dir)?;
+        let dependency = json!({
+            "contractName": "Ownable",
+            "sourceName": "@openzeppelin/contracts/access/Ownable.sol",
+        });
+        fs::write(dependency_dir.join("Ownable.json"), dependency.to_string())?;
+
+        let hardhat = Hardhat::new(project.path()).unwrap();
+        let variant = BuildVariant { profile: "default".to_string(), optimizer_override: None };
+        assert_eq!(hardhat.get_artifacts(&variant, &artifacts_dir)?, vec![artifact]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_info_is_parsed_once() -> Result<(), Box<dyn Error>> {
+        let content = json!({ "evm": { "bytecode": { "object": "60806040" } } });
+        let (project, artifact) = create_test_project(&content)?;
+        let hardhat = Hardhat::new(project.path()).unwrap();
+        let expected = hardhat.structure_found_creation_code(&artifact)?;
+
+        // Once read, the build info is reused rather than read from disk again.
+        fs::remove_dir_all(project.path().join("artifacts/build-info"))?;
+        assert_eq!(hardhat.structure_found_creation_code(&artifact)?, expected);
+        assert!(Hardhat::get_artifact_creation_code(&artifact).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_install_command() -> Result<(), Box<dyn Error>> {
+        let (project, _) = create_test_project(&json!({}))?;
+        let hardhat = Hardhat::new(project.path()).unwrap();
+        assert!(hardhat.install_command().is_none());
+
+        fs::write(project.path().join("package.json"), "{}")?;
+        let command = hardhat.install_command().unwrap();
+        assert_eq!(command.get_args().next().unwrap(), "install");
+        fs::write(project.path().join("package-lock.json"), "{}")?;
+        let command = hardhat.install_command().unwrap();
+        assert_eq!(command.get_args().next().unwrap(), "ci");
+
+        // Dependencies that are already installed aren't installed again.
+        fs::create_dir(project.path().join("node_modules"))?;
+        assert!(hardhat.install_command().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_commands() -> Result<(), Box<dyn Error>> {
+        let (project, _) = create_test_project(&json!({}))?;
+        let hardhat = Hardhat::new(project.path()).unwrap();
+
+        let commands = hardhat.build_commands(None, false)?;
+        assert_eq!(commands.len(), 1);
+        assert_eq!(commands[0].out_dir, project.path().join("artifacts"));
+        assert!(hardhat.build_commands(None, true).is_err());
+        Ok(())
+    }
+}
@@ -3,3 +3,9 @@ pub mod framework;
 
 /// Defines the `Foundry` struct which implements the `Framework` trait.
 pub mod foundry;
+
+/// Defines the `Hardhat` struct which implements the `Framework` trait.
+pub mod hardhat;
+
+/// Defines the `Ape` struct which implements the `Framework` trait.
+pub mod ape;
@@ -0,0 +1,287 @@
+use data_encoding::BASE32_NOPAD;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    path::{Component, Path},
+};
+
+/// The size of the chunks files are split into, matching the default IPFS chunker.
+const CHUNK_SIZE: usize = 262_144;
+/// The maximum number of links in a file node, matching the default IPFS balanced layout.
+const MAX_LINKS: usize = 174;
+/// The multicodec code for raw binary blocks.
+const RAW_CODEC: u64 = 0x55;
+/// The multicodec code for dag-pb (MerkleDAG protobuf) blocks.
+const DAG_PB_CODEC: u64 = 0x70;
+/// The multihash code for sha2-256.
+const SHA2_256: u64 = 0x12;
+/// The UnixFS data type of directory nodes.
+const UNIXFS_DIRECTORY: u64 = 1;
+/// The UnixFS data type of file nodes.
+const UNIXFS_FILE: u64 = 2;
+
+/// A block that has been hashed into a CID, along with the sizes needed to link to it.
+struct Block {
+    /// The binary CID of the block.
+    cid: Vec<u8>,
+    /// The size of the block plus the sizes of all blocks it links to, used as a link's `Tsize`.
+    tree_size: u64,
+    /// The number of file bytes the block represents.
+    file_size: u64,
+}
+
+/// An entry in a directory being built from source paths.
+enum Entry<'a> {
+    /// A file with the given content.
+    File(&'a [u8]),
+    /// A subdirectory with the given entries, keyed by name.
+    Directory(BTreeMap<String, Entry<'a>>),
+}
+
+/// Returns the CIDv1 that IPFS assigns to a file with the given content when it is added with
+/// `ipfs add --cid-version=1`. Files that fit in a single chunk are a raw block, and larger files
+/// are a dag-pb UnixFS file linking to their raw chunks.
+pub fn file_cid(content: &[u8]) -> String {
+    encode_cid(&file_block(content).cid)
+}
+
+/// Returns the sha2-256 multihash of a metadata file as solc embeds it in the `ipfs` metadata hash,
+/// which is the CIDv0 IPFS assigns to the file when it is added with `ipfs add`. Unlike CIDv1
+/// files, the content is wrapped in a dag-pb UnixFS node even when it fits in a single chunk.
+/// Metadata only exceeds a chunk when sources are embedded with `useLiteralContent`, and since
+/// solc's chunking of larger files isn't reproduced here, `None` is returned for those.
+pub fn metadata_multihash(content: &[u8]) -> Option<Vec<u8>> {
+    if content.len() > CHUNK_SIZE {
+        return None
+    }
+    let mut data = Vec::new();
+    put_varint_field(&mut data, 1, UNIXFS_FILE);
+    if !content.is_empty() {
+        put_bytes_field(&mut data, 2, content);
+    }
+    put_varint_field(&mut data, 3, content.len() as u64);
+    let mut node = Vec::new();
+    put_bytes_field(&mut node, 1, &data);
+    Some(multihash(&node))
+}
+
+/// Returns the CIDv1 of the dag-pb block with the given multihash, such as the multihash solc
+/// embeds in the `ipfs` metadata hash. This identifies the same content as the CIDv0 that solc's
+/// multihash represents, so it can be used to fetch the metadata file from IPFS.
+pub fn multihash_cid(multihash: &[u8]) -> String {
+    let mut cid = Vec::new();
+    put_varint(&mut cid, 1);
+    put_varint(&mut cid, DAG_PB_CODEC);
+    cid.extend_from_slice(multihash);
+    encode_cid(&cid)
+}
+
+/// Returns the CIDv1 of a UnixFS directory containing the given files at their paths, which is
+/// the CID IPFS assigns to the directory when it is added with `ipfs add -r --cid-version=1`.
+/// Paths are relative to the directory, and intermediate directories are created as needed.
+pub fn directory_cid<'a>(files: impl IntoIterator<Item = (&'a Path, &'a [u8])>) -> String {
+    let mut root = BTreeMap::new();
+    for (path, content) in files {
+        let names: Vec<String> = path
+            .components()
+            .filter_map(|component| match component {
+                Component::Normal(name) => Some(name.to_string_lossy().to_string()),
+                _ => None,
+            })
+            .collect();
+        let Some((file_name, dir_names)) = names.split_last() else { continue };
+
+        let mut dir = &mut root;
+        for name in dir_names {
+            let entry =
+                dir.entry(name.clone()).or_insert_with(|| Entry::Directory(BTreeMap::new()));
+            // If a path is used as both a file and a directory, the directory takes precedence.
+            if let Entry::File(_) = entry {
+                *entry = Entry::Directory(BTreeMap::new());
+            }
+            let Entry::Directory(entries) = entry else { unreachable!() };
+            dir = entries;
+        }
+        dir.insert(file_name.clone(), Entry::File(content));
+    }
+    encode_cid(&directory_block(&root).cid)
+}
+
+/// Builds the root block of a file, chunking it and arranging the chunks in a balanced tree.
+fn file_block(content: &[u8]) -> Block {
+    let mut blocks: Vec<Block> = content
+        .chunks(CHUNK_SIZE)
+        .map(|chunk| Block {
+            cid: cid(RAW_CODEC, chunk),
+            tree_size: chunk.len() as u64,
+            file_size: chunk.len() as u64,
+        })
+        .collect();
+    if blocks.is_empty() {
+        blocks.push(Block { cid: cid(RAW_CODEC, &[]), tree_size: 0, file_size: 0 });
+    }
+
+    // Group each level of blocks under parent nodes until only the root remains.
+    while blocks.len() > 1 {
+        blocks = blocks.chunks(MAX_LINKS).map(file_node).collect();
+    }
+    blocks.pop().expect("a file always has at least one block")
+}
+
+/// Builds a dag-pb UnixFS file node linking to the given blocks.
+fn file_node(children: &[Block]) -> Block {
+    let file_size = children.iter().map(|child| child.file_size).sum();
+    let mut data = Vec::new();
+    put_varint_field(&mut data, 1, UNIXFS_FILE);
+    put_varint_field(&mut data, 3, file_size);
+    for child in children {
+        put_varint_field(&mut data, 4, child.file_size);
+    }
+    let links = children.iter().map(|child| ("", child));
+    dag_pb_block(links, &data, file_size)
+}
+
+/// Builds a dag-pb UnixFS directory node for the given entries.
+fn directory_block(entries: &BTreeMap<String, Entry>) -> Block {
+    let children: Vec<(&str, Block)> = entries
+        .iter()
+        .map(|(name, entry)| {
+            let block = match entry {
+                Entry::File(content) => file_block(content),
+                Entry::Directory(entries) => directory_block(entries),
+            };
+            (name.as_str(), block)
+        })
+        .collect();
+    let mut data = Vec::new();
+    put_varint_field(&mut data, 1, UNIXFS_DIRECTORY);
+    let links = children.iter().map(|(name, block)| (*name, block));
+    dag_pb_block(links, &data, 0)
+}
+
+/// Encodes a dag-pb node with the given named links and UnixFS data, and returns it as a block.
+/// Links must already be sorted by name, and are encoded before the data as dag-pb requires.
+fn dag_pb_block<'a>(
+    links: impl Iterator<Item = (&'a str, &'a Block)>,
+    data: &[u8],
+    file_size: u64,
+) -> Block {
+    let mut node = Vec::new();
+    let mut tree_size = 0;
+    for (name, child) in links {
+        let mut link = Vec::new();
+        put_bytes_field(&mut link, 1, &child.cid);
+        put_bytes_field(&mut link, 2, name.as_bytes());
+        put_varint_field(&mut link, 3, child.tree_size);
+        put_bytes_field(&mut node, 2, &link);
+        tree_size += child.tree_size;
+    }
+    put_bytes_field(&mut node, 1, data);
+    Block { cid: cid(DAG_PB_CODEC, &node), tree_size: tree_size + node.len() as u64, file_size }
+}
+
+/// Returns the binary CIDv1 of the given block data using a sha2-256 multihash.
+fn cid(codec: u64, data: &[u8]) -> Vec<u8> {
+    let mut cid = Vec::new();
+    put_varint(&mut cid, 1);
+    put_varint(&mut cid, codec);
+    cid.extend_from_slice(&multihash(data));
+    cid
+}
+
+/// Returns the sha2-256 multihash of the given data.
+fn multihash(data: &[u8]) -> Vec<u8> {
+    let digest = Sha256::digest(data);
+    let mut multihash = Vec::new();
+    put_varint(&mut multihash, SHA2_256);
+    put_varint(&mut multihash, digest.len() as u64);
+    multihash.extend_from_slice(&digest);
+    multihash
+}
+
+/// Encodes a binary CID as a string using the default base32 multibase encoding.
+fn encode_cid(cid: &[u8]) -> String {
+    format!("b{}", BASE32_NOPAD.encode(cid).to_lowercase())
+}
+
+/// Appends an unsigned LEB128 varint, as used by both multiformats and protobuf.
+fn put_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+/// Appends a protobuf varint field.
+fn put_varint_field(buf: &mut Vec<u8>, field: u64, value: u64) {
+    put_varint(buf, field << 3);
+    put_varint(buf, value);
+}
+
+/// Appends a protobuf length-delimited field.
+fn put_bytes_field(buf: &mut Vec<u8>, field: u64, value: &[u8]) {
+    put_varint(buf, field << 3 | 2);
+    put_varint(buf, value.len() as u64);
+    buf.extend_from_slice(value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_cid() {
+        assert_eq!(file_cid(b""), "bafkreihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku");
+        assert_eq!(
+            file_cid(b"hello world"),
+            "bafkreifzjut3te2nhyekklss27nh3k72ysco7y32koao5eei66wof36n5e"
+        );
+
+        // Files larger than a chunk are dag-pb nodes rather than raw blocks.
+        let large = vec![0u8; CHUNK_SIZE + 1];
+        assert!(file_cid(&large).starts_with("bafybei"));
+    }
+
+    #[test]
+    fn test_metadata_multihash() {
+        // These are `Qmf412jQZiuVUtdgnB36FXFX7xg5V6KEbSJ4dpQuhkLyfD` and
+        // `QmbFMke1KXqnYyBBWxB74N4c5SBnJMVAiMNRcGu6x1AwQH` when base58 encoded as a CIDv0.
+        let multihash = metadata_multihash(b"hello world").unwrap();
+        assert_eq!(
+            data_encoding::HEXLOWER.encode(&multihash),
+            "1220f852c7fa62f971817f54d8a80dcd63fcf7098b3cbde9ae8ec1ee449013ec5db0"
+        );
+        let multihash = metadata_multihash(b"").unwrap();
+        assert_eq!(
+            data_encoding::HEXLOWER.encode(&multihash),
+            "1220bfccda787baba32b59c78450ac3d20b633360b43992c77289f9ed46d843561e6"
+        );
+        assert!(metadata_multihash(&vec![0u8; CHUNK_SIZE + 1]).is_none());
+    }
+
+    #[test]
+    fn test_multihash_cid() {
+        // The CIDv1 of `QmbFMke1KXqnYyBBWxB74N4c5SBnJMVAiMNRcGu6x1AwQH`, the empty file above.
+        let multihash = metadata_multihash(b"").unwrap();
+        assert_eq!(
+            multihash_cid(&multihash),
+            "bafybeif7ztnhq65lumvvtr4ekcwd2ifwgm3awq4zfr3srh462rwyinlb4y"
+        );
+    }
+
+    #[test]
+    fn test_directory_cid() {
+        let empty: [(&Path, &[u8]); 0] = [];
+        assert_eq!(
+            directory_cid(empty),
+            "bafybeiczsscdsbs7ffqz55asqdf3smv6klcw3gofszvwlyarci47bgf354"
+        );
+
+        // The CID only depends on the paths and contents, not the order the files are given in.
+        let a = (Path::new("src/A.sol"), b"contract A {}".as_slice());
+        let b = (Path::new("lib/B.sol"), b"contract B {}".as_slice());
+        assert_eq!(directory_cid([a, b]), directory_cid([b, a]));
+        assert_ne!(directory_cid([a, b]), directory_cid([a]));
+    }
+}
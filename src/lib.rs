@@ -1,23 +1,70 @@
 #![doc = include_str!("../README.md")]
 
+/// Summarizes the functions, events, and custom errors of a contract's ABI.
+pub mod abi;
+
+/// Caches build output so verifying another contract from an already built codebase skips
+/// compilation.
+pub mod artifact_cache;
+
+/// Authenticates requests to the verify endpoints with HMAC signatures.
+pub mod auth;
+
 /// Contains methods and types for analyzing and comparing bytecode.
 pub mod bytecode;
 
+/// Tracks RPC failures per chain so chains whose RPCs are repeatedly failing are temporarily
+/// skipped.
+pub mod circuit_breaker;
+
 /// Handles all app configuration.
 pub mod config;
 
+/// Decodes calldata sent to known CREATE2 factories, and to contracts that batch calls to them, to
+/// extract creation code without tracing.
+pub mod factory;
+
 /// Defines the `Framework` trait for abstracting over different development frameworks. Also
 /// contains an implementation for Foundry.
 pub mod frameworks;
 
+/// Computes IPFS CIDs locally so verified sources can be pinned and checked against a response.
+pub mod ipfs;
+
+/// Logs verification requests to an external store, such as Airtable.
+pub mod logger;
+
+/// Collects verification, build, clone, and RPC metrics and renders them for Prometheus.
+pub mod metrics;
+
 /// Contains methods and types for interacting with an Ethereum provider and comparing bytecode.
 pub mod provider;
 
+/// Retries RPC calls after transient failures, with exponential backoff and jitter.
+pub mod retry;
+
 /// Defines the handlers for all API routes.
 pub mod routes;
 
+/// Defines the JSON-RPC transport used by providers, including an in-memory transport for tests.
+pub mod rpc;
+
+/// Runs verification jobs on a bounded pool of workers with a bounded queue, rejecting jobs when
+/// the queue is full.
+pub mod scheduler;
+
 /// Handles the server startup, such as route configuration and middleware.
 pub mod startup;
 
+/// Stores successful verifications on the filesystem so they can be looked up later.
+pub mod store;
+
 /// Handles logs and tracing.
 pub mod telemetry;
+
+/// Detects the external binaries that verification depends on, such as `git` and `forge`.
+pub mod tools;
+
+/// Traces transactions to find contract deployments, using the trace RPC method supported by the
+/// chain.
+pub mod trace;
@@ -4,7 +4,10 @@
 //! ready for production use. See the repository [README](https://github.com/ScopeLift/cove-backend#readme)
 //! for more information on the current status. For more details, refer to individual module
 //! documentation.
-use cove::{config, startup, telemetry};
+use cove::{
+    config, startup, telemetry,
+    tools::{check_forge_version, ToolVersions},
+};
 use std::net::TcpListener;
 
 /// Entrypoint for the application.
@@ -13,9 +16,24 @@ async fn main() -> hyper::Result<()> {
     let subscriber = telemetry::get_subscriber("cove".into(), "info".into(), std::io::stdout);
     telemetry::init_subscriber(subscriber);
 
+    // Fail fast if a binary required for verification is missing, instead of failing every
+    // verification request with an opaque IO error.
+    let tools = ToolVersions::detect();
+    if let Err(err) = tools.ensure_installed() {
+        eprintln!("ERROR: {err}");
+        std::process::exit(1);
+    }
+    println!("Found {}", tools.git.unwrap_or_default());
+    println!("Found {}", tools.forge.as_deref().unwrap_or_default());
+
     let configuration = config::get_configuration().expect("Failed to read configuration.");
+    let allowed_forge_versions = &configuration.verification.allowed_forge_versions;
+    if let Err(err) = check_forge_version(tools.forge.as_deref(), allowed_forge_versions) {
+        eprintln!("ERROR: {err}");
+        std::process::exit(1);
+    }
     let address = format!("{}:{}", configuration.application.host, configuration.application.port);
     println!("Listening on {}", address);
     let listener = TcpListener::bind(address).expect("Unable to bind to port");
-    startup::run(listener)?.await
+    startup::run_until_shutdown(listener, configuration, startup::shutdown_signal()).await
 }
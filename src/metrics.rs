@@ -0,0 +1,261 @@
+use crate::scheduler::SchedulerMetrics;
+use ethers::types::Chain;
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{Mutex, MutexGuard},
+    time::Duration,
+};
+
+/// The upper bounds, in seconds, of the buckets that durations are counted in. These span quick
+/// RPC calls up to builds that hit the default build timeout.
+pub const DURATION_BUCKETS: [f64; 13] =
+    [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0];
+
+/// The build profiles that label build durations. Profile names come from the verified projects,
+/// so any other profile is labeled `other` to keep the number of label values bounded.
+const BUILD_PROFILE_LABELS: [&str; 6] =
+    ["default", "ci", "lite", "optimized", "production", "via_ir"];
+
+/// The outcome of a verification request, used to label the verification counter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum VerificationOutcome {
+    /// The contract was verified.
+    Success,
+    /// The contract was built and compared, but no artifact matched the on-chain code or the
+    /// match didn't satisfy the request's requirements.
+    NoMatch,
+    /// The request was rejected with a `BadRequest` error.
+    BadRequest,
+    /// The request was rejected with a `NotImplemented` error.
+    NotImplemented,
+    /// The verification failed with an `InternalServerError` error.
+    InternalServerError,
+    /// The request was rejected with a `ServiceUnavailable` error because the queue was full.
+    ServiceUnavailable,
+}
+
+impl VerificationOutcome {
+    /// Returns the value of the `outcome` label for this outcome.
+    pub fn label(&self) -> &'static str {
+        match self {
+            VerificationOutcome::Success => "success",
+            VerificationOutcome::NoMatch => "no_match",
+            VerificationOutcome::BadRequest => "bad_request",
+            VerificationOutcome::NotImplemented => "not_implemented",
+            VerificationOutcome::InternalServerError => "internal_server_error",
+            VerificationOutcome::ServiceUnavailable => "service_unavailable",
+        }
+    }
+}
+
+/// The RPC operation whose latency is recorded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RpcOperation {
+    /// Fetching the deployed code of a contract.
+    DeployedCode,
+    /// Fetching the creation code of a contract, including discovering its creation transaction.
+    CreationCode,
+}
+
+impl RpcOperation {
+    /// Returns the value of the `operation` label for this operation.
+    pub fn label(&self) -> &'static str {
+        match self {
+            RpcOperation::DeployedCode => "deployed_code",
+            RpcOperation::CreationCode => "creation_code",
+        }
+    }
+}
+
+/// Counts observed durations in the buckets of `DURATION_BUCKETS`.
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    /// The number of observations less than or equal to each bucket's upper bound.
+    bucket_counts: [u64; DURATION_BUCKETS.len()],
+    /// The sum of all observations, in seconds.
+    sum: f64,
+    /// The number of observations.
+    count: u64,
+}
+
+impl Histogram {
+    /// Records a duration.
+    fn observe(&mut self, duration: Duration) {
+        let secs = duration.as_secs_f64();
+        for (bound, count) in DURATION_BUCKETS.iter().zip(self.bucket_counts.iter_mut()) {
+            if secs <= *bound {
+                *count += 1;
+            }
+        }
+        self.sum += secs;
+        self.count += 1;
+    }
+
+    /// Writes the histogram's samples in the Prometheus text format, with the given labels added
+    /// to every sample. `labels` is either empty or a comma-separated list of `name="value"` pairs.
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        let separator = if labels.is_empty() { "" } else { "," };
+        for (bound, count) in DURATION_BUCKETS.iter().zip(self.bucket_counts.iter()) {
+            let _ = writeln!(out, "{name}_bucket{{{labels}{separator}le=\"{bound}\"}} {count}");
+        }
+        let _ = writeln!(out, "{name}_bucket{{{labels}{separator}le=\"+Inf\"}} {}", self.count);
+        let labels = if labels.is_empty() { String::new() } else { format!("{{{labels}}}") };
+        let _ = writeln!(out, "{name}_sum{labels} {}", self.sum);
+        let _ = writeln!(out, "{name}_count{labels} {}", self.count);
+    }
+}
+
+/// Collects metrics about verifications since the server started, and renders them in the
+/// Prometheus text exposition format for the `/metrics` endpoint.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    /// The number of verification requests with each outcome.
+    verifications: Mutex<BTreeMap<VerificationOutcome, u64>>,
+    /// The duration of builds, keyed by the label of the build profile.
+    build_durations: Mutex<BTreeMap<&'static str, Histogram>>,
+    /// The duration of RPC operations, keyed by chain and operation.
+    rpc_durations: Mutex<BTreeMap<(String, RpcOperation), Histogram>>,
+    /// The duration of cloning repositories and checking out the requested commit.
+    clone_durations: Mutex<Histogram>,
+}
+
+impl Metrics {
+    /// Records the outcome of a verification request.
+    pub fn record_verification(&self, outcome: VerificationOutcome) {
+        *lock(&self.verifications).entry(outcome).or_default() += 1;
+    }
+
+    /// Records the duration of a build with the given profile, which is labeled `other` unless
+    /// it's one of `BUILD_PROFILE_LABELS`. Builds restored from the cache aren't recorded.
+    pub fn record_build(&self, profile: &str, duration: Duration) {
+        let label = BUILD_PROFILE_LABELS.into_iter().find(|label| *label == profile);
+        lock(&self.build_durations).entry(label.unwrap_or("other")).or_default().observe(duration);
+    }
+
+    /// Records the duration of an RPC operation on the given chain, including any retries.
+    pub fn record_rpc(&self, chain: Chain, operation: RpcOperation, duration: Duration) {
+        let key = (chain.to_string(), operation);
+        lock(&self.rpc_durations).entry(key).or_default().observe(duration);
+    }
+
+    /// Records the duration of cloning a repository and checking out the requested commit.
+    pub fn record_clone(&self, duration: Duration) {
+        lock(&self.clone_durations).observe(duration);
+    }
+
+    /// Renders the metrics, along with the given scheduler load, in the Prometheus text
+    /// exposition format.
+    pub fn render(&self, scheduler: &SchedulerMetrics) -> String {
+        let mut out = String::new();
+
+        write_header(&mut out, "cove_verifications_total", "counter", "Verifications by outcome.");
+        for (outcome, count) in lock(&self.verifications).iter() {
+            let outcome = outcome.label();
+            let _ = writeln!(out, "cove_verifications_total{{outcome=\"{outcome}\"}} {count}");
+        }
+
+        let name = "cove_build_duration_seconds";
+        write_header(&mut out, name, "histogram", "Duration of builds by profile.");
+        for (profile, histogram) in lock(&self.build_durations).iter() {
+            histogram.render(&mut out, name, &format!("profile=\"{profile}\""));
+        }
+
+        let name = "cove_rpc_duration_seconds";
+        write_header(&mut out, name, "histogram", "Duration of RPC operations by chain.");
+        for ((chain, operation), histogram) in lock(&self.rpc_durations).iter() {
+            let labels = format!("chain=\"{chain}\",operation=\"{}\"", operation.label());
+            histogram.render(&mut out, name, &labels);
+        }
+
+        let name = "cove_clone_duration_seconds";
+        write_header(&mut out, name, "histogram", "Duration of cloning repositories.");
+        lock(&self.clone_durations).render(&mut out, name, "");
+
+        let gauges = [
+            ("cove_scheduler_workers", "Verification workers.", scheduler.workers),
+            ("cove_scheduler_queue_capacity", "Queue capacity.", scheduler.queue_capacity),
+            ("cove_scheduler_queue_depth", "Queued verifications.", scheduler.queue_depth),
+            ("cove_scheduler_active_jobs", "Running verifications.", scheduler.active_jobs),
+        ];
+        for (name, help, value) in gauges {
+            write_header(&mut out, name, "gauge", help);
+            let _ = writeln!(out, "{name} {value}");
+        }
+        let name = "cove_scheduler_rejected_jobs_total";
+        write_header(&mut out, name, "counter", "Verifications rejected by a full queue.");
+        let _ = writeln!(out, "{name} {}", scheduler.rejected_jobs);
+        out
+    }
+}
+
+/// Writes the `HELP` and `TYPE` lines that precede a metric's samples.
+fn write_header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} {kind}");
+}
+
+/// Locks the mutex. Metrics are only updated by simple increments that can't leave them
+/// inconsistent, so a lock poisoned by a panicking thread is still used.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the scheduler load of an idle scheduler.
+    fn idle_scheduler() -> SchedulerMetrics {
+        SchedulerMetrics {
+            workers: 2,
+            queue_capacity: 8,
+            queue_depth: 0,
+            active_jobs: 0,
+            rejected_jobs: 0,
+        }
+    }
+
+    #[test]
+    fn test_render_counts_and_histograms() {
+        let metrics = Metrics::default();
+        metrics.record_verification(VerificationOutcome::Success);
+        metrics.record_verification(VerificationOutcome::Success);
+        metrics.record_verification(VerificationOutcome::BadRequest);
+        metrics.record_build("default", Duration::from_millis(700));
+        metrics.record_build("default", Duration::from_secs(20));
+        metrics.record_rpc(Chain::Mainnet, RpcOperation::DeployedCode, Duration::from_millis(80));
+        metrics.record_clone(Duration::from_secs(3));
+
+        let rendered = metrics.render(&idle_scheduler());
+        let has = |line: &str| rendered.lines().any(|rendered| rendered == line);
+        assert!(has(r#"cove_verifications_total{outcome="success"} 2"#));
+        assert!(has(r#"cove_verifications_total{outcome="bad_request"} 1"#));
+        assert!(has(r#"cove_build_duration_seconds_bucket{profile="default",le="0.5"} 0"#));
+        assert!(has(r#"cove_build_duration_seconds_bucket{profile="default",le="1"} 1"#));
+        assert!(has(r#"cove_build_duration_seconds_bucket{profile="default",le="30"} 2"#));
+        assert!(has(r#"cove_build_duration_seconds_count{profile="default"} 2"#));
+        assert!(has(
+            r#"cove_rpc_duration_seconds_bucket{chain="mainnet",operation="deployed_code",le="0.1"} 1"#
+        ));
+        assert!(has(r#"cove_clone_duration_seconds_bucket{le="2.5"} 0"#));
+        assert!(has(r#"cove_clone_duration_seconds_bucket{le="+Inf"} 1"#));
+        assert!(has("cove_clone_duration_seconds_sum 3"));
+        assert!(has("# TYPE cove_scheduler_workers gauge"));
+        assert!(has("cove_scheduler_workers 2"));
+        assert!(has("cove_scheduler_rejected_jobs_total 0"));
+    }
+
+    #[test]
+    fn test_build_profile_labels_are_bounded() {
+        let metrics = Metrics::default();
+        metrics.record_build("ci", Duration::from_secs(1));
+        metrics.record_build("my-profile-1", Duration::from_secs(1));
+        metrics.record_build("my-profile-2\"", Duration::from_secs(1));
+
+        let rendered = metrics.render(&idle_scheduler());
+        let has = |line: &str| rendered.lines().any(|rendered| rendered == line);
+        assert!(has(r#"cove_build_duration_seconds_count{profile="ci"} 1"#));
+        assert!(has(r#"cove_build_duration_seconds_count{profile="other"} 2"#));
+        assert!(!rendered.contains("my-profile"));
+    }
+}
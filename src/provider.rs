@@ -1,13 +1,44 @@
 use crate::{
-    bytecode::{creation_code_equality_check, deployed_code_equality_check, MatchType},
+    bytecode::{
+        creation_code_equality_check, deployed_code_equality_check, deployed_code_padding,
+        diff_report, is_minimal_proxy, BytecodeDiff, MatchType,
+    },
+    circuit_breaker::CircuitBreaker,
+    config::ChainSettings,
+    factory::known_deployer_creation_code,
     frameworks::framework::Framework,
+    metrics::{Metrics, RpcOperation},
+    retry::RetryPolicy,
+    rpc::RpcClient,
+    trace::{find_creation_tx_hash, trace_creation_code},
 };
 use ethers::{
-    providers::{Http, Middleware, Provider},
-    types::{Address, BlockNumber, Bytes, Chain, Transaction, TxHash, U256},
+    providers::{Http, Middleware, Provider, ProviderError},
+    types::{Address, BlockNumber, Bytes, Chain, Transaction, TransactionReceipt, TxHash, H256},
+    utils::get_create2_address,
 };
-use futures::future;
-use std::{collections::HashMap, env, error::Error, path::PathBuf, str::FromStr, sync::Arc};
+use futures::{future, stream, StreamExt};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    error::Error,
+    path::PathBuf,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+/// The error returned for chains that were skipped because their circuit breaker is open.
+const CHAIN_UNAVAILABLE: &str = "Chain temporarily unavailable due to repeated RPC failures.";
+
+/// The maximum number of transaction receipts fetched at once when searching a block for the
+/// transaction that deployed a contract.
+const MAX_CONCURRENT_RECEIPTS: usize = 16;
 
 /// Contract creation data.
 pub struct ContractCreation {
@@ -17,6 +48,47 @@ pub struct ContractCreation {
     pub block: BlockNumber,
     /// The creation code of the contract.
     pub creation_code: Bytes,
+    /// How the creation code was obtained.
+    pub source: CreationCodeSource,
+}
+
+/// An EIP-1167 minimal proxy whose implementation was verified in place of the proxy.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MinimalProxy {
+    /// The address of the implementation that the proxy delegates every call to.
+    pub implementation: Address,
+    /// The deployed code of the proxy, i.e. the 45 byte EIP-1167 template.
+    pub proxy_runtime_code: Bytes,
+}
+
+/// How the creation code of a contract was obtained.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CreationCodeSource {
+    /// Extracted from the input data of the provided CREATE transaction.
+    ProvidedTxHash,
+    /// Decoded from the calldata of a transaction sent to the named, known CREATE2 factory, or to
+    /// a known batching contract that called the factory.
+    KnownFactory(String),
+    /// Found by tracing the provided transaction.
+    Traced,
+    /// Built from the artifact's creation code and the constructor arguments provided with a
+    /// factory deployment.
+    ProvidedConstructorArgs,
+}
+
+/// A CREATE2 deployment made through a factory. Since the CREATE2 address commits to the init code,
+/// this lets creation code be verified without the creation transaction.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FactoryDeployment {
+    /// The address of the factory that deployed the contract.
+    pub factory: Address,
+    /// The salt passed to the factory.
+    pub salt: H256,
+    /// The ABI-encoded constructor arguments appended to the artifact's creation code to form the
+    /// init code.
+    #[serde(default)]
+    pub constructor_args: Bytes,
 }
 
 /// Match data for a given artifact.
@@ -26,79 +98,188 @@ pub struct ContractMatch {
     pub artifact: PathBuf,
     /// The type of match for that artifact against the expected code.
     pub match_type: MatchType,
+    /// Whether trailing zero padding of the deployed code was ignored to find this match. This is
+    /// always false for creation code matches.
+    pub padding_normalized: bool,
+    /// Where the found and expected deployed code differ, which is only set for partial matches of
+    /// deployed code.
+    pub deployed_code_diff: Option<BytecodeDiff>,
 }
 
 // ==============================
 // ======== Single Chain ========
 // ==============================
 
-/// Create a provider for the given chain.
-pub fn provider_from_chain(chain: Chain) -> Arc<Provider<Http>> {
-    match chain {
-        // Mainnet + Testnets.
-        Chain::Mainnet => {
-            Arc::new(Provider::<Http>::try_from(provider_url_from_chain(chain)).unwrap())
-        }
-        Chain::Goerli => {
-            Arc::new(Provider::<Http>::try_from(provider_url_from_chain(chain)).unwrap())
-        }
-        Chain::Sepolia => {
-            Arc::new(Provider::<Http>::try_from(provider_url_from_chain(chain)).unwrap())
-        }
-        // Other chains.
-        Chain::Optimism => {
-            Arc::new(Provider::<Http>::try_from(provider_url_from_chain(chain)).unwrap())
-        }
-        Chain::Arbitrum => {
-            Arc::new(Provider::<Http>::try_from(provider_url_from_chain(chain)).unwrap())
-        }
-        Chain::Polygon => {
-            Arc::new(Provider::<Http>::try_from(provider_url_from_chain(chain)).unwrap())
-        }
-        Chain::XDai => {
-            Arc::new(Provider::<Http>::try_from(provider_url_from_chain(chain)).unwrap())
-        }
-        Chain::Avalanche => {
-            Arc::new(Provider::<Http>::try_from(provider_url_from_chain(chain)).unwrap())
+/// The provider type used for all chains. The transport is HTTP when running the server, but can
+/// be an in-memory transport in tests.
+pub type ChainProvider = Provider<RpcClient>;
+
+/// Returned when a provider can't be created for a chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChainError {
+    /// The chain ID is not a chain known to the server.
+    UnknownChainId(u64),
+    /// The chain is not configured in the `chains` settings.
+    NotConfigured(Chain),
+    /// The chain's RPC URL is empty, e.g. because the environment variable it's read from is
+    /// unset.
+    MissingRpcUrl(Chain),
+    /// The chain's RPC URL could not be parsed.
+    InvalidRpcUrl(Chain, String),
+}
+
+impl std::fmt::Display for ChainError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChainError::UnknownChainId(id) => write!(f, "Chain ID {id} is not supported"),
+            ChainError::NotConfigured(chain) => write!(f, "Chain {chain} is not configured"),
+            ChainError::MissingRpcUrl(chain) => write!(f, "No RPC URL is set for chain {chain}"),
+            ChainError::InvalidRpcUrl(chain, err) => {
+                write!(f, "Invalid RPC URL for chain {chain}: {err}")
+            }
         }
-        _ => panic!("Unsupported chain"),
     }
 }
 
-/// Return the RPC provider URL for the given chain.
-pub fn provider_url_from_chain(chain: Chain) -> String {
-    match chain {
-        // Mainnet + Testnets.
-        Chain::Mainnet => env::var("MAINNET_RPC_URL").unwrap(),
-        Chain::Goerli => env::var("GOERLI_RPC_URL").unwrap(),
-        Chain::Sepolia => env::var("SEPOLIA_RPC_URL").unwrap(),
-        // Other chains.
-        Chain::Optimism => env::var("OPTIMISM_RPC_URL").unwrap(),
-        Chain::Arbitrum => env::var("ARBITRUM_ONE_RPC_URL").unwrap(),
-        Chain::Polygon => env::var("POLYGON_RPC_URL").unwrap(),
-        Chain::XDai => env::var("GNOSIS_CHAIN_RPC_URL").unwrap(),
-        Chain::Avalanche => env::var("AVALANCHE_RPC_URL").unwrap(),
-        _ => panic!("Unsupported chain"),
-    }
+impl Error for ChainError {}
+
+/// Returns the chain with the given ID, or an error if it's not a chain known to the server.
+pub fn chain_from_id(chain_id: u64) -> Result<Chain, ChainError> {
+    Chain::try_from(chain_id).map_err(|_| ChainError::UnknownChainId(chain_id))
 }
 
-/// Return the runtime code at the given address using the given provider.
-pub async fn contract_runtime_code(provider: &Arc<Provider<Http>>, address: Address) -> Bytes {
-    provider.get_code(address, None).await.unwrap()
+/// Create a provider for the given chain, using its RPC URL from the configured chains.
+pub fn provider_from_chain(
+    chain: Chain,
+    chains: &HashMap<u64, ChainSettings>,
+) -> Result<Arc<ChainProvider>, ChainError> {
+    http_provider(chain, provider_url_from_chain(chain, chains)?)
+}
+
+/// Create a provider that sends requests to the given RPC URL.
+fn http_provider(chain: Chain, url: String) -> Result<Arc<ChainProvider>, ChainError> {
+    let http =
+        Http::from_str(&url).map_err(|err| ChainError::InvalidRpcUrl(chain, err.to_string()))?;
+    Ok(Arc::new(Provider::new(RpcClient::Http(http))))
+}
+
+/// Return the RPC provider URL for the given chain from the configured chains.
+pub fn provider_url_from_chain(
+    chain: Chain,
+    chains: &HashMap<u64, ChainSettings>,
+) -> Result<String, ChainError> {
+    let settings = chains.get(&u64::from(chain)).ok_or(ChainError::NotConfigured(chain))?;
+    resolve_rpc_url(&settings.rpc_url, env_var).ok_or(ChainError::MissingRpcUrl(chain))
+}
+
+/// Returns the value of the environment variable with the given name, or `None` if it's unset.
+fn env_var(name: &str) -> Option<String> {
+    env::var(name).ok()
+}
+
+/// Returns the RPC URL for a configured value, which is either the URL itself or, if it starts
+/// with `$`, the name of the environment variable containing it, which is read with `env_var`.
+/// Returns `None` if the URL is empty or the environment variable is unset.
+fn resolve_rpc_url(value: &str, env_var: impl Fn(&str) -> Option<String>) -> Option<String> {
+    let url = match value.strip_prefix('$') {
+        Some(var) => env_var(var)?,
+        None => value.to_string(),
+    };
+    Some(url).filter(|url| !url.is_empty())
+}
+
+/// Return the runtime code at the given address using the given provider, retrying transient
+/// failures with the given policy.
+pub async fn contract_runtime_code(
+    provider: &Arc<ChainProvider>,
+    address: Address,
+    retry: &RetryPolicy,
+) -> Result<Bytes, ProviderError> {
+    retry.retry(|| provider.get_code(address, None)).await
 }
 
 // =============================
 // ======== Multi-Chain ========
 // =============================
 
+/// The status of querying a single chain for the code at an address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChainStatus {
+    /// The chain was queried successfully and code was found.
+    Ok,
+    /// The chain could not be queried, e.g. because its RPC is down. The contract may or may not
+    /// be deployed on this chain, so the caller can retry.
+    RpcError,
+    /// The chain was queried successfully but there is no code at the address.
+    NoCode,
+    /// The chain was not queried because its RPC has been failing repeatedly. The chain is
+    /// queried again once its cooldown has elapsed, so the caller can retry later.
+    Unavailable,
+}
+
 /// The response from a multi-chain provider's query.
 #[derive(Debug, Default)]
 pub struct ChainResponse<T> {
     /// A mapping from chain to the response for that chain.
     pub responses: HashMap<Chain, Option<T>>,
+    /// A mapping from chain to the error encountered while querying that chain. Chains that were
+    /// queried successfully are not present. The response for a chain with an error is `None`.
+    pub errors: HashMap<Chain, String>,
+    /// Chains that were not queried because their circuit breaker is open. These chains are also
+    /// present in `errors`.
+    pub unavailable: HashSet<Chain>,
 }
 
 impl<T> ChainResponse<T> {
+    /// Builds a response from the result of querying each chain, where `Ok(None)` means the query
+    /// succeeded but found nothing, and `Err` means the query failed.
+    pub fn from_results<E: ToString>(
+        results: impl IntoIterator<Item = (Chain, Result<Option<T>, E>)>,
+    ) -> Self {
+        let mut response =
+            Self { responses: HashMap::new(), errors: HashMap::new(), unavailable: HashSet::new() };
+        for (chain, result) in results {
+            match result {
+                Ok(value) => {
+                    response.responses.insert(chain, value);
+                }
+                Err(err) => {
+                    response.responses.insert(chain, None);
+                    response.errors.insert(chain, err.to_string());
+                }
+            }
+        }
+        response
+    }
+
+    /// Returns the status of each queried chain.
+    pub fn statuses(&self) -> HashMap<Chain, ChainStatus> {
+        self.responses
+            .iter()
+            .map(|(chain, value)| {
+                let status = match (value, self.errors.contains_key(chain)) {
+                    _ if self.unavailable.contains(chain) => ChainStatus::Unavailable,
+                    (Some(_), _) => ChainStatus::Ok,
+                    (None, true) => ChainStatus::RpcError,
+                    (None, false) => ChainStatus::NoCode,
+                };
+                (*chain, status)
+            })
+            .collect()
+    }
+
+    /// Returns the chains with the given status, sorted by chain ID.
+    pub fn chains_with_status(&self, status: ChainStatus) -> Vec<Chain> {
+        let mut chains: Vec<Chain> = self
+            .statuses()
+            .into_iter()
+            .filter(|(_, chain_status)| *chain_status == status)
+            .map(|(chain, _)| chain)
+            .collect();
+        chains.sort();
+        chains
+    }
+
     /// Returns `true` if all responses are `None`, `false` otherwise.
     pub fn is_all_none(&self) -> bool {
         self.responses.values().all(|value| value.is_none())
@@ -108,6 +289,17 @@ impl<T> ChainResponse<T> {
     pub fn iter_entries(&self) -> impl Iterator<Item = (&Chain, &T)> {
         self.responses.iter().filter_map(|(key, value)| value.as_ref().map(|v| (key, v)))
     }
+
+    /// Returns the response for the given chain, or `None` if the chain was not queried or there
+    /// was no response for it.
+    pub fn get(&self, chain: &Chain) -> Option<&T> {
+        self.responses.get(chain).and_then(Option::as_ref)
+    }
+
+    /// Consumes the response and returns a map of the `Some` entries, dropping the `None`s.
+    pub fn into_map(self) -> HashMap<Chain, T> {
+        self.responses.into_iter().filter_map(|(key, value)| value.map(|v| (key, v))).collect()
+    }
 }
 
 /// A provider that performs the same queries or operations across multiple chains simultaneously.
@@ -115,35 +307,234 @@ pub struct MultiChainProvider {
     /// The chains that this provider supports.
     pub chains: Vec<Chain>,
     /// The provider for each chain.
-    pub providers: HashMap<Chain, Arc<Provider<Http>>>,
+    pub providers: HashMap<Chain, Arc<ChainProvider>>,
+    /// The archive provider for chains that have one, which is queried for deployed code when the
+    /// primary provider has none, e.g. because it's a pruned node.
+    pub archive_providers: HashMap<Chain, Arc<ChainProvider>>,
+    /// Chains whose RPCs support JSON-RPC batch requests. Related requests to these chains are
+    /// batched to reduce the request count against rate-limited RPCs.
+    pub batched_chains: HashSet<Chain>,
+    /// Tracks RPC failures so chains whose RPCs are repeatedly failing are skipped. This is shared
+    /// across providers so failures are tracked across requests.
+    pub circuit_breaker: Option<Arc<CircuitBreaker>>,
+    /// The maximum number of trailing zero bytes ignored when comparing deployed code, for chains
+    /// that pad deployed code. Chains not in the map don't allow any padding.
+    pub deployed_code_padding: HashMap<Chain, usize>,
+    /// Whether transactions are traced to find creation code. When enabled, deployments by unknown
+    /// factories are traced, including when scanning a block for a contract's creation
+    /// transaction. Operators whose RPCs don't support tracing can disable it to avoid wasted
+    /// requests.
+    pub tracing_enabled: bool,
+    /// Chains whose RPCs support the Otterscan `ots_getContractCreator` method, which is used to
+    /// discover the creation transaction of a contract when no transaction hash is provided.
+    pub contract_creator_chains: HashSet<Chain>,
+    /// The maximum number of blocks back from the latest block that are binary searched for the
+    /// deployment block of a contract whose creation transaction can't be found otherwise. Each
+    /// step of the search queries the code at a block, so this requires an archive node. The
+    /// search is disabled when unset.
+    pub creation_search_max_blocks: Option<u64>,
+    /// How calls to each chain's RPC are retried after transient failures, so a rate limited or
+    /// timed out call isn't mistaken for a chain without the contract.
+    pub retry: RetryPolicy,
+    /// Records the latency of each chain's RPC operations, if set.
+    pub metrics: Option<Arc<Metrics>>,
 }
 
-impl Default for MultiChainProvider {
-    fn default() -> Self {
-        Self::new()
+impl MultiChainProvider {
+    /// Create a new `MultiChainProvider` for the given chains, keyed by chain ID, using each
+    /// chain's configured RPC URL and archive RPC URL. Chains whose RPC URL is read from an unset
+    /// environment variable are skipped with a warning, so one missing URL doesn't disable every
+    /// chain. Returns an error if a chain ID is unknown or an RPC URL is invalid.
+    pub fn new(chains: &HashMap<u64, ChainSettings>) -> Result<Self, ChainError> {
+        Self::new_with_env(chains, env_var)
     }
-}
 
-impl MultiChainProvider {
-    /// Create a new `MultiChainProvider` with all supported chains.
-    pub fn new() -> Self {
-        let chains = vec![
-            Chain::Arbitrum,
-            Chain::Goerli,
-            Chain::Mainnet,
-            Chain::Optimism,
-            Chain::Polygon,
-            Chain::Sepolia,
-            Chain::XDai,
-            Chain::Avalanche,
-        ];
+    /// Create a new `MultiChainProvider` like `new`, reading the environment variables that RPC
+    /// URLs refer to with `env_var` rather than from the process environment.
+    fn new_with_env(
+        chains: &HashMap<u64, ChainSettings>,
+        env_var: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, ChainError> {
+        let mut providers = HashMap::new();
+        let mut archive_providers = HashMap::new();
+        for (chain_id, settings) in chains {
+            let chain = chain_from_id(*chain_id)?;
+            let Some(url) = resolve_rpc_url(&settings.rpc_url, &env_var) else {
+                tracing::warn!("Skipping chain {chain}: {}", ChainError::MissingRpcUrl(chain));
+                continue
+            };
+            providers.insert(chain, http_provider(chain, url)?);
+            let archive_url = settings.archive_rpc_url.as_deref();
+            if let Some(url) = archive_url.and_then(|url| resolve_rpc_url(url, &env_var)) {
+                archive_providers.insert(chain, http_provider(chain, url)?);
+            }
+        }
 
-        let providers = chains
-            .iter()
-            .map(|chain| (*chain, provider_from_chain(*chain)))
-            .collect::<HashMap<_, _>>();
+        let mut chains: Vec<Chain> = providers.keys().copied().collect();
+        chains.sort();
+        Ok(Self {
+            chains,
+            providers,
+            archive_providers,
+            batched_chains: HashSet::new(),
+            circuit_breaker: None,
+            deployed_code_padding: HashMap::new(),
+            tracing_enabled: true,
+            contract_creator_chains: HashSet::new(),
+            creation_search_max_blocks: None,
+            retry: RetryPolicy::default(),
+            metrics: None,
+        })
+    }
+
+    /// Create a new `MultiChainProvider` from the given providers, supporting only those chains.
+    /// This is useful for injecting in-memory providers in tests.
+    pub fn from_providers(providers: HashMap<Chain, Arc<ChainProvider>>) -> Self {
+        let chains = providers.keys().copied().collect();
+        Self {
+            chains,
+            providers,
+            archive_providers: HashMap::new(),
+            batched_chains: HashSet::new(),
+            circuit_breaker: None,
+            deployed_code_padding: HashMap::new(),
+            tracing_enabled: true,
+            contract_creator_chains: HashSet::new(),
+            creation_search_max_blocks: None,
+            retry: RetryPolicy::default(),
+            metrics: None,
+        }
+    }
+
+    /// Returns a provider that only queries the given chains, sharing this provider's RPC
+    /// providers and settings. Returns an error if any of the chains isn't supported.
+    pub fn with_only_chains(&self, chains: &[Chain]) -> Result<Self, ChainError> {
+        if let Some(chain) = chains.iter().find(|chain| !self.providers.contains_key(chain)) {
+            return Err(ChainError::NotConfigured(*chain))
+        }
+        let only = |providers: &HashMap<Chain, Arc<ChainProvider>>| {
+            providers
+                .iter()
+                .filter(|(chain, _)| chains.contains(chain))
+                .map(|(chain, provider)| (*chain, provider.clone()))
+                .collect()
+        };
+        Ok(Self {
+            chains: self.chains.iter().filter(|chain| chains.contains(chain)).copied().collect(),
+            providers: only(&self.providers),
+            archive_providers: only(&self.archive_providers),
+            batched_chains: self.batched_chains.clone(),
+            circuit_breaker: self.circuit_breaker.clone(),
+            deployed_code_padding: self.deployed_code_padding.clone(),
+            tracing_enabled: self.tracing_enabled,
+            contract_creator_chains: self.contract_creator_chains.clone(),
+            creation_search_max_blocks: self.creation_search_max_blocks,
+            retry: self.retry,
+            metrics: self.metrics.clone(),
+        })
+    }
+
+    /// Returns the provider with the given policy for retrying RPC calls after transient failures.
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Returns the provider with the given archive providers, which are queried for deployed code
+    /// when the primary provider for the chain has none.
+    pub fn with_archive_providers(
+        mut self,
+        archive_providers: HashMap<Chain, Arc<ChainProvider>>,
+    ) -> Self {
+        self.archive_providers = archive_providers;
+        self
+    }
+
+    /// Returns the provider with the given circuit breaker, which is used to skip chains whose RPCs
+    /// are repeatedly failing.
+    pub fn with_circuit_breaker(mut self, circuit_breaker: Arc<CircuitBreaker>) -> Self {
+        self.circuit_breaker = Some(circuit_breaker);
+        self
+    }
+
+    /// Returns the provider with the given metrics, which record the latency of RPC operations.
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Records the duration of an RPC operation on the given chain that started at `started`.
+    fn record_rpc(&self, chain: Chain, operation: RpcOperation, started: Instant) {
+        if let Some(metrics) = &self.metrics {
+            metrics.record_rpc(chain, operation, started.elapsed());
+        }
+    }
+
+    /// Returns the chains whose circuit breaker is open, which should not be queried.
+    fn unavailable_chains(&self) -> HashSet<Chain> {
+        match &self.circuit_breaker {
+            Some(breaker) => {
+                self.chains.iter().filter(|chain| !breaker.is_available(**chain)).copied().collect()
+            }
+            None => HashSet::new(),
+        }
+    }
 
-        Self { chains, providers }
+    /// Returns the provider with the given maximum trailing zero padding of deployed code for each
+    /// chain.
+    pub fn with_deployed_code_padding(mut self, padding: HashMap<Chain, usize>) -> Self {
+        self.deployed_code_padding = padding;
+        self
+    }
+
+    /// Returns the maximum number of trailing zero bytes ignored when comparing deployed code on
+    /// the given chain.
+    pub fn max_deployed_code_padding(&self, chain: &Chain) -> usize {
+        self.deployed_code_padding.get(chain).copied().unwrap_or(0)
+    }
+
+    /// Returns the provider with tracing enabled or disabled.
+    pub fn with_tracing(mut self, enabled: bool) -> Self {
+        self.tracing_enabled = enabled;
+        self
+    }
+
+    /// Returns the provider with `ots_getContractCreator` lookups enabled for the given chains.
+    pub fn with_contract_creator_lookup(mut self, chains: impl IntoIterator<Item = Chain>) -> Self {
+        self.contract_creator_chains.extend(chains);
+        self
+    }
+
+    /// Returns the provider with the given maximum number of blocks searched for the deployment
+    /// block of a contract, or with the search disabled if `None`.
+    pub fn with_creation_search_max_blocks(mut self, max_blocks: Option<u64>) -> Self {
+        self.creation_search_max_blocks = max_blocks;
+        self
+    }
+
+    /// Returns the provider with JSON-RPC batching enabled for the given chains.
+    pub fn with_batching(mut self, chains: impl IntoIterator<Item = Chain>) -> Self {
+        self.batched_chains.extend(chains);
+        self
+    }
+
+    /// Queries the latest block number from each chain's RPC to check that it's reachable,
+    /// returning the block number or the error for each chain. Chains whose RPC doesn't respond
+    /// within `timeout` are reported as errors. The circuit breaker is bypassed so chains that are
+    /// currently skipped are still checked.
+    pub async fn check_connectivity(
+        &self,
+        timeout: Duration,
+    ) -> HashMap<Chain, Result<u64, String>> {
+        let checks = self.providers.iter().map(|(chain, provider)| async move {
+            let result = match tokio::time::timeout(timeout, provider.get_block_number()).await {
+                Ok(Ok(block_number)) => Ok(block_number.as_u64()),
+                Ok(Err(err)) => Err(err.to_string()),
+                Err(_) => Err(format!("RPC did not respond within {} seconds", timeout.as_secs())),
+            };
+            (*chain, result)
+        });
+        future::join_all(checks).await.into_iter().collect()
     }
 
     /// Given an address, return the creation code at that address for each supported chain.
@@ -153,49 +544,206 @@ impl MultiChainProvider {
         creation_tx_hashes: Option<HashMap<Chain, TxHash>>,
     ) -> Result<ChainResponse<ContractCreation>, Box<dyn Error + Send + Sync>> {
         /// Given an address, return the creation code at that address for the chain specified by
-        /// the provider.
+        /// the provider. If no transaction hash was provided for the chain, the creation
+        /// transaction is discovered if possible. Returns `None` if no transaction hash was
+        /// provided or discovered for the chain.
         async fn find_creation_code(
-            provider: &Arc<Provider<Http>>,
+            provider: &Arc<ChainProvider>,
+            chain: Chain,
             address: Address,
             creation_tx_hash: Option<TxHash>,
-        ) -> Option<ContractCreation> {
+            lookup: &CreationLookup,
+        ) -> Result<Option<ContractCreation>, Box<dyn Error + Send + Sync>> {
+            let creation_tx_hash = match creation_tx_hash {
+                Some(tx_hash) => Some(tx_hash),
+                None => discover_creation_tx_hash(provider, chain, address, lookup).await?,
+            };
+            let Some(creation_tx_hash) = creation_tx_hash else { return Ok(None) };
             let creation_data =
-                find_creation_data(provider, address, creation_tx_hash).await.ok()?;
-            Some(creation_data)
+                find_creation_data(provider, chain, address, creation_tx_hash, lookup).await?;
+            Ok(Some(creation_data))
         }
 
+        let unavailable = self.unavailable_chains();
         let futures = self.providers.iter().map(|(chain, provider)| {
             let creation_tx_hash = creation_tx_hashes.as_ref().and_then(|h| h.get(chain)).cloned();
-            async move { (*chain, find_creation_code(provider, address, creation_tx_hash).await) }
+            let lookup = CreationLookup {
+                batch: self.batched_chains.contains(chain),
+                tracing_enabled: self.tracing_enabled,
+                contract_creator_lookup: self.contract_creator_chains.contains(chain),
+                search_max_blocks: self.creation_search_max_blocks,
+                retry: self.retry,
+            };
+            let unavailable = &unavailable;
+            async move {
+                if unavailable.contains(chain) {
+                    return (*chain, Err(CHAIN_UNAVAILABLE.into()))
+                }
+                // Each RPC call of the lookup is retried on its own, so a transient failure late
+                // in the lookup doesn't repeat the calls that already succeeded.
+                let started = Instant::now();
+                let creation_code =
+                    find_creation_code(provider, *chain, address, creation_tx_hash, &lookup).await;
+                self.record_rpc(*chain, RpcOperation::CreationCode, started);
+                (*chain, creation_code)
+            }
         });
-        let responses = future::join_all(futures).await.into_iter().collect::<HashMap<_, _>>();
-        Ok(ChainResponse { responses })
+        let results = future::join_all(futures).await;
+        let mut response = ChainResponse::from_results(results);
+        response.unavailable = unavailable;
+        Ok(response)
     }
 
     /// Given an address, return the deployed code at that address for each supported chain.
     pub async fn get_deployed_code(
         &self,
         address: Address,
+    ) -> Result<ChainResponse<Bytes>, Box<dyn Error>> {
+        self.get_deployed_code_with_overrides(address, &HashMap::new(), &HashMap::new()).await
+    }
+
+    /// Given an address, return the deployed code at that address for each supported chain, using
+    /// the given code instead of querying the RPC for chains in `overrides`. This allows verifying
+    /// against captured runtime code for chains whose RPC is not accessible. Chains without code
+    /// are queried again with their archive provider, at the block of the creation transaction in
+    /// `creation_tx_hashes` if one is given.
+    pub async fn get_deployed_code_with_overrides(
+        &self,
+        address: Address,
+        overrides: &HashMap<Chain, Bytes>,
+        creation_tx_hashes: &HashMap<Chain, TxHash>,
     ) -> Result<ChainResponse<Bytes>, Box<dyn Error>> {
         /// Given an address, return the deployed code at that address for the chain specified by
-        /// the given provider.
+        /// the given provider. Returns `None` if there is no code at the address, and an error if
+        /// the chain could not be queried after retrying transient failures.
         async fn find_deployed_code(
-            provider: &Arc<Provider<Http>>,
+            provider: &Arc<ChainProvider>,
+            archive_provider: Option<&Arc<ChainProvider>>,
             address: Address,
-        ) -> Option<Bytes> {
-            let code = provider.get_code(address, None).await.ok()?;
-            if code.is_empty() {
-                None
-            } else {
-                Some(code)
+            creation_tx_hash: Option<TxHash>,
+            retry: &RetryPolicy,
+        ) -> Result<Option<Bytes>, ProviderError> {
+            let code = retry.retry(|| provider.get_code(address, None)).await?;
+            if !code.is_empty() {
+                return Ok(Some(code))
+            }
+            let Some(archive_provider) = archive_provider else { return Ok(None) };
+
+            // The primary provider was queried successfully, so a failing archive provider is
+            // logged rather than reported as an RPC error for the chain.
+            let archive_code =
+                retry.retry(|| archive_deployed_code(archive_provider, address, creation_tx_hash));
+            match archive_code.await {
+                Ok(code) => Ok(code),
+                Err(err) => {
+                    tracing::warn!("Failed to query archive RPC for deployed code: {err}");
+                    Ok(None)
+                }
             }
         }
 
-        let futures = self.providers.iter().map(|(chain, provider)| async move {
-            (*chain, find_deployed_code(provider, address).await)
+        /// Returns the deployed code at the address from the archive provider. The code is queried
+        /// at the block of the creation transaction if given, so it's found even if the contract
+        /// has since self-destructed, and at the latest block otherwise.
+        async fn archive_deployed_code(
+            provider: &Arc<ChainProvider>,
+            address: Address,
+            creation_tx_hash: Option<TxHash>,
+        ) -> Result<Option<Bytes>, ProviderError> {
+            let block = match creation_tx_hash {
+                Some(tx_hash) => {
+                    provider.get_transaction(tx_hash).await?.and_then(|tx| tx.block_number)
+                }
+                None => None,
+            };
+            let code = provider.get_code(address, block.map(|block| block.into())).await?;
+            Ok((!code.is_empty()).then_some(code))
+        }
+
+        // Chains with overridden code are never queried, so they can't be unavailable.
+        let mut unavailable = self.unavailable_chains();
+        unavailable.retain(|chain| !overrides.contains_key(chain));
+        let futures = self.providers.iter().map(|(chain, provider)| {
+            let unavailable = &unavailable;
+            async move {
+                if let Some(code) = overrides.get(chain) {
+                    return (*chain, Ok((!code.is_empty()).then(|| code.clone())))
+                }
+                if unavailable.contains(chain) {
+                    return (*chain, Err(ProviderError::CustomError(CHAIN_UNAVAILABLE.to_string())))
+                }
+                let archive_provider = self.archive_providers.get(chain);
+                let creation_tx_hash = creation_tx_hashes.get(chain).copied();
+                let started = Instant::now();
+                let code = find_deployed_code(
+                    provider,
+                    archive_provider,
+                    address,
+                    creation_tx_hash,
+                    &self.retry,
+                )
+                .await;
+                self.record_rpc(*chain, RpcOperation::DeployedCode, started);
+                (*chain, code)
+            }
         });
-        let responses = future::join_all(futures).await.into_iter().collect::<HashMap<_, _>>();
-        Ok(ChainResponse { responses })
+        let results = future::join_all(futures).await;
+
+        // Only deployed code queries update the circuit breaker, since they fail only when the RPC
+        // does, whereas creation code queries can also fail due to bad inputs.
+        if let Some(breaker) = &self.circuit_breaker {
+            let queried =
+                |chain: &Chain| !unavailable.contains(chain) && !overrides.contains_key(chain);
+            for (chain, result) in results.iter().filter(|(chain, _)| queried(chain)) {
+                match result {
+                    Ok(_) => breaker.record_success(*chain),
+                    Err(_) => breaker.record_failure(*chain),
+                }
+            }
+        }
+
+        let mut response = ChainResponse::from_results(results);
+        response.unavailable = unavailable;
+        Ok(response)
+    }
+
+    /// Replaces the deployed code of each chain whose code is an EIP-1167 minimal proxy with the
+    /// deployed code of the proxy's implementation, so the implementation is verified instead of
+    /// the proxy. Returns the proxy of each chain whose implementation code was found. Chains whose
+    /// implementation code can't be fetched keep the proxy's code.
+    pub async fn resolve_minimal_proxies(
+        &self,
+        deployed_code: &mut ChainResponse<Bytes>,
+    ) -> HashMap<Chain, MinimalProxy> {
+        let proxies: Vec<(Chain, Address)> = deployed_code
+            .iter_entries()
+            .filter_map(|(chain, code)| Some((*chain, is_minimal_proxy(code)?)))
+            .collect();
+        let futures = proxies.into_iter().filter_map(|(chain, implementation)| {
+            let provider = self.providers.get(&chain)?;
+            Some(async move {
+                let code = self.retry.retry(|| provider.get_code(implementation, None)).await;
+                (chain, implementation, code)
+            })
+        });
+
+        let mut resolved = HashMap::new();
+        for (chain, implementation, code) in future::join_all(futures).await {
+            match code {
+                Ok(code) if !code.is_empty() => {
+                    let proxy = deployed_code.responses.insert(chain, Some(code)).flatten();
+                    let proxy_runtime_code = proxy.unwrap_or_default();
+                    resolved.insert(chain, MinimalProxy { implementation, proxy_runtime_code });
+                }
+                Ok(_) => tracing::warn!(
+                    "Implementation {implementation:?} of minimal proxy has no code on {chain}"
+                ),
+                Err(err) => tracing::warn!(
+                    "Failed to fetch implementation code of minimal proxy on {chain}: {err}"
+                ),
+            }
+        }
+        resolved
     }
 
     /// Given the creation code data being compared against and the build artifacts from a project,
@@ -203,68 +751,90 @@ impl MultiChainProvider {
     /// the best match found. It's possible that no match is found.
     pub fn compare_creation_code(
         &self,
-        project: &impl Framework,
+        project: &(impl Framework + Sync + ?Sized),
+        artifacts: &[PathBuf],
         creation_data: &ChainResponse<ContractCreation>,
     ) -> ChainResponse<ContractMatch> {
         /// Compares the creation code against the expected creation code for each artifact and
         /// returns the best match.
         fn compare(
-            project: &impl Framework,
+            project: &(impl Framework + Sync + ?Sized),
+            artifacts: &[PathBuf],
             expected_creation_code: &Bytes,
         ) -> Option<ContractMatch> {
-            let artifacts = project.get_artifacts().unwrap();
-            if artifacts.is_empty() {
-                panic!("No artifacts found in project");
-            }
+            best_artifact_match(artifacts, |artifact| {
+                let found = project.structure_found_creation_code(artifact).ok()?;
+                let expected = project
+                    .structure_expected_creation_code(artifact, &found, expected_creation_code)
+                    .ok()?;
+                match creation_code_equality_check(&found, &expected) {
+                    MatchType::None => None,
+                    match_type => Some(ContractMatch {
+                        artifact: artifact.clone(),
+                        match_type,
+                        padding_normalized: false,
+                        deployed_code_diff: None,
+                    }),
+                }
+            })
+        }
 
-            let mut best_artifact_match: Option<ContractMatch> = None;
-            for artifact in artifacts {
-                let found = match project.structure_found_creation_code(&artifact) {
-                    Ok(found) => found,
-                    Err(_) => continue,
-                };
+        // Comparison is CPU-bound and independent per chain, so compare chains in parallel.
+        let responses = self
+            .providers
+            .par_iter()
+            .map(|(chain, _)| {
+                let contract_match = creation_data
+                    .get(chain)
+                    .and_then(|data| compare(project, artifacts, &data.creation_code));
+                (*chain, contract_match)
+            })
+            .collect::<HashMap<_, _>>();
 
-                let expected = match project.structure_expected_creation_code(
-                    &artifact,
-                    &found,
-                    expected_creation_code,
-                ) {
-                    Ok(expected) => expected,
-                    Err(_) => continue,
-                };
+        ChainResponse { responses, errors: HashMap::new(), unavailable: HashSet::new() }
+    }
 
-                // If we have an exact match, return it. If we have a partial match, save it off.
-                // We'll return it if we don't find an exact match. Note that treats all partial
-                // matches equally and arbitrarily gives priority to the last one.
-                match creation_code_equality_check(&found, &expected) {
-                    MatchType::Full => {
-                        return Some(ContractMatch { artifact, match_type: MatchType::Full })
-                    }
-                    MatchType::Partial => {
-                        best_artifact_match =
-                            Some(ContractMatch { artifact, match_type: MatchType::Partial })
-                    }
-                    _ => {}
-                }
+    /// Compares creation code for a contract deployed by a CREATE2 factory, without needing the
+    /// creation transaction. For each artifact, the init code is the artifact's creation code with
+    /// the constructor arguments appended, and the artifact can only match if the CREATE2 address
+    /// derived from the factory, salt, and init code is `address`. The match applies to every chain
+    /// with deployed code at `address`.
+    pub fn compare_factory_creation_code(
+        &self,
+        project: &(impl Framework + ?Sized),
+        artifacts: &[PathBuf],
+        address: Address,
+        deployment: &FactoryDeployment,
+        deployed_code: &ChainResponse<Bytes>,
+    ) -> ChainResponse<ContractMatch> {
+        let contract_match = artifacts.iter().find_map(|artifact| {
+            let found = project.structure_found_creation_code(artifact).ok()?;
+            let init_code: Bytes =
+                [found.raw_code.as_ref(), deployment.constructor_args.as_ref()].concat().into();
+            if get_create2_address(deployment.factory, deployment.salt, &init_code) != address {
+                return None
             }
-            best_artifact_match
-        }
+
+            let expected =
+                project.structure_expected_creation_code(artifact, &found, &init_code).ok()?;
+            match creation_code_equality_check(&found, &expected) {
+                MatchType::None => None,
+                match_type => Some(ContractMatch {
+                    artifact: artifact.clone(),
+                    match_type,
+                    padding_normalized: false,
+                    deployed_code_diff: None,
+                }),
+            }
+        });
 
         let responses = self
             .providers
             .keys()
-            .map(|chain| {
-                let expected_creation_data = creation_data.responses.get(chain).unwrap();
-                if expected_creation_data.is_none() {
-                    return (*chain, None)
-                }
-                let expected_creation_code =
-                    &expected_creation_data.as_ref().unwrap().creation_code;
-                (*chain, compare(project, expected_creation_code))
-            })
+            .map(|chain| (*chain, deployed_code.get(chain).and(contract_match.clone())))
             .collect::<HashMap<_, _>>();
 
-        ChainResponse { responses }
+        ChainResponse { responses, errors: HashMap::new(), unavailable: HashSet::new() }
     }
 
     /// Given the deployed code being compared against and the build artifacts from a project,
@@ -272,159 +842,996 @@ impl MultiChainProvider {
     /// the best match found. It's possible that no match is found.
     pub fn compare_deployed_code(
         &self,
-        project: &impl Framework,
+        project: &(impl Framework + Sync + ?Sized),
+        artifacts: &[PathBuf],
         deployed_code: &ChainResponse<Bytes>,
     ) -> ChainResponse<ContractMatch> {
         /// Compares the deployed code against the expected deployed code for each artifact and
         /// returns the best match.
         fn compare(
-            project: &impl Framework,
+            project: &(impl Framework + Sync + ?Sized),
+            artifacts: &[PathBuf],
             expected_deployed_code: &Bytes,
+            max_padding: usize,
         ) -> Option<ContractMatch> {
-            let artifacts = project.get_artifacts().unwrap();
-            if artifacts.is_empty() {
-                panic!("No artifacts found in project");
-            }
-
-            let mut best_artifact_match: Option<ContractMatch> = None;
-            for artifact in artifacts {
-                let found = match project.structure_found_deployed_code(&artifact) {
-                    Ok(found) => found,
-                    Err(_) => continue,
-                };
-
-                let expected = match project
+            best_artifact_match(artifacts, |artifact| {
+                let found = project.structure_found_deployed_code(artifact).ok()?;
+                let expected = project
                     .structure_expected_deployed_code(&found, expected_deployed_code)
-                {
-                    Ok(expected) => expected,
-                    Err(_) => continue,
-                };
-
-                // If we have an exact match, return it. If we have a partial match, save it off.
-                // We'll return it if we don't find an exact match. Note that treats all partial
-                // matches equally and arbitrarily gives priority to the last one.
-                match deployed_code_equality_check(&found, &expected) {
-                    MatchType::Full => {
-                        return Some(ContractMatch { artifact, match_type: MatchType::Full })
-                    }
-                    MatchType::Partial => {
-                        best_artifact_match =
-                            Some(ContractMatch { artifact, match_type: MatchType::Partial })
-                    }
-                    _ => {}
+                    .ok()?;
+                let padding_normalized = deployed_code_padding(&found, &expected, max_padding) > 0;
+                match deployed_code_equality_check(&found, &expected, max_padding) {
+                    MatchType::Full => Some(ContractMatch {
+                        artifact: artifact.clone(),
+                        match_type: MatchType::Full,
+                        padding_normalized,
+                        deployed_code_diff: None,
+                    }),
+                    MatchType::Partial => Some(ContractMatch {
+                        artifact: artifact.clone(),
+                        match_type: MatchType::Partial,
+                        padding_normalized,
+                        deployed_code_diff: Some(diff_report(&found, &expected)),
+                    }),
+                    _ => None,
                 }
-            }
-            best_artifact_match
+            })
         }
 
+        // Comparison is CPU-bound and independent per chain, so compare chains in parallel.
         let responses = self
             .providers
-            .keys()
-            .map(|chain| {
-                let expected_deployed_code = deployed_code.responses.get(chain).unwrap();
-                if expected_deployed_code.is_none() {
-                    return (*chain, None)
-                }
-                let expected_creation_code = &expected_deployed_code.as_ref().unwrap();
-                (*chain, compare(project, expected_creation_code))
+            .par_iter()
+            .map(|(chain, _)| {
+                let max_padding = self.max_deployed_code_padding(chain);
+                let contract_match = deployed_code
+                    .get(chain)
+                    .and_then(|code| compare(project, artifacts, code, max_padding));
+                (*chain, contract_match)
             })
             .collect::<HashMap<_, _>>();
 
-        ChainResponse { responses }
+        ChainResponse { responses, errors: HashMap::new(), unavailable: HashSet::new() }
+    }
+}
+
+/// Compares each artifact in parallel and returns the best match, where `compare` returns an
+/// artifact's match or `None` if it doesn't match. The first artifact with a full match is
+/// returned. If none fully match, partial matches are treated equally and the last one is
+/// returned. Artifacts after a full match that was already found are skipped, but every artifact
+/// before it is still compared, so the result is the same as comparing them in order.
+fn best_artifact_match(
+    artifacts: &[PathBuf],
+    compare: impl Fn(&PathBuf) -> Option<ContractMatch> + Sync,
+) -> Option<ContractMatch> {
+    let is_full = |contract_match: &ContractMatch| {
+        matches!(contract_match.match_type, MatchType::Full | MatchType::FullWithConstructorArgs)
+    };
+    let first_full_match = AtomicUsize::new(usize::MAX);
+    let mut matches: Vec<ContractMatch> = artifacts
+        .par_iter()
+        .enumerate()
+        .filter_map(|(index, artifact)| {
+            if index > first_full_match.load(Ordering::Relaxed) {
+                return None
+            }
+            let contract_match = compare(artifact)?;
+            if is_full(&contract_match) {
+                first_full_match.fetch_min(index, Ordering::Relaxed);
+            }
+            Some(contract_match)
+        })
+        .collect();
+
+    // Matches are collected in the order of the artifacts.
+    match matches.iter().position(is_full) {
+        Some(index) => Some(matches.swap_remove(index)),
+        None => matches.pop(),
+    }
+}
+
+/// How the creation code of a contract is looked up on a chain, based on what the chain's RPC
+/// supports and how the server is configured.
+#[derive(Debug, Clone, Copy)]
+struct CreationLookup {
+    /// Whether related requests are sent as JSON-RPC batch requests.
+    batch: bool,
+    /// Whether transactions are traced to find deployments by unknown factories.
+    tracing_enabled: bool,
+    /// Whether the RPC supports `ots_getContractCreator`, which is used to discover the creation
+    /// transaction.
+    contract_creator_lookup: bool,
+    /// The maximum number of blocks searched for the deployment block, or `None` if the search is
+    /// disabled.
+    search_max_blocks: Option<u64>,
+    /// How each RPC call is retried after transient failures.
+    retry: RetryPolicy,
+}
+
+/// Discovers the hash of the transaction that deployed the contract at `address`. If the lookup
+/// has `contract_creator_lookup` enabled, the node's trace index is queried first. If that's
+/// unavailable or unsupported and `search_max_blocks` is set, the deployment block is found by
+/// binary search and its transactions are scanned for the deployment. Returns `None` if there's no
+/// contract at the address, or if the creation transaction can't be discovered because neither
+/// lookup is available.
+async fn discover_creation_tx_hash(
+    provider: &Arc<ChainProvider>,
+    chain: Chain,
+    address: Address,
+    lookup: &CreationLookup,
+) -> Result<Option<TxHash>, Box<dyn Error + Send + Sync>> {
+    if lookup.contract_creator_lookup {
+        match lookup.retry.retry(|| find_creation_tx_hash(provider, address)).await {
+            Ok(tx_hash) => return Ok(tx_hash),
+            Err(err) => tracing::warn!("Failed to discover creation transaction on {chain}: {err}"),
+        }
     }
+
+    let Some(max_blocks) = lookup.search_max_blocks else { return Ok(None) };
+    let Some(block) = find_deployment_block(provider, address, max_blocks, &lookup.retry).await?
+    else {
+        return Ok(None)
+    };
+    let tx_hash = find_creation_tx_in_block(provider, chain, address, block, lookup).await?;
+    Ok(Some(tx_hash))
+}
+
+/// Finds the block the contract at `address` was deployed in, by binary searching for the first
+/// block with code at the address. Only the `max_blocks` blocks before the latest block are
+/// searched. Returns `None` if there's no code at the address. A contract that was redeployed to
+/// the same address after self-destructing may be found at either deployment.
+async fn find_deployment_block(
+    provider: &Arc<ChainProvider>,
+    address: Address,
+    max_blocks: u64,
+    retry: &RetryPolicy,
+) -> Result<Option<u64>, Box<dyn Error + Send + Sync>> {
+    let latest = retry.retry(|| provider.get_block_number()).await?.as_u64();
+    if !has_code_at_block(provider, address, latest, retry).await? {
+        return Ok(None)
+    }
+
+    let earliest = latest.saturating_sub(max_blocks);
+    if has_code_at_block(provider, address, earliest, retry).await? {
+        return Err(format!(
+            "The deployment block could not be isolated because the contract already had code at block {earliest}, the earliest block searched. Provide the creation transaction hash instead."
+        )
+        .into())
+    }
+
+    // The contract has no code at `low` and has code at `high`, so it was deployed in a block after
+    // `low` and no later than `high`.
+    let (mut low, mut high) = (earliest, latest);
+    while high - low > 1 {
+        let mid = low + (high - low) / 2;
+        if has_code_at_block(provider, address, mid, retry).await? {
+            high = mid;
+        } else {
+            low = mid;
+        }
+    }
+    Ok(Some(high))
+}
+
+/// Returns whether there is code at `address` as of the given block.
+async fn has_code_at_block(
+    provider: &Arc<ChainProvider>,
+    address: Address,
+    block: u64,
+    retry: &RetryPolicy,
+) -> Result<bool, ProviderError> {
+    let code = retry.retry(|| provider.get_code(address, Some(block.into()))).await?;
+    Ok(!code.is_empty())
+}
+
+/// Finds the transaction in `block` that deployed the contract at `address`, by checking the
+/// receipts of the block's transactions for a matching contract address. Receipts are fetched
+/// concurrently, up to `MAX_CONCURRENT_RECEIPTS` at once, and checked in the order of the block's
+/// transactions. Deployments by factories don't set the receipt's contract address, so if no
+/// receipt matches and tracing is enabled, the block's transactions are traced instead.
+async fn find_creation_tx_in_block(
+    provider: &Arc<ChainProvider>,
+    chain: Chain,
+    address: Address,
+    block: u64,
+    lookup: &CreationLookup,
+) -> Result<TxHash, Box<dyn Error + Send + Sync>> {
+    let retry = &lookup.retry;
+    let block_data = retry.retry(|| provider.get_block(block)).await?;
+    let tx_hashes = block_data.ok_or("Block not found")?.transactions;
+    // The stream owns the hashes, since borrowing them would make this future not `Send`.
+    let mut receipts = stream::iter(tx_hashes.clone())
+        .map(move |tx_hash| async move {
+            let receipt = retry.retry(|| provider.get_transaction_receipt(tx_hash)).await?;
+            Ok::<_, ProviderError>((tx_hash, receipt))
+        })
+        .buffered(MAX_CONCURRENT_RECEIPTS);
+    while let Some(result) = receipts.next().await {
+        let (tx_hash, receipt) = result?;
+        if receipt.and_then(|receipt| receipt.contract_address) == Some(address) {
+            return Ok(tx_hash)
+        }
+    }
+
+    if lookup.tracing_enabled {
+        for tx_hash in &tx_hashes {
+            if trace_creation_code(provider, chain, *tx_hash, address, retry).await.is_ok() {
+                return Ok(*tx_hash)
+            }
+        }
+    }
+    Err(format!("Unable to find the transaction in block {block} that deployed the contract.")
+        .into())
 }
 
 /// Given the transaction hash of a contract creation transaction, extracts the creation code from
-/// the transaction and returns the creation data. This feature is currently not supported.
+/// the transaction and returns the creation data.
 async fn find_creation_data(
-    provider: &Arc<Provider<Http>>,
+    provider: &Arc<ChainProvider>,
+    chain: Chain,
     address: Address,
-    tx_hash: Option<TxHash>,
+    tx_hash: TxHash,
+    lookup: &CreationLookup,
 ) -> Result<ContractCreation, Box<dyn std::error::Error + Send + Sync>> {
-    // If we have a transaction hash, use that to find the creation code.
-    if let Some(tx_hash) = tx_hash {
-        let (creation_code, tx) = creation_code_from_tx_hash(provider, address, tx_hash).await?;
-        let block = BlockNumber::from(tx.block_number.unwrap());
-        return Ok(ContractCreation { tx_hash, block, creation_code })
-    }
-
-    Err("Automatically finding creation data is currently not supported.".into())
+    let (creation_code, tx, source) =
+        creation_code_from_tx_hash(provider, chain, address, tx_hash, lookup).await?;
+    let block = BlockNumber::from(tx.block_number.unwrap());
+    Ok(ContractCreation { tx_hash, block, creation_code, source })
 }
 
 /// Given the transaction hash of a contract creation transaction, extracts the creation code from
-/// the transaction. This feature is currently not supported.
+/// the transaction. If the contract was deployed by an unknown factory and tracing is enabled, the
+/// transaction is traced using the trace method appropriate for the chain.
 async fn creation_code_from_tx_hash(
-    provider: &Arc<Provider<Http>>,
+    provider: &Arc<ChainProvider>,
+    chain: Chain,
     address: Address,
     tx_hash: TxHash,
-) -> Result<(Bytes, Transaction), Box<dyn std::error::Error + Send + Sync>> {
-    // TODO This is not currently supported, but the flow would be as follows:
+    lookup: &CreationLookup,
+) -> Result<(Bytes, Transaction, CreationCodeSource), Box<dyn std::error::Error + Send + Sync>> {
+    // The flow is as follows:
     //   1. Fetch the transaction data.
     //   2. If `to` is None, this was a regular CREATE transaction so we can extract the creation
     //      code from the input data.
     //   3. Otherwise, the contract was deployed by a factory. First, check the `to` address and see
-    //      if it's a known factory. If so, we'll know how to decode the transaction data to extract
-    //      the creation code.
+    //      if it's a known factory, or a known batching contract such as MultiSend that calls one.
+    //      If so, we'll know how to decode the transaction data to extract the creation code.
     //   4. If the `to` address is not a known factory, we trace the transaction to find the call
-    //      that deployed the contract. Infura now supports `trace_call` so we can use that here.
-    // Note that steps 1, 2, and 3 are implemented below. Step 4 is not implemented. Step 3 can also
-    // be expanded to support more factories, or it can be removed entirely and we can always trace.
-    let tx = provider.get_transaction(tx_hash).await?.ok_or("Transaction not found")?;
+    //      that deployed the contract. The tracing RPC method differs between node clients and
+    //      chains, so see the `trace` module for how it's selected.
+    // Step 3 can be expanded to support more factories, or it can be removed entirely and we can
+    // always trace.
+    let (tx, receipt) =
+        lookup.retry.retry(|| get_transaction_and_receipt(provider, tx_hash, lookup.batch)).await?;
 
     // Regular CREATE transaction.
     if tx.to.is_none() {
-        let receipt =
-            provider.get_transaction_receipt(tx_hash).await?.ok_or("Receipt not found")?;
+        let receipt = receipt.ok_or("Receipt not found")?;
         if let Some(contract_address) = receipt.contract_address {
             if contract_address == address {
                 let creation_code = tx.input.clone();
-                return Ok((creation_code, tx))
+                return Ok((creation_code, tx, CreationCodeSource::ProvidedTxHash))
             }
         }
     }
 
-    // Contract was deployed from a factory. For now, to avoid tracing, we hardcode a few known,
-    // popular create2 factories.
-    if let Some(factory) = tx.to {
-        // https://github.com/Arachnid/deterministic-deployment-proxy
-        if factory == Address::from_str("0x4e59b44847b379578588920cA78FbF26c0B4956C")? {
-            // The first 32 bytes of calldata are the salt, and the rest are the creation code.
-            let creation_code = Bytes::from_iter(tx.input[32..].to_vec());
-            return Ok((creation_code, tx))
+    // Contract was deployed from a factory. To avoid tracing, calldata sent to known factories,
+    // or to known batching contracts that call them, is decoded to extract the creation code.
+    if let Some(to) = tx.to {
+        if let Some((creation_code, name)) = known_deployer_creation_code(to, &tx.input, address) {
+            return Ok((creation_code, tx, CreationCodeSource::KnownFactory(name)))
         }
 
-        // Create2 factory by 0age.
-        if factory == Address::from_str("0x0000000000FFe8B47B3e2130213B802212439497")? {
-            // The only function on this deployer is:
-            //   `function safeCreate2(bytes32 salt, bytes calldata initializationCode)`
-            // so we know that method was called and can extract the creation code. The input
-            // data is structured as follows:
-            //   - Bytes 1-4: Function selector
-            //   - Bytes 5-36: Salt
-            //   - Bytes 37-68: Offset to creation code data
-            //   - Bytes 69-100: Offset to creation code length
-            let len = &tx.input[69..100];
-            let len = U256::from(len).as_usize();
-            let creation_code = &tx.input[100..len + 100];
-            let creation_code = Bytes::from_iter(creation_code);
-            return Ok((creation_code, tx))
+        // Unknown factory, so trace the transaction to find the deployment.
+        if lookup.tracing_enabled {
+            match trace_creation_code(provider, chain, tx_hash, address, &lookup.retry).await {
+                Ok(creation_code) => return Ok((creation_code, tx, CreationCodeSource::Traced)),
+                Err(err) => tracing::warn!("Failed to trace creation transaction: {err}"),
+            }
         }
     }
     Err("Contract creation transaction not found. It may have been deployed by an unsupported factory, or the wrong transaction hash for this chain was provided.".into())
 }
 
+/// Fetches the transaction with the given hash, along with its receipt if the transaction is a
+/// contract creation. When `batch` is true, both are fetched in a single JSON-RPC batch request,
+/// at the cost of fetching a receipt that's unused for factory deployments.
+async fn get_transaction_and_receipt(
+    provider: &Arc<ChainProvider>,
+    tx_hash: TxHash,
+    batch: bool,
+) -> Result<(Transaction, Option<TransactionReceipt>), Box<dyn Error + Send + Sync>> {
+    if batch {
+        let params = serde_json::json!([tx_hash]);
+        let requests =
+            [("eth_getTransactionByHash", params.clone()), ("eth_getTransactionReceipt", params)];
+        let client: &RpcClient = (**provider).as_ref();
+        let mut results = client.batch_request(&requests).await?.into_iter();
+        let tx: Option<Transaction> = serde_json::from_value(results.next().unwrap_or_default())?;
+        let receipt: Option<TransactionReceipt> =
+            serde_json::from_value(results.next().unwrap_or_default())?;
+        return Ok((tx.ok_or("Transaction not found")?, receipt))
+    }
+
+    let tx = provider.get_transaction(tx_hash).await?.ok_or("Transaction not found")?;
+    let receipt = match tx.to {
+        None => provider.get_transaction_receipt(tx_hash).await?,
+        Some(_) => None,
+    };
+    Ok((tx, receipt))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{frameworks::foundry::Foundry, rpc::InMemoryClient};
     use dotenvy::dotenv;
+    use ethers::{types::Block, utils::keccak256};
     use futures::future::try_join_all;
+    use std::{path::Path, time::Duration};
 
-    fn get_provider() -> Arc<Provider<Http>> {
+    fn get_provider() -> Arc<ChainProvider> {
         if dotenv().is_err() {
             // We don't error since there's no `.env` file in CI.
             println!("WARNING: No .env file found, using default environment variables.");
         }
-        Arc::new(Provider::<Http>::try_from(env::var("GOERLI_RPC_URL").unwrap()).unwrap())
+        http_provider(Chain::Goerli, env::var("GOERLI_RPC_URL").unwrap()).unwrap()
+    }
+
+    /// Returns a creation code lookup with tracing enabled and discovery of the creation
+    /// transaction disabled.
+    fn creation_lookup(batch: bool) -> CreationLookup {
+        CreationLookup {
+            batch,
+            tracing_enabled: true,
+            contract_creator_lookup: false,
+            search_max_blocks: None,
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    #[test]
+    fn test_chain_response_statuses() {
+        let results: Vec<(Chain, Result<Option<Bytes>, String>)> = vec![
+            (Chain::Mainnet, Ok(Some(Bytes::from_static(&[0x60, 0x80])))),
+            (Chain::Optimism, Ok(None)),
+            (Chain::Arbitrum, Err("connection refused".to_string())),
+        ];
+        let response = ChainResponse::from_results(results);
+
+        assert_eq!(response.responses.len(), 3);
+        assert_eq!(response.errors.len(), 1);
+        let statuses = response.statuses();
+        assert_eq!(statuses[&Chain::Mainnet], ChainStatus::Ok);
+        assert_eq!(statuses[&Chain::Optimism], ChainStatus::NoCode);
+        assert_eq!(statuses[&Chain::Arbitrum], ChainStatus::RpcError);
+        assert_eq!(response.chains_with_status(ChainStatus::NoCode), vec![Chain::Optimism]);
+        assert_eq!(response.chains_with_status(ChainStatus::RpcError), vec![Chain::Arbitrum]);
+        assert!(response.chains_with_status(ChainStatus::Unavailable).is_empty());
+
+        assert_eq!(response.get(&Chain::Mainnet), Some(&Bytes::from_static(&[0x60, 0x80])));
+        assert_eq!(response.get(&Chain::Optimism), None);
+        assert_eq!(response.get(&Chain::Polygon), None);
+        let map = response.into_map();
+        assert_eq!(map.len(), 1);
+        assert!(map.contains_key(&Chain::Mainnet));
+    }
+
+    /// Returns a match of the given type for the artifact.
+    fn artifact_match(artifact: &Path, match_type: MatchType) -> Option<ContractMatch> {
+        Some(ContractMatch { artifact: artifact.to_path_buf(), match_type, ..Default::default() })
+    }
+
+    #[test]
+    fn test_best_artifact_match_is_deterministic() {
+        let artifacts: Vec<PathBuf> =
+            (0..200).map(|i| PathBuf::from(format!("out/Contract{i}.json"))).collect();
+        let index = |artifact: &PathBuf| artifacts.iter().position(|a| a == artifact).unwrap();
+
+        // The first full match wins, even if a later full match is found first.
+        for _ in 0..20 {
+            let best = best_artifact_match(&artifacts, |artifact| match index(artifact) {
+                37 | 150 => artifact_match(artifact, MatchType::Full),
+                i if i % 2 == 0 => artifact_match(artifact, MatchType::Partial),
+                _ => None,
+            });
+            assert_eq!(best.unwrap().artifact, artifacts[37]);
+        }
+
+        // Without a full match, the last partial match wins.
+        for _ in 0..20 {
+            let best = best_artifact_match(&artifacts, |artifact| match index(artifact) {
+                i if i % 3 == 0 => artifact_match(artifact, MatchType::Partial),
+                _ => None,
+            });
+            let best = best.unwrap();
+            assert_eq!(best.artifact, artifacts[198]);
+            assert_eq!(best.match_type, MatchType::Partial);
+        }
+
+        assert!(best_artifact_match(&artifacts, |_| None).is_none());
+    }
+
+    #[test]
+    fn test_best_artifact_match_matches_sequential() {
+        let artifacts: Vec<PathBuf> =
+            (0..64).map(|i| PathBuf::from(format!("out/Contract{i}.json"))).collect();
+        let index = |artifact: &PathBuf| artifacts.iter().position(|a| a == artifact).unwrap();
+
+        // Comparing in order returns the first full match, or the last partial match if none fully
+        // match. The parallel comparison must return the same match for any mix of match types.
+        for seed in 0..50 {
+            let compare = |artifact: &PathBuf| match (index(artifact) * 7 + seed * 13) % 23 {
+                0 => artifact_match(artifact, MatchType::Full),
+                1 => artifact_match(artifact, MatchType::FullWithConstructorArgs),
+                2..=7 => artifact_match(artifact, MatchType::Partial),
+                _ => None,
+            };
+            let matches: Vec<ContractMatch> = artifacts.iter().filter_map(compare).collect();
+            let sequential = matches
+                .iter()
+                .find(|m| {
+                    matches!(m.match_type, MatchType::Full | MatchType::FullWithConstructorArgs)
+                })
+                .or(matches.last())
+                .map(|m| (m.artifact.clone(), m.match_type));
+
+            let parallel = best_artifact_match(&artifacts, compare);
+            assert_eq!(parallel.map(|m| (m.artifact, m.match_type)), sequential, "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn test_compare_factory_creation_code() -> Result<(), Box<dyn std::error::Error>> {
+        let project_dir = tempfile::tempdir()?;
+        std::fs::write(project_dir.path().join("foundry.toml"), "[profile.default]")?;
+        let project = Foundry::new(project_dir.path()).unwrap();
+        let artifact = project_dir.path().join("Counter.json");
+        let artifact_json = serde_json::json!({
+            "bytecode": { "object": "0x6080604052" },
+            "metadata": { "settings": { "metadata": { "bytecodeHash": "none", "appendCBOR": false } } },
+        });
+        std::fs::write(&artifact, artifact_json.to_string())?;
+
+        let deployment = FactoryDeployment {
+            factory: Address::from_str("0x4e59b44847b379578588920cA78FbF26c0B4956C")?,
+            salt: H256::from_low_u64_be(1),
+            constructor_args: Bytes::default(),
+        };
+        let init_code = Bytes::from_str("0x6080604052")?;
+        let address = get_create2_address(deployment.factory, deployment.salt, &init_code);
+
+        let client = RpcClient::InMemory(InMemoryClient::new());
+        let provider = MultiChainProvider::from_providers(HashMap::from([(
+            Chain::Goerli,
+            Arc::new(Provider::new(client)),
+        )]));
+        let deployed_code = ChainResponse {
+            responses: HashMap::from([(Chain::Goerli, Some(Bytes::from_str("0x6080")?))]),
+            errors: HashMap::new(),
+            unavailable: HashSet::new(),
+        };
+        let artifacts = vec![artifact.clone()];
+
+        let matches = provider.compare_factory_creation_code(
+            &project,
+            &artifacts,
+            address,
+            &deployment,
+            &deployed_code,
+        );
+        let contract_match = matches.responses[&Chain::Goerli].as_ref().unwrap();
+        assert_eq!(contract_match.artifact, artifact);
+        assert_eq!(contract_match.match_type, MatchType::Full);
+
+        // A different salt derives a different address, so nothing matches.
+        let deployment = FactoryDeployment { salt: H256::from_low_u64_be(2), ..deployment };
+        let matches = provider.compare_factory_creation_code(
+            &project,
+            &artifacts,
+            address,
+            &deployment,
+            &deployed_code,
+        );
+        assert!(matches.is_all_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_compare_creation_code_per_chain() -> Result<(), Box<dyn std::error::Error>> {
+        let project_dir = tempfile::tempdir()?;
+        std::fs::write(project_dir.path().join("foundry.toml"), "[profile.default]")?;
+        let project = Foundry::new(project_dir.path()).unwrap();
+        let mut artifacts = Vec::new();
+        for (name, code) in [("CounterA", "0x60806040aa"), ("CounterB", "0x60806040bb")] {
+            let artifact = project_dir.path().join(format!("{name}.json"));
+            let artifact_json = serde_json::json!({
+                "bytecode": { "object": code },
+                "metadata": { "settings": { "metadata": { "bytecodeHash": "none", "appendCBOR": false } } },
+            });
+            std::fs::write(&artifact, artifact_json.to_string())?;
+            artifacts.push(artifact);
+        }
+
+        let chains = [Chain::Mainnet, Chain::Optimism, Chain::Polygon, Chain::Goerli];
+        let providers = chains
+            .iter()
+            .map(|chain| {
+                (*chain, Arc::new(Provider::new(RpcClient::InMemory(InMemoryClient::new()))))
+            })
+            .collect();
+        let provider = MultiChainProvider::from_providers(providers);
+        let creation = |code: &str| -> Result<_, Box<dyn std::error::Error>> {
+            Ok(Some(ContractCreation {
+                tx_hash: TxHash::zero(),
+                block: BlockNumber::Number(1.into()),
+                creation_code: Bytes::from_str(code)?,
+                source: CreationCodeSource::ProvidedTxHash,
+            }))
+        };
+        let creation_data = ChainResponse {
+            responses: HashMap::from([
+                (Chain::Mainnet, creation("0x60806040aa")?),
+                (Chain::Optimism, creation("0x60806040bb")?),
+                (Chain::Polygon, None),
+                (Chain::Goerli, creation("0x60806040cc")?),
+            ]),
+            errors: HashMap::new(),
+            unavailable: HashSet::new(),
+        };
+
+        let matches = provider.compare_creation_code(&project, &artifacts, &creation_data);
+        assert_eq!(matches.responses.len(), chains.len());
+        assert_eq!(matches.get(&Chain::Mainnet).unwrap().artifact, artifacts[0]);
+        assert_eq!(matches.get(&Chain::Mainnet).unwrap().match_type, MatchType::Full);
+        assert_eq!(matches.get(&Chain::Optimism).unwrap().artifact, artifacts[1]);
+        assert!(matches.get(&Chain::Polygon).is_none());
+        assert!(matches.get(&Chain::Goerli).is_none());
+        Ok(())
+    }
+
+    /// Writes `artifact_json` to an artifact in a Foundry project and compares it against the
+    /// contract on each chain in `onchain_code`, which is used as both the contract's creation code
+    /// and deployed code. Returns the creation code matches and the deployed code matches.
+    fn compare_artifact(
+        artifact_json: serde_json::Value,
+        onchain_code: &[(Chain, Bytes)],
+    ) -> Result<[ChainResponse<ContractMatch>; 2], Box<dyn std::error::Error>> {
+        let project_dir = tempfile::tempdir()?;
+        std::fs::write(project_dir.path().join("foundry.toml"), "[profile.default]")?;
+        let project = Foundry::new(project_dir.path()).unwrap();
+        let artifact = project_dir.path().join("Contract.json");
+        std::fs::write(&artifact, artifact_json.to_string())?;
+        let artifacts = vec![artifact];
+
+        let providers = onchain_code
+            .iter()
+            .map(|(chain, _)| {
+                (*chain, Arc::new(Provider::new(RpcClient::InMemory(InMemoryClient::new()))))
+            })
+            .collect();
+        let provider = MultiChainProvider::from_providers(providers);
+        let deployed_code = ChainResponse {
+            responses: onchain_code
+                .iter()
+                .map(|(chain, code)| (*chain, Some(code.clone())))
+                .collect(),
+            errors: HashMap::new(),
+            unavailable: HashSet::new(),
+        };
+        let creation_data = ChainResponse {
+            responses: onchain_code
+                .iter()
+                .map(|(chain, code)| {
+                    let creation = ContractCreation {
+                        tx_hash: TxHash::zero(),
+                        block: BlockNumber::Number(1.into()),
+                        creation_code: code.clone(),
+                        source: CreationCodeSource::ProvidedTxHash,
+                    };
+                    (*chain, Some(creation))
+                })
+                .collect(),
+            errors: HashMap::new(),
+            unavailable: HashSet::new(),
+        };
+
+        Ok([
+            provider.compare_creation_code(&project, &artifacts, &creation_data),
+            provider.compare_deployed_code(&project, &artifacts, &deployed_code),
+        ])
+    }
+
+    #[test]
+    fn test_compare_partial_match() -> Result<(), Box<dyn std::error::Error>> {
+        // The metadata is CBOR encoding an IPFS hash made of `digest_byte` and solc 0.8.19.
+        let metadata = |digest_byte: &str| {
+            format!("a2646970667358221220{}64736f6c63430008130033", digest_byte.repeat(32))
+        };
+        let build_code = format!("0x6080604052{}", metadata("aa"));
+        let artifact_json = serde_json::json!({
+            "bytecode": { "object": build_code },
+            "deployedBytecode": { "object": build_code },
+            "metadata": {
+                "compiler": { "version": "0.8.19+commit.7dd6d404" },
+                "language": "Solidity",
+                "output": { "abi": [], "devdoc": null, "userdoc": null },
+                "settings": {
+                    "optimizer": { "enabled": false, "runs": 200 },
+                    "metadata": { "bytecodeHash": "ipfs", "appendCBOR": true },
+                },
+                "sources": {},
+                "version": 1,
+            },
+        });
+
+        // Mainnet's code differs from the build only in the metadata hash, so it's a partial
+        // match. Optimism's code is identical, and Polygon's leading code differs.
+        let onchain_code = [
+            (Chain::Mainnet, Bytes::from_str(&format!("0x6080604052{}", metadata("bb")))?),
+            (Chain::Optimism, Bytes::from_str(&build_code)?),
+            (Chain::Polygon, Bytes::from_str(&format!("0x6080604053{}", metadata("aa")))?),
+        ];
+        for matches in compare_artifact(artifact_json, &onchain_code)? {
+            assert!(matches.get(&Chain::Mainnet).unwrap().artifact.ends_with("Contract.json"));
+            assert_eq!(matches.get(&Chain::Mainnet).unwrap().match_type, MatchType::Partial);
+            assert_eq!(matches.get(&Chain::Optimism).unwrap().match_type, MatchType::Full);
+            assert!(matches.get(&Chain::Polygon).is_none());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_compare_yul_without_metadata() -> Result<(), Box<dyn std::error::Error>> {
+        // Yul artifacts have no metadata. The code's last two bytes don't encode a metadata length,
+        // so the entire code must be compared exactly instead of being split at a metadata hash.
+        let build_code = "0x600160005260206000f3";
+        let artifact_json = serde_json::json!({
+            "abi": [],
+            "bytecode": { "object": build_code },
+            "deployedBytecode": { "object": build_code },
+        });
+
+        // Mainnet's code is identical, and Optimism's differs in its last byte.
+        let onchain_code = [
+            (Chain::Mainnet, Bytes::from_str(build_code)?),
+            (Chain::Optimism, Bytes::from_str("0x600160005260206000fd")?),
+        ];
+        for matches in compare_artifact(artifact_json, &onchain_code)? {
+            assert!(matches.get(&Chain::Mainnet).unwrap().artifact.ends_with("Contract.json"));
+            assert_eq!(matches.get(&Chain::Mainnet).unwrap().match_type, MatchType::Full);
+            assert!(matches.get(&Chain::Optimism).is_none());
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_skips_failing_chains() -> Result<(), Box<dyn std::error::Error>> {
+        let address = Address::from_str("0x8d56e3e001132d84488DbacDbB01AfB8C3171242")?;
+        let code = Bytes::from_str("0x6080")?;
+        let working =
+            InMemoryClient::new().with_response("eth_getCode", (address, "latest"), &code)?;
+        // The failing chain has no fixture response, so every request to it errors.
+        let failing = InMemoryClient::new();
+        let breaker =
+            Arc::new(CircuitBreaker::new(1, Duration::from_secs(60), Duration::from_secs(60)));
+        let provider = MultiChainProvider::from_providers(HashMap::from([
+            (Chain::Mainnet, Arc::new(Provider::new(RpcClient::InMemory(working)))),
+            (Chain::Optimism, Arc::new(Provider::new(RpcClient::InMemory(failing)))),
+        ]))
+        .with_circuit_breaker(breaker.clone());
+
+        let deployed_code = provider.get_deployed_code(address).await?;
+        assert_eq!(deployed_code.statuses()[&Chain::Optimism], ChainStatus::RpcError);
+        assert!(!breaker.is_available(Chain::Optimism));
+
+        let deployed_code = provider.get_deployed_code(address).await?;
+        assert_eq!(deployed_code.statuses()[&Chain::Mainnet], ChainStatus::Ok);
+        assert_eq!(deployed_code.statuses()[&Chain::Optimism], ChainStatus::Unavailable);
+        assert!(deployed_code.errors[&Chain::Optimism].contains(CHAIN_UNAVAILABLE));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_deployed_code_from_archive() -> Result<(), Box<dyn std::error::Error>> {
+        let address = Address::from_str("0x8d56e3e001132d84488DbacDbB01AfB8C3171242")?;
+        let tx_hash = TxHash::from_low_u64_be(1);
+        let code = Bytes::from_str("0x6080")?;
+        let tx = Transaction { hash: tx_hash, block_number: Some(5.into()), ..Default::default() };
+
+        // The primary provider has no code, and the archive only has it at the creation block.
+        let primary = InMemoryClient::new().with_response(
+            "eth_getCode",
+            (address, "latest"),
+            Bytes::new(),
+        )?;
+        let archive = InMemoryClient::new()
+            .with_response("eth_getCode", (address, "latest"), Bytes::new())?
+            .with_response("eth_getCode", (address, "0x5"), &code)?
+            .with_response("eth_getTransactionByHash", [tx_hash], &tx)?;
+        let provider = MultiChainProvider::from_providers(HashMap::from([(
+            Chain::Mainnet,
+            Arc::new(Provider::new(RpcClient::InMemory(primary))),
+        )]))
+        .with_archive_providers(HashMap::from([(
+            Chain::Mainnet,
+            Arc::new(Provider::new(RpcClient::InMemory(archive))),
+        )]));
+
+        let deployed_code = provider.get_deployed_code(address).await?;
+        assert_eq!(deployed_code.statuses()[&Chain::Mainnet], ChainStatus::NoCode);
+
+        let tx_hashes = HashMap::from([(Chain::Mainnet, tx_hash)]);
+        let deployed_code =
+            provider.get_deployed_code_with_overrides(address, &HashMap::new(), &tx_hashes).await?;
+        assert_eq!(deployed_code.get(&Chain::Mainnet), Some(&code));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_resolve_minimal_proxies() -> Result<(), Box<dyn std::error::Error>> {
+        let address = Address::from_str("0x8d56e3e001132d84488DbacDbB01AfB8C3171242")?;
+        let implementation = Address::from_str("0x1f98431c8ad98523631ae4a59f267346ea31f984")?;
+        let proxy_code = Bytes::from_str(&format!(
+            "363d3d373d3d3d363d73{}5af43d82803e903d91602b57fd5bf3",
+            ethers::utils::hex::encode(implementation)
+        ))?;
+        let implementation_code = Bytes::from_str("0x6080")?;
+
+        // Mainnet has a minimal proxy, and Optimism has an ordinary contract at the address.
+        let mainnet = InMemoryClient::new()
+            .with_response("eth_getCode", (address, "latest"), &proxy_code)?
+            .with_response("eth_getCode", (implementation, "latest"), &implementation_code)?;
+        let optimism = InMemoryClient::new().with_response(
+            "eth_getCode",
+            (address, "latest"),
+            &implementation_code,
+        )?;
+        let provider = MultiChainProvider::from_providers(HashMap::from([
+            (Chain::Mainnet, Arc::new(Provider::new(RpcClient::InMemory(mainnet)))),
+            (Chain::Optimism, Arc::new(Provider::new(RpcClient::InMemory(optimism)))),
+        ]));
+
+        let mut deployed_code = provider.get_deployed_code(address).await?;
+        let proxies = provider.resolve_minimal_proxies(&mut deployed_code).await;
+        assert_eq!(
+            proxies,
+            HashMap::from([(
+                Chain::Mainnet,
+                MinimalProxy { implementation, proxy_runtime_code: proxy_code }
+            )])
+        );
+        assert_eq!(deployed_code.get(&Chain::Mainnet), Some(&implementation_code));
+        assert_eq!(deployed_code.get(&Chain::Optimism), Some(&implementation_code));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_deployed_code_with_overrides() -> Result<(), Box<dyn std::error::Error>> {
+        let address = Address::from_str("0x8d56e3e001132d84488DbacDbB01AfB8C3171242")?;
+        let code = Bytes::from_str("0x6080")?;
+        // Neither client has fixture responses, so any RPC request errors.
+        let provider = MultiChainProvider::from_providers(HashMap::from([
+            (Chain::Mainnet, Arc::new(Provider::new(RpcClient::InMemory(InMemoryClient::new())))),
+            (Chain::Optimism, Arc::new(Provider::new(RpcClient::InMemory(InMemoryClient::new())))),
+        ]));
+
+        let overrides = HashMap::from([(Chain::Mainnet, code.clone())]);
+        let deployed_code =
+            provider.get_deployed_code_with_overrides(address, &overrides, &HashMap::new()).await?;
+        assert_eq!(deployed_code.get(&Chain::Mainnet), Some(&code));
+        assert_eq!(deployed_code.statuses()[&Chain::Mainnet], ChainStatus::Ok);
+        assert_eq!(deployed_code.statuses()[&Chain::Optimism], ChainStatus::RpcError);
+
+        let overrides = HashMap::from([(Chain::Mainnet, Bytes::new())]);
+        let deployed_code =
+            provider.get_deployed_code_with_overrides(address, &overrides, &HashMap::new()).await?;
+        assert_eq!(deployed_code.statuses()[&Chain::Mainnet], ChainStatus::NoCode);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_find_creation_data_batched(
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let address = Address::from_str("0xc9E7278C9f386f307524eBbAaafcfEb649Be39b4")?;
+        let tx_hash = TxHash::from_low_u64_be(1);
+        let creation_code = Bytes::from_str("0x6080604052")?;
+        let tx = Transaction {
+            hash: tx_hash,
+            block_number: Some(1.into()),
+            input: creation_code.clone(),
+            ..Default::default()
+        };
+        let receipt = TransactionReceipt {
+            transaction_hash: tx_hash,
+            contract_address: Some(address),
+            ..Default::default()
+        };
+        let client = InMemoryClient::new()
+            .with_response("eth_getTransactionByHash", [tx_hash], &tx)?
+            .with_response("eth_getTransactionReceipt", [tx_hash], &receipt)?;
+        let provider = Arc::new(Provider::new(RpcClient::InMemory(client)));
+
+        for batch in [true, false] {
+            let lookup = creation_lookup(batch);
+            let creation_data =
+                find_creation_data(&provider, Chain::Mainnet, address, tx_hash, &lookup).await?;
+            assert_eq!(creation_data.creation_code, creation_code);
+            assert_eq!(creation_data.source, CreationCodeSource::ProvidedTxHash);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_new_from_configured_chains() {
+        let chain = |rpc_url: &str, archive_rpc_url: Option<&str>| ChainSettings {
+            rpc_url: rpc_url.to_string(),
+            archive_rpc_url: archive_rpc_url.map(str::to_string),
+        };
+        // Environment variables are read from this map rather than the process environment, which
+        // other tests share.
+        let env_var = |name: &str| {
+            (name == "CONFIGURED_RPC_URL").then(|| "http://localhost:8545".to_string())
+        };
+        let chains = HashMap::from([
+            (1, chain("http://localhost:8545", Some("$UNSET_ARCHIVE_RPC_URL"))),
+            (31337, chain("$CONFIGURED_RPC_URL", Some("http://localhost:8546"))),
+            (137, chain("$UNSET_RPC_URL", None)),
+        ]);
+        let provider = MultiChainProvider::new_with_env(&chains, env_var).unwrap();
+        // Chains whose RPC URL is unset are skipped rather than failing every chain.
+        assert_eq!(provider.chains, vec![Chain::Mainnet, Chain::Dev]);
+        assert_eq!(provider.archive_providers.keys().collect::<Vec<_>>(), vec![&Chain::Dev]);
+
+        // Restricting the provider to some chains drops the other chains' providers.
+        let only_mainnet = provider.with_only_chains(&[Chain::Mainnet]).unwrap();
+        assert_eq!(only_mainnet.chains, vec![Chain::Mainnet]);
+        assert_eq!(only_mainnet.providers.keys().collect::<Vec<_>>(), vec![&Chain::Mainnet]);
+        assert!(only_mainnet.archive_providers.is_empty());
+        assert_eq!(
+            provider.with_only_chains(&[Chain::Polygon]).err(),
+            Some(ChainError::NotConfigured(Chain::Polygon))
+        );
+
+        // Unconfigured chains, unknown chain IDs, and invalid URLs are errors.
+        assert_eq!(
+            provider_url_from_chain(Chain::Optimism, &chains),
+            Err(ChainError::NotConfigured(Chain::Optimism))
+        );
+        let unknown = HashMap::from([(123_456_789, chain("http://localhost:8545", None))]);
+        assert_eq!(
+            MultiChainProvider::new_with_env(&unknown, env_var).err(),
+            Some(ChainError::UnknownChainId(123_456_789))
+        );
+        let invalid = HashMap::from([(1, chain("not a url", None))]);
+        assert!(matches!(
+            MultiChainProvider::new_with_env(&invalid, env_var),
+            Err(ChainError::InvalidRpcUrl(Chain::Mainnet, _))
+        ));
+        assert_eq!(chain_from_id(1), Ok(Chain::Mainnet));
+        assert_eq!(chain_from_id(123_456_789), Err(ChainError::UnknownChainId(123_456_789)));
+    }
+
+    #[tokio::test]
+    async fn test_get_creation_code_discovers_traced_deployment(
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let address = Address::from_str("0x3333333333333333333333333333333333333333")?;
+        let factory = Address::from_str("0x2222222222222222222222222222222222222222")?;
+        let tx_hash = TxHash::from_low_u64_be(1);
+        let creation_code = Bytes::from_str("0x6080604052")?;
+        let tx = Transaction {
+            hash: tx_hash,
+            block_number: Some(1.into()),
+            to: Some(factory),
+            input: Bytes::from_str("0x1234")?,
+            ..Default::default()
+        };
+        let traces = serde_json::json!([{
+            "action": {
+                "from": factory,
+                "gas": "0x1000",
+                "init": creation_code,
+                "value": "0x0"
+            },
+            "blockHash": H256::from_low_u64_be(1),
+            "blockNumber": 1,
+            "result": { "address": address, "code": "0x6080", "gasUsed": "0x10" },
+            "subtraces": 0,
+            "traceAddress": [0],
+            "type": "create"
+        }]);
+        let client = InMemoryClient::new()
+            .with_response(
+                "ots_getContractCreator",
+                [address],
+                serde_json::json!({ "hash": tx_hash, "creator": factory }),
+            )?
+            .with_response("eth_getTransactionByHash", [tx_hash], &tx)?
+            .with_response("trace_transaction", [tx_hash], &traces)?;
+        let provider = Arc::new(Provider::new(RpcClient::InMemory(client)));
+        let providers = HashMap::from([(Chain::Mainnet, provider)]);
+
+        // Without a transaction hash, the creation transaction is discovered and traced on chains
+        // whose RPCs support `ots_getContractCreator`.
+        let multi_provider = MultiChainProvider::from_providers(providers.clone())
+            .with_contract_creator_lookup([Chain::Mainnet]);
+        let creation_data = multi_provider.get_creation_code(address, None).await?;
+        let creation = creation_data.get(&Chain::Mainnet).ok_or("Creation data not found")?;
+        assert_eq!(creation.tx_hash, tx_hash);
+        assert_eq!(creation.creation_code, creation_code);
+        assert_eq!(creation.source, CreationCodeSource::Traced);
+
+        // Other chains don't look up the creation transaction, and have no creation data.
+        let multi_provider = MultiChainProvider::from_providers(providers);
+        let creation_data = multi_provider.get_creation_code(address, None).await?;
+        assert!(creation_data.get(&Chain::Mainnet).is_none());
+        assert!(creation_data.errors.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_creation_code_searches_blocks(
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let address = Address::from_str("0xc9E7278C9f386f307524eBbAaafcfEb649Be39b4")?;
+        let other_tx_hash = TxHash::from_low_u64_be(1);
+        let tx_hash = TxHash::from_low_u64_be(2);
+        let creation_code = Bytes::from_str("0x6080604052")?;
+        let deployment_block = 5u64;
+        let tx = Transaction {
+            hash: tx_hash,
+            block_number: Some(deployment_block.into()),
+            input: creation_code.clone(),
+            ..Default::default()
+        };
+        let block = Block::<TxHash> {
+            number: Some(deployment_block.into()),
+            transactions: vec![other_tx_hash, tx_hash],
+            ..Default::default()
+        };
+        let other_receipt =
+            TransactionReceipt { transaction_hash: other_tx_hash, ..Default::default() };
+        let receipt = TransactionReceipt {
+            transaction_hash: tx_hash,
+            contract_address: Some(address),
+            ..Default::default()
+        };
+
+        // The contract has code from the deployment block through the latest block, 16.
+        let mut client = InMemoryClient::new()
+            .with_response("eth_blockNumber", (), "0x10")?
+            .with_response("eth_getBlockByNumber", ("0x5", false), &block)?
+            .with_response("eth_getTransactionReceipt", [other_tx_hash], &other_receipt)?
+            .with_response("eth_getTransactionReceipt", [tx_hash], &receipt)?
+            .with_response("eth_getTransactionByHash", [tx_hash], &tx)?;
+        for block_number in 0..=16u64 {
+            let code = if block_number < deployment_block { "0x" } else { "0x6080" };
+            client = client.with_response(
+                "eth_getCode",
+                (address, format!("{block_number:#x}")),
+                code,
+            )?;
+        }
+        let provider = Arc::new(Provider::new(RpcClient::InMemory(client)));
+        let providers = HashMap::from([(Chain::Mainnet, provider)]);
+
+        // The RPC doesn't support `ots_getContractCreator`, so even with the lookup enabled the
+        // deployment block is searched.
+        let multi_provider = MultiChainProvider::from_providers(providers.clone())
+            .with_contract_creator_lookup([Chain::Mainnet])
+            .with_creation_search_max_blocks(Some(16));
+        let creation_data = multi_provider.get_creation_code(address, None).await?;
+        let creation = creation_data.get(&Chain::Mainnet).ok_or("Creation data not found")?;
+        assert_eq!(creation.tx_hash, tx_hash);
+        assert_eq!(creation.block, BlockNumber::from(deployment_block));
+        assert_eq!(creation.creation_code, creation_code);
+
+        // A deployment before the searched range is reported as an error.
+        let multi_provider =
+            MultiChainProvider::from_providers(providers).with_creation_search_max_blocks(Some(8));
+        let creation_data = multi_provider.get_creation_code(address, None).await?;
+        let error = creation_data.errors.get(&Chain::Mainnet).ok_or("Error not found")?;
+        assert!(error.to_string().contains("already had code at block 8"), "{error}");
+        Ok(())
     }
 
     #[tokio::test]
@@ -433,19 +1840,26 @@ mod tests {
 
         #[rustfmt::skip]
         let test_cases = vec![
-            ("0xc9E7278C9f386f307524eBbAaafcfEb649Be39b4", "0x005c7b8f0ccbd49ff8892ec0ef27058b79d9a1ed6592faaa44699cccce1aa350", "Counter, CREATE"),
-            ("0x1F98431c8aD98523631AE4a59f267346ea31F984", "0x7f0c3a53db387e9b3ff4af69c2ae9c45182ba189b2c1d3607e6a5e1cdab29fc8", "UniV3Factory, CREATE"),
-            ("0x00000000000001ad428e4906aE43D8F9852d0dD6", "0x48ad9bd93b31a55c08cfd99b48bea139e9f448f0bff1ab03d064ae6dce09f7f6", "Seaport, CREATE2"),
+            ("0xc9E7278C9f386f307524eBbAaafcfEb649Be39b4", "0x005c7b8f0ccbd49ff8892ec0ef27058b79d9a1ed6592faaa44699cccce1aa350", "Counter, CREATE", CreationCodeSource::ProvidedTxHash),
+            ("0x1F98431c8aD98523631AE4a59f267346ea31F984", "0x7f0c3a53db387e9b3ff4af69c2ae9c45182ba189b2c1d3607e6a5e1cdab29fc8", "UniV3Factory, CREATE", CreationCodeSource::ProvidedTxHash),
+            ("0x00000000000001ad428e4906aE43D8F9852d0dD6", "0x48ad9bd93b31a55c08cfd99b48bea139e9f448f0bff1ab03d064ae6dce09f7f6", "Seaport, CREATE2", CreationCodeSource::KnownFactory("0age create2 factory".into())),
         ];
 
-        let tasks = test_cases.into_iter().map(|(contract, tx_hash, name)| {
+        let tasks = test_cases.into_iter().map(|(contract, tx_hash, name, source)| {
             let provider = provider.clone();
             async move {
                 let contract_addr = Address::from_str(contract)?;
                 let expected_tx_hash = TxHash::from_str(tx_hash)?;
-                let creation_data =
-                    find_creation_data(&provider, contract_addr, Some(expected_tx_hash)).await?;
+                let creation_data = find_creation_data(
+                    &provider,
+                    Chain::Goerli,
+                    contract_addr,
+                    expected_tx_hash,
+                    &creation_lookup(false),
+                )
+                .await?;
                 assert_eq!(creation_data.tx_hash, expected_tx_hash, "{name}");
+                assert_eq!(creation_data.source, source, "{name}");
                 Ok::<(), Box<dyn std::error::Error + Send + Sync>>(())
             }
         });
@@ -453,4 +1867,33 @@ mod tests {
         try_join_all(tasks).await?;
         Ok(())
     }
+
+    #[tokio::test]
+    #[ignore = "Requires a mainnet archive RPC that supports tracing, set as MAINNET_ARCHIVE_RPC_URL"]
+    async fn test_get_creation_code_discovers_factory_deployment(
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if dotenv().is_err() {
+            println!("WARNING: No .env file found, using default environment variables.");
+        }
+        let provider = http_provider(Chain::Mainnet, env::var("MAINNET_ARCHIVE_RPC_URL")?)?;
+        let multi_provider =
+            MultiChainProvider::from_providers(HashMap::from([(Chain::Mainnet, provider)]))
+                .with_creation_search_max_blocks(Some(u64::MAX));
+
+        // The Uniswap V2 USDC/WETH pair was deployed by the Uniswap V2 factory, which isn't a known
+        // factory, so the creation transaction is found by searching for the deployment block and
+        // the creation code by tracing it.
+        let pair = Address::from_str("0xB4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc")?;
+        let creation_data = multi_provider.get_creation_code(pair, None).await?;
+        assert!(creation_data.errors.is_empty(), "{:?}", creation_data.errors);
+        let creation = creation_data.get(&Chain::Mainnet).expect("No creation data found");
+        assert_eq!(creation.source, CreationCodeSource::Traced);
+
+        // Pairs have no constructor arguments, so the creation code is the pair init code, whose
+        // hash the Uniswap V2 periphery contracts use to compute pair addresses.
+        let init_code_hash =
+            H256::from_str("0x96e8ac4277198ff8b6f785478aa9a39f403cb768dd02cbee326c3e7da348845f")?;
+        assert_eq!(H256::from(keccak256(&creation.creation_code)), init_code_hash);
+        Ok(())
+    }
 }
@@ -0,0 +1,152 @@
+use ethers::providers::ProviderError;
+use std::{
+    error::Error,
+    future::Future,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// JSON-RPC error codes that RPCs return when a request is rate limited. `429` is used by RPCs
+/// that forward the HTTP status, and `-32005` is the "limit exceeded" code from EIP-1474.
+const RATE_LIMIT_CODES: [i64; 2] = [429, -32005];
+
+/// Substrings of error messages that indicate a transient failure, for RPCs that don't return a
+/// rate limit code or whose error isn't a JSON-RPC response.
+const TRANSIENT_MESSAGES: [&str; 5] =
+    ["rate limit", "too many requests", "timeout", "timed out", "temporarily unavailable"];
+
+/// How RPC calls are retried after transient failures, such as rate limiting or timeouts. Each
+/// retry waits twice as long as the previous one, starting from `base_delay`, plus up to
+/// `base_delay` of jitter so concurrent requests don't retry in lockstep.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// The maximum number of times a call is made, including the first call. A value of 1 or less
+    /// disables retries.
+    pub attempts: u32,
+    /// The delay before the first retry.
+    pub base_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self { attempts: 3, base_delay: Duration::from_millis(200) }
+    }
+}
+
+impl RetryPolicy {
+    /// Calls `call` until it succeeds, it fails with an error that isn't transient, or `attempts`
+    /// calls have been made, and returns the last result. Successful results are returned as is,
+    /// so a call that succeeds with empty code isn't retried.
+    pub async fn retry<T, E, F, Fut>(&self, mut call: F) -> Result<T, E>
+    where
+        E: TransientError,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match call().await {
+                Err(err) if attempt < self.attempts && err.is_transient() => {
+                    let delay = self.delay(attempt);
+                    tracing::warn!("RPC call failed, retrying in {delay:?}: {err}");
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Returns the delay before the retry that follows the given attempt.
+    fn delay(&self, attempt: u32) -> Duration {
+        let backoff = self.base_delay.saturating_mul(2u32.saturating_pow(attempt - 1));
+        // The jitter only needs to differ between concurrent requests, so the clock's sub-second
+        // nanoseconds are random enough.
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().subsec_nanos();
+        let jitter = self.base_delay.mul_f64(f64::from(nanos) / 1e9);
+        backoff + jitter
+    }
+}
+
+/// An error that can tell whether the call that returned it may succeed if it's retried.
+pub trait TransientError: std::fmt::Display {
+    /// Returns true if the failure is transient, e.g. because the RPC rate limited the request,
+    /// timed out, or couldn't be reached.
+    fn is_transient(&self) -> bool;
+}
+
+impl TransientError for ProviderError {
+    fn is_transient(&self) -> bool {
+        let transient_message = || {
+            let message = self.to_string().to_lowercase();
+            TRANSIENT_MESSAGES.iter().any(|transient| message.contains(transient))
+        };
+        match self {
+            ProviderError::HTTPError(_) => true,
+            ProviderError::JsonRpcClientError(err) => match err.as_error_response() {
+                Some(response) => RATE_LIMIT_CODES.contains(&response.code) || transient_message(),
+                // Errors that aren't JSON-RPC responses or malformed responses are transport
+                // failures, e.g. a connection that was refused or reset.
+                None => err.as_serde_error().is_none() || transient_message(),
+            },
+            _ => transient_message(),
+        }
+    }
+}
+
+impl TransientError for Box<dyn Error + Send + Sync> {
+    fn is_transient(&self) -> bool {
+        self.downcast_ref::<ProviderError>().map_or(false, ProviderError::is_transient)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::RpcClientError;
+    use ethers::providers::{JsonRpcError, MockError};
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Returns a provider error for a JSON-RPC error response with the given code.
+    fn rpc_error(code: i64) -> ProviderError {
+        let error = JsonRpcError { code, message: "error".to_string(), data: None };
+        RpcClientError::InMemory(MockError::JsonRpcError(error)).into()
+    }
+
+    #[tokio::test]
+    async fn test_retry_transient_failures() {
+        let policy = RetryPolicy { attempts: 3, base_delay: Duration::from_millis(1) };
+        let calls = AtomicU32::new(0);
+
+        // Rate limited calls are retried until they succeed.
+        let result = policy
+            .retry(|| async {
+                match calls.fetch_add(1, Ordering::SeqCst) {
+                    0 | 1 => Err(rpc_error(429)),
+                    _ => Ok("0x"),
+                }
+            })
+            .await;
+        assert_eq!(result.unwrap(), "0x");
+        assert_eq!(calls.swap(0, Ordering::SeqCst), 3);
+
+        // Calls give up after the last attempt.
+        let result: Result<(), _> = policy
+            .retry(|| async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(rpc_error(-32005))
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(calls.swap(0, Ordering::SeqCst), 3);
+
+        // Failures that aren't transient aren't retried.
+        let result: Result<(), _> = policy
+            .retry(|| async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(rpc_error(-32601))
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
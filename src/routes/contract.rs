@@ -1,6 +1,13 @@
-use crate::provider::{contract_runtime_code, provider_from_chain, provider_url_from_chain};
+use crate::{
+    bytecode::MatchType,
+    provider::{
+        chain_from_id, contract_runtime_code, provider_from_chain, provider_url_from_chain,
+    },
+    routes::SuccessfulVerification,
+    startup::AppState,
+};
 use axum::{
-    extract::Query,
+    extract::{Query, State},
     http,
     response::{IntoResponse, Response},
     Json,
@@ -8,7 +15,7 @@ use axum::{
 use ethers::types::{Address, Bytes, Chain};
 use heimdall::decompile::DecompileBuilder;
 use serde::{Deserialize, Serialize};
-use std::str::FromStr;
+use std::{error::Error, str::FromStr, sync::Arc};
 use tempfile::TempDir;
 
 /// Data that the caller provides to lookup a contract.
@@ -23,7 +30,18 @@ pub struct ContractQuery {
 /// Data returned for a verified contract.
 #[derive(Serialize)]
 pub struct VerifiedResponse {
-    // TODO This should probably use the same `SuccessfulVerification` struct from `verify.rs`.
+    /// Chain the contract is deployed on.
+    pub chain_id: Chain,
+    /// Address of the contract.
+    pub address: Address,
+    /// True if the contract's creation code or deployed code fully matched on the chain, and false
+    /// if they only partially matched, i.e. the metadata hash differs.
+    pub verified: bool,
+    /// The strongest match of the contract's creation code and deployed code on the chain.
+    pub match_type: MatchType,
+    /// The stored result of the contract's verification, including its sources, ABI, and match
+    /// types.
+    pub verification: SuccessfulVerification,
 }
 
 /// Data returned for an unverified contract that was decompiled.
@@ -55,9 +73,8 @@ pub struct ErrorResponse {
 
 /// Response for the contract route.
 enum ApiResponse {
-    /// Contract is verified so verification data is returned. This is currently unused since Cove
-    /// does not persist verification data. This is why the leading underscore is used.
-    _Verified(VerifiedResponse),
+    /// Contract is verified so verification data is returned.
+    Verified(Box<VerifiedResponse>),
     /// Contract is not verified so decompilation data is returned.
     Decompiled(DecompiledResponse),
     /// An error occurred while processing the request.
@@ -67,9 +84,7 @@ enum ApiResponse {
 impl IntoResponse for ApiResponse {
     fn into_response(self) -> Response {
         match self {
-            ApiResponse::_Verified(success) => {
-                (http::StatusCode::OK, Json(success)).into_response()
-            }
+            ApiResponse::Verified(success) => (http::StatusCode::OK, Json(success)).into_response(),
             ApiResponse::Decompiled(success) => {
                 (http::StatusCode::OK, Json(success)).into_response()
             }
@@ -80,19 +95,51 @@ impl IntoResponse for ApiResponse {
     }
 }
 
-/// This route is intended to return data for a contract that was previously verified, and for
-/// unverified contracts falls back to decompiling the bytecode with heimdall. However, Cove does
-/// not currently persist verification results in a database. As a result, this route will always
-/// decompile the bytecode with heimdall.
-pub async fn contract(Query(contract_query): Query<ContractQuery>) -> impl IntoResponse {
-    let chain_id = Chain::try_from(contract_query.chain_id).unwrap();
-    let address = Address::from_str(&contract_query.address).unwrap();
+/// Returns the stored verification of a contract that was previously verified, and for unverified
+/// contracts falls back to decompiling the bytecode with heimdall. Verifications are only stored if
+/// a database directory is configured.
+pub async fn contract(
+    State(state): State<Arc<AppState>>,
+    Query(contract_query): Query<ContractQuery>,
+) -> impl IntoResponse {
+    let chain_id = match chain_from_id(contract_query.chain_id) {
+        Ok(chain) => chain,
+        Err(err) => return ApiResponse::Error(ErrorResponse { error: err.to_string() }),
+    };
+    let Ok(address) = Address::from_str(&contract_query.address) else {
+        let error = format!("Invalid contract address {}", contract_query.address);
+        return ApiResponse::Error(ErrorResponse { error })
+    };
 
-    // TODO Check if the contract is verified.
+    // If the contract is verified, return its verification.
+    let verification = state.store.as_ref().and_then(|store| store.get(chain_id, address));
+    if let Some(verification) = verification {
+        let match_type = verification.matches.get(&chain_id).map_or(MatchType::None, |m| {
+            strongest_match_type(m.creation_code_match_type, m.deployed_code_match_type)
+        });
+        let verified = matches!(match_type, MatchType::Full | MatchType::FullWithConstructorArgs);
+        let response = VerifiedResponse { chain_id, address, verified, match_type, verification };
+        return ApiResponse::Verified(Box::new(response))
+    }
 
     // Otherwise, decompile and return what we can.
-    let provider = provider_from_chain(chain_id);
-    let runtime_code = contract_runtime_code(&provider, address).await;
+    let chains = &state.settings.chains;
+    let rpc_url = match provider_url_from_chain(chain_id, chains) {
+        Ok(rpc_url) => rpc_url,
+        Err(err) => return ApiResponse::Error(ErrorResponse { error: err.to_string() }),
+    };
+    let provider = match provider_from_chain(chain_id, chains) {
+        Ok(provider) => provider,
+        Err(err) => return ApiResponse::Error(ErrorResponse { error: err.to_string() }),
+    };
+    let retry = state.settings.rpc.retry_policy();
+    let runtime_code = match contract_runtime_code(&provider, address, &retry).await {
+        Ok(runtime_code) => runtime_code,
+        Err(err) => {
+            let error = format!("Failed to fetch runtime code on chain ID {chain_id}: {err}");
+            return ApiResponse::Error(ErrorResponse { error })
+        }
+    };
     println!("runtime_code: {:?}", runtime_code);
 
     if runtime_code == Bytes::from_str("0x").unwrap() {
@@ -109,7 +156,7 @@ pub async fn contract(Query(contract_query): Query<ContractQuery>) -> impl IntoR
         .include_sol(true)
         .verbosity(0)
         .skip_resolving(false)
-        .rpc(&provider_url_from_chain(chain_id))
+        .rpc(&rpc_url)
         .decompile();
 
     // Read in the files generated by heimdall, their names are always the same:
@@ -126,7 +173,7 @@ pub async fn contract(Query(contract_query): Query<ContractQuery>) -> impl IntoR
     let response = DecompiledResponse {
         chain_id,
         address,
-        verified: true,
+        verified: false,
         abi,
         bytecode,
         disassembled,
@@ -134,3 +181,43 @@ pub async fn contract(Query(contract_query): Query<ContractQuery>) -> impl IntoR
     };
     ApiResponse::Decompiled(response)
 }
+
+/// Disassembles the given runtime code with heimdall. This is CPU bound and blocks while heimdall
+/// writes its output files, so it should be run with `spawn_blocking` from async code.
+pub fn disassemble(runtime_code: &Bytes) -> Result<String, Box<dyn Error + Send + Sync>> {
+    let temp_dir = TempDir::new()?;
+    let output = temp_dir.path().to_str().ok_or("Temporary directory path is not valid UTF-8")?;
+    DecompileBuilder::new(&runtime_code.to_string())
+        .output(output)
+        .include_sol(false)
+        .verbosity(0)
+        .skip_resolving(true)
+        .decompile();
+    Ok(std::fs::read_to_string(temp_dir.path().join("disassembled.asm"))?)
+}
+
+/// Returns the strongest of the creation code and deployed code match types, where full matches are
+/// stronger than partial matches.
+fn strongest_match_type(creation: MatchType, deployed: MatchType) -> MatchType {
+    let strength = |match_type: &MatchType| match match_type {
+        MatchType::Full | MatchType::FullWithConstructorArgs => 2,
+        MatchType::Partial => 1,
+        MatchType::None => 0,
+    };
+    [creation, deployed].into_iter().max_by_key(strength).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strongest_match_type() {
+        let (full, partial, none) = (MatchType::Full, MatchType::Partial, MatchType::None);
+        let full_with_args = MatchType::FullWithConstructorArgs;
+        assert_eq!(strongest_match_type(full, partial), full);
+        assert_eq!(strongest_match_type(none, full_with_args), full_with_args);
+        assert_eq!(strongest_match_type(partial, none), partial);
+        assert_eq!(strongest_match_type(none, none), none);
+    }
+}
@@ -1,6 +1,101 @@
-use axum::http;
+use crate::{provider::chain_from_id, startup::AppState, tools::ToolVersions};
+use axum::{extract::State, http, response::IntoResponse, Json};
+use ethers::types::Chain;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+/// The version of the heimdall library linked into the server, which decompiles unverified
+/// contracts for the `/contract` route. This must be updated when heimdall is upgraded.
+const HEIMDALL_VERSION: &str = "0.4.8";
+
+/// How long each chain's RPC has to respond to the detailed health check.
+const RPC_TIMEOUT: Duration = Duration::from_secs(5);
 
 /// Health check route that returns a 200 OK status code if the server is running.
 pub async fn health_check() -> http::StatusCode {
     http::StatusCode::OK
 }
+
+/// The status of an external dependency of the server.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DependencyStatus {
+    /// Whether the dependency is available.
+    pub available: bool,
+    /// The version of the dependency, if it's available.
+    pub version: Option<String>,
+}
+
+impl From<Option<String>> for DependencyStatus {
+    fn from(version: Option<String>) -> Self {
+        Self { available: version.is_some(), version }
+    }
+}
+
+/// The status of a chain's RPC.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcStatus {
+    /// Whether the RPC responded with the latest block number.
+    pub reachable: bool,
+    /// The latest block number returned by the RPC.
+    pub block_number: Option<u64>,
+    /// The error returned by the RPC, if it couldn't be reached.
+    pub error: Option<String>,
+}
+
+/// A report of every external dependency the server relies on.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DetailedHealth {
+    /// Whether every dependency is available and every chain's RPC is reachable.
+    pub healthy: bool,
+    /// The status of `git`, used to clone repositories.
+    pub git: DependencyStatus,
+    /// The status of `forge`, used to build Foundry projects.
+    pub forge: DependencyStatus,
+    /// The status of heimdall, used to decompile unverified contracts. Heimdall is linked into the
+    /// server, so it's always available.
+    pub heimdall: DependencyStatus,
+    /// The status of the RPC of each supported chain.
+    pub rpcs: HashMap<Chain, RpcStatus>,
+}
+
+/// Detailed health check route that reports whether `git`, `forge`, and heimdall are available and
+/// their versions, and whether each chain's RPC is reachable. Tools are detected when the route is
+/// called rather than at startup, so this reflects the current state of the server. Returns 200 OK
+/// if everything is available, and 503 Service Unavailable with the same report otherwise.
+pub async fn health_check_detailed(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let tools = tokio::task::spawn_blocking(ToolVersions::detect).await.unwrap_or_default();
+    let connectivity = match state.provider() {
+        Ok(provider) => provider.check_connectivity(RPC_TIMEOUT).await,
+        // Without a provider no chain can be checked, so every configured chain is unreachable.
+        Err(err) => {
+            let chains = state.settings.chains.keys().filter_map(|id| chain_from_id(*id).ok());
+            chains.map(|chain| (chain, Err(err.to_string()))).collect()
+        }
+    };
+    let rpcs: HashMap<Chain, RpcStatus> = connectivity
+        .into_iter()
+        .map(|(chain, result)| {
+            let status = match result {
+                Ok(block_number) => {
+                    RpcStatus { reachable: true, block_number: Some(block_number), error: None }
+                }
+                Err(error) => {
+                    RpcStatus { reachable: false, block_number: None, error: Some(error) }
+                }
+            };
+            (chain, status)
+        })
+        .collect();
+
+    let health = DetailedHealth {
+        healthy: tools.ensure_installed().is_ok() && rpcs.values().all(|rpc| rpc.reachable),
+        git: tools.git.into(),
+        forge: tools.forge.into(),
+        heimdall: Some(HEIMDALL_VERSION.to_string()).into(),
+        rpcs,
+    };
+    let status =
+        if health.healthy { http::StatusCode::OK } else { http::StatusCode::SERVICE_UNAVAILABLE };
+    (status, Json(health))
+}
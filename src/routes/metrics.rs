@@ -0,0 +1,11 @@
+use crate::startup::AppState;
+use axum::{extract::State, http::header, response::IntoResponse};
+use std::sync::Arc;
+
+/// Returns metrics about verifications in the Prometheus text exposition format: the number of
+/// verifications by outcome, the duration of builds, clones, and RPC operations, and the current
+/// load on the verification scheduler.
+pub async fn metrics(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let body = state.metrics.render(&state.scheduler.metrics());
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body)
+}
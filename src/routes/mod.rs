@@ -1,15 +1,39 @@
-/// This route is intended to return data for a contract that was previously verified, and for
-/// unverified contracts falls back to decompiling the bytecode with heimdall. However, Cove does
-/// not currently persist verification results in a database. As a result, this route will always
-/// decompile the bytecode with heimdall.
+/// Route that returns data for a contract that was previously verified, and for unverified
+/// contracts falls back to decompiling the bytecode with heimdall.
 pub mod contract;
 
-/// Health check route that returns a 200 OK status code if the server is running.
+/// Health check routes that report whether the server is running and whether its external
+/// dependencies are available.
 pub mod health_check;
 
+/// Route that reports verification metrics, including the load on the verification scheduler, for
+/// Prometheus.
+pub mod metrics;
+
+/// Route for downloading the sources of a verification whose response truncated them.
+pub mod sources;
+
+/// Route that exports a stored verification as a Sourcify-compatible bundle.
+pub mod sourcify_export;
+
 /// Route for verifying a contract.
 pub mod verify;
 
+/// Route for verifying a contract from its solc metadata, without a repository or framework.
+pub mod verify_metadata;
+
+/// Route for fetching the state of a verification that runs asynchronously.
+pub mod verify_status;
+
+/// Route that returns the server version and the versions of the binaries used for verification.
+pub mod version;
+
 pub use contract::*;
 pub use health_check::*;
+pub use metrics::*;
+pub use sources::*;
+pub use sourcify_export::*;
 pub use verify::*;
+pub use verify_metadata::*;
+pub use verify_status::*;
+pub use version::*;
@@ -0,0 +1,44 @@
+use crate::startup::AppState;
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use std::{
+    path::{self, PathBuf},
+    sync::Arc,
+};
+
+/// Returns the path of the source archive for the given verification ID within `archive_dir`.
+pub fn archive_path(archive_dir: &path::Path, verification_id: &str) -> PathBuf {
+    archive_dir.join(format!("{verification_id}.zip"))
+}
+
+/// Returns the zip archive of every source of a verification whose sources were truncated in the
+/// verification response.
+pub async fn sources_zip(
+    State(state): State<Arc<AppState>>,
+    Path(verification_id): Path<String>,
+) -> Response {
+    // Verification IDs are 0x-prefixed hashes, so anything else can't have an archive and must not
+    // be joined onto the archive directory.
+    let is_valid_id = verification_id.len() == 66
+        && verification_id.starts_with("0x")
+        && verification_id[2..].chars().all(|c| c.is_ascii_hexdigit());
+    if !is_valid_id {
+        return (StatusCode::BAD_REQUEST, "Invalid verification ID".to_string()).into_response()
+    }
+
+    let archive_dir = state.settings.verification.source_archive_dir();
+    match tokio::fs::read(archive_path(&archive_dir, &verification_id)).await {
+        Ok(archive) => {
+            let disposition = "attachment; filename=\"sources.zip\"";
+            let headers = [
+                (header::CONTENT_TYPE, "application/zip"),
+                (header::CONTENT_DISPOSITION, disposition),
+            ];
+            (StatusCode::OK, headers, archive).into_response()
+        }
+        Err(_) => (StatusCode::NOT_FOUND, "No source archive found".to_string()).into_response(),
+    }
+}
@@ -0,0 +1,312 @@
+use crate::{
+    bytecode::MatchType,
+    provider::chain_from_id,
+    routes::{sources::archive_path, SourceFile, SuccessfulVerification},
+    startup::AppState,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use ethers::{
+    types::{Address, Chain, H256},
+    utils::{keccak256, to_checksum},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fs,
+    io::Read,
+    path::{self, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
+use zip::ZipArchive;
+
+/// The match status of a Sourcify bundle, named like the directories of Sourcify's repository.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SourcifyMatch {
+    /// The code and the metadata hash match, so `metadata.json` is exactly the compiled metadata.
+    FullMatch,
+    /// The code matches, but the metadata hash doesn't, or the metadata couldn't be reproduced.
+    PartialMatch,
+}
+
+/// A verification formatted as a Sourcify bundle, so it can be added to Sourcify's repository
+/// without compiling the contract again.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SourcifyBundle {
+    /// Whether the contract is a full or partial match on the chain.
+    pub status: SourcifyMatch,
+    /// The ID of the chain the contract was verified on.
+    pub chain_id: u64,
+    /// The address of the contract.
+    pub address: Address,
+    /// The content of `metadata.json`, i.e. the solc metadata of the contract.
+    pub metadata: serde_json::Value,
+    /// The content of each source listed in the metadata, keyed by its path in the metadata.
+    pub sources: BTreeMap<PathBuf, String>,
+}
+
+impl SourcifyBundle {
+    /// Formats the verification as a bundle for the given chain, which must be one it matched on.
+    /// The metadata is the compiler's metadata if it was recorded, and otherwise it's rebuilt from
+    /// the compiler info, ABI, and sources. A rebuilt metadata file doesn't hash to the on-chain
+    /// metadata hash, so the bundle is then only a partial match.
+    pub fn new(
+        verification: &SuccessfulVerification,
+        chain: Chain,
+        sources: &[SourceFile],
+    ) -> Result<Self, Box<dyn Error>> {
+        let verification_match = verification
+            .matches
+            .get(&chain)
+            .ok_or_else(|| format!("Contract was not verified on chain {chain:?}"))?;
+
+        let (mut metadata, metadata_is_exact) = match &verification.raw_metadata {
+            Some(raw_metadata) => (serde_json::from_str(raw_metadata)?, true),
+            None => (Self::rebuild_metadata(verification, sources)?, false),
+        };
+
+        // A reproduced metadata hash was computed from the metadata with `bytecodeHash: ipfs`.
+        if verification_match.metadata_hash_reproduced == Some(true) {
+            if let Some(bytecode_hash) = metadata.pointer_mut("/settings/metadata/bytecodeHash") {
+                *bytecode_hash = "ipfs".into();
+            }
+        }
+
+        let is_full_match = |match_type: MatchType| {
+            matches!(match_type, MatchType::Full | MatchType::FullWithConstructorArgs)
+        };
+        let status = if metadata_is_exact
+            && (is_full_match(verification_match.deployed_code_match_type)
+                || is_full_match(verification_match.creation_code_match_type))
+        {
+            SourcifyMatch::FullMatch
+        } else {
+            SourcifyMatch::PartialMatch
+        };
+
+        Ok(Self {
+            status,
+            chain_id: chain.into(),
+            address: verification.contract_address,
+            metadata,
+            sources: sources
+                .iter()
+                .map(|source| (source.path.clone(), source.content.clone()))
+                .collect(),
+        })
+    }
+
+    /// Rebuilds the solc metadata of the verified contract from the fields of the verification.
+    fn rebuild_metadata(
+        verification: &SuccessfulVerification,
+        sources: &[SourceFile],
+    ) -> Result<serde_json::Value, Box<dyn Error>> {
+        // Metadata identifies sources by the keccak256 hash of their content.
+        let metadata_sources: serde_json::Map<String, serde_json::Value> = sources
+            .iter()
+            .map(|source| {
+                let hash = format!("{:?}", H256::from(keccak256(source.content.as_bytes())));
+                let path = source.path.to_string_lossy().to_string();
+                (path, json!({ "keccak256": hash, "urls": [] }))
+            })
+            .collect();
+        let compiler_info = &verification.compiler_info;
+        Ok(json!({
+            "compiler": { "version": compiler_info.compiler },
+            "language": compiler_info.language,
+            "output": { "abi": verification.abi, "devdoc": {}, "userdoc": {} },
+            "settings": serde_json::to_value(&compiler_info.settings)?,
+            "sources": metadata_sources,
+            "version": 1,
+        }))
+    }
+
+    /// Returns the files of the bundle keyed by their path in Sourcify's repository, i.e.
+    /// `<status>/<chain ID>/<address>/metadata.json` and `<status>/<chain ID>/<address>/sources/`
+    /// followed by the path of each source.
+    pub fn repository_files(&self) -> Result<BTreeMap<PathBuf, String>, Box<dyn Error>> {
+        let status = serde_json::to_value(self.status)?;
+        let status = status.as_str().ok_or("Match status is not a string")?;
+        let dir = PathBuf::from(status)
+            .join(self.chain_id.to_string())
+            .join(to_checksum(&self.address, None));
+        let mut files = BTreeMap::new();
+        files.insert(dir.join("metadata.json"), serde_json::to_string(&self.metadata)?);
+        for (path, content) in &self.sources {
+            files.insert(dir.join("sources").join(path), content.clone());
+        }
+        Ok(files)
+    }
+}
+
+/// Returns the stored verification of a contract formatted as a Sourcify bundle. Verifications are
+/// only stored if a database directory is configured.
+pub async fn sourcify_export(
+    State(state): State<Arc<AppState>>,
+    Path((chain_id, address)): Path<(u64, String)>,
+) -> Response {
+    let chain = match chain_from_id(chain_id) {
+        Ok(chain) => chain,
+        Err(err) => return (StatusCode::BAD_REQUEST, err.to_string()).into_response(),
+    };
+    let Ok(address) = Address::from_str(&address) else {
+        let msg = format!("Invalid contract address {address}");
+        return (StatusCode::BAD_REQUEST, msg).into_response()
+    };
+    let verification = state.store.as_ref().and_then(|store| store.get(chain, address));
+    let Some(verification) = verification else {
+        return (StatusCode::NOT_FOUND, "No verification found".to_string()).into_response()
+    };
+
+    // Truncated verifications only store the most-derived source, so every source is read from the
+    // verification's source archive instead.
+    let archived_sources;
+    let sources = if verification.sources_truncated {
+        let archive_dir = state.settings.verification.source_archive_dir();
+        match read_sources_archive(&archive_path(&archive_dir, &verification.verification_id)) {
+            Ok(sources) => archived_sources = sources,
+            Err(err) => {
+                let msg = format!("Failed to read source archive: {err}");
+                return (StatusCode::INTERNAL_SERVER_ERROR, msg).into_response()
+            }
+        }
+        &archived_sources
+    } else {
+        &verification.sources
+    };
+
+    match SourcifyBundle::new(&verification, chain, sources) {
+        Ok(bundle) => (StatusCode::OK, Json(bundle)).into_response(),
+        Err(err) => {
+            let msg = format!("Failed to export verification: {err}");
+            (StatusCode::INTERNAL_SERVER_ERROR, msg).into_response()
+        }
+    }
+}
+
+/// Reads every source from the zip archive at `path`, as written for truncated verifications.
+fn read_sources_archive(path: &path::Path) -> Result<Vec<SourceFile>, Box<dyn Error>> {
+    let mut archive = ZipArchive::new(fs::File::open(path)?)?;
+    let mut sources = Vec::new();
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index)?;
+        let mut content = String::new();
+        file.read_to_string(&mut content)?;
+        let path = PathBuf::from(file.name());
+        sources.push(SourceFile { path, content, is_root: false });
+    }
+    Ok(sources)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{frameworks::framework::BuildVariant, routes::VerificationMatch};
+
+    /// The content of the only source of the test verification.
+    const COUNTER_SOURCE: &str = "contract Counter {}";
+
+    /// Returns a verification of a `Counter` contract that matched on mainnet with the given match
+    /// type, with the given raw metadata.
+    fn counter_verification(
+        match_type: MatchType,
+        raw_metadata: Option<&str>,
+    ) -> Result<SuccessfulVerification, Box<dyn Error>> {
+        let mut verification: SuccessfulVerification = serde_json::from_value(json!({
+            "verification_id": format!("0x{}", "ab".repeat(32)),
+            "repo_url": "https://github.com/ScopeLift/cove-test-repo",
+            "repo_commit": "188587ee3ee9ff0c8b3a4efa6e41cbcfdd8ad1a9",
+            "contract_address": "0x8d56e3e001132d84488dbacdbb01afb8c3171242",
+            "matches": {},
+            "chain_statuses": {},
+            "deployed_on": [],
+            "chains_matched": 1,
+            "artifacts_compiled": 1,
+            "explorer_urls": {},
+            "deployments": {},
+            "sources": [ { "path": "src/Counter.sol", "content": COUNTER_SOURCE } ],
+            "sources_truncated": false,
+            "source_integrity": "NotApplicable",
+            "ipfs_cids": {},
+            "bundle_cid": "",
+            "deployed_bytecode": { "object": "0x6080" },
+            "abi": [],
+            "abi_summary": { "functions": [], "events": [], "errors": [] },
+            "compiler_info": {
+                "compiler": "0.8.19+commit.7dd6d404",
+                "language": "Solidity",
+                "settings": { "optimizer": { "enabled": true, "runs": 200 } },
+            },
+            "raw_metadata": raw_metadata,
+            "toolchain": { "solc_version": "0.8.19+commit.7dd6d404", "allowed_forge_versions": [] },
+        }))?;
+        let verification_match = VerificationMatch {
+            artifact: PathBuf::from("out/Counter.sol/Counter.json"),
+            build_variant: BuildVariant {
+                profile: "default".to_string(),
+                optimizer_override: None,
+            },
+            creation_code_match_type: MatchType::None,
+            deployed_code_match_type: match_type,
+            metadata_hash_reproduced: None,
+            metadata_hash_type: None,
+            deployed_code_padding_normalized: false,
+            deployed_code_diff: None,
+        };
+        verification.matches.insert(Chain::Mainnet, verification_match);
+        Ok(verification)
+    }
+
+    #[test]
+    fn test_sourcify_bundle_round_trip() -> Result<(), Box<dyn Error>> {
+        let raw_metadata = r#"{"compiler":{"version":"0.8.19+commit.7dd6d404"},"language":"Solidity","settings":{"optimizer":{"enabled":true,"runs":200}},"version":1}"#;
+        let verification = counter_verification(MatchType::Full, Some(raw_metadata))?;
+        let bundle = SourcifyBundle::new(&verification, Chain::Mainnet, &verification.sources)?;
+        assert_eq!(bundle.status, SourcifyMatch::FullMatch);
+        assert_eq!(bundle.chain_id, 1);
+        assert_eq!(bundle.metadata, serde_json::from_str::<serde_json::Value>(raw_metadata)?);
+        assert_eq!(bundle.sources[path::Path::new("src/Counter.sol")], COUNTER_SOURCE);
+
+        // The bundle is unchanged by serializing and deserializing it.
+        let serialized = serde_json::to_string(&bundle)?;
+        assert!(serialized.contains(r#""status":"full_match""#));
+        assert_eq!(serde_json::from_str::<SourcifyBundle>(&serialized)?, bundle);
+
+        // Files are laid out like Sourcify's repository.
+        let dir = PathBuf::from("full_match/1/0x8d56e3e001132d84488DbacDbB01AfB8C3171242");
+        let files = bundle.repository_files()?;
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[&dir.join("metadata.json")], bundle.metadata.to_string());
+        assert_eq!(files[&dir.join("sources/src/Counter.sol")], COUNTER_SOURCE);
+
+        // The bundle is only exported for chains the contract matched on.
+        assert!(SourcifyBundle::new(&verification, Chain::Goerli, &verification.sources).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_sourcify_bundle_partial_match() -> Result<(), Box<dyn Error>> {
+        // Without the compiler's metadata the metadata is rebuilt, so it's only a partial match.
+        let verification = counter_verification(MatchType::Full, None)?;
+        let bundle = SourcifyBundle::new(&verification, Chain::Mainnet, &verification.sources)?;
+        assert_eq!(bundle.status, SourcifyMatch::PartialMatch);
+        assert_eq!(bundle.metadata["compiler"]["version"], "0.8.19+commit.7dd6d404");
+        assert_eq!(bundle.metadata["settings"]["optimizer"]["runs"], 200);
+        let hash = format!("{:?}", H256::from(keccak256(COUNTER_SOURCE)));
+        assert_eq!(bundle.metadata["sources"]["src/Counter.sol"]["keccak256"], hash);
+
+        // A partial match of the code is a partial match of the bundle.
+        let verification = counter_verification(MatchType::Partial, Some("{}"))?;
+        let bundle = SourcifyBundle::new(&verification, Chain::Mainnet, &verification.sources)?;
+        assert_eq!(bundle.status, SourcifyMatch::PartialMatch);
+        Ok(())
+    }
+}
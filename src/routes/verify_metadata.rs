@@ -0,0 +1,425 @@
+use crate::{
+    bytecode::{
+        creation_code_equality_check, deployed_code_equality_check,
+        structure_expected_creation_code, structure_expected_deployed_code,
+        structure_found_creation_code, structure_found_deployed_code, ImmutableReferences,
+        LinkReferences, MatchType,
+    },
+    provider::ChainStatus,
+    routes::verify::{
+        chain_status_summary, deployed_creation_tx_hashes, no_match_message, request_id,
+        VerifyError,
+    },
+    startup::AppState,
+};
+use axum::{
+    extract::{Json, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use ethers::types::{Address, Bytes, Chain, TxHash};
+use ethers_solc::{
+    artifacts::{BytecodeHash, Metadata, SettingsMetadata},
+    Solc,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// Data that a caller provides to verify a contract from its solc metadata, such as the
+/// `metadata.json` accepted by block explorers.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyMetadataData {
+    /// The solc metadata of the contract. The content of every source file must be included in
+    /// the `content` field of its entry in `sources`.
+    metadata: Value,
+    /// The address of the contract to verify.
+    contract_address: Address,
+    /// Optional, the transaction hashes that created the contract. Creation code is only verified
+    /// on chains with a transaction hash.
+    creation_tx_hashes: Option<HashMap<Chain, TxHash>>,
+}
+
+/// The types of match found for a contract verified from its metadata.
+#[derive(Serialize, Deserialize)]
+pub struct MetadataMatch {
+    /// The type of match for the creation code.
+    pub creation_code_match_type: MatchType,
+    /// The type of match for the deployed code.
+    pub deployed_code_match_type: MatchType,
+}
+
+/// Data returned for a successful verification from metadata.
+#[derive(Serialize, Deserialize)]
+pub struct MetadataVerification {
+    /// The address of the contract that was verified.
+    pub contract_address: Address,
+    /// The path of the source file containing the verified contract.
+    pub source_path: String,
+    /// The name of the verified contract.
+    pub contract_name: String,
+    /// The full version of the solc compiler the contract was compiled with.
+    pub compiler_version: String,
+    /// A map from chain to the match types found on that chain.
+    pub matches: HashMap<Chain, MetadataMatch>,
+    /// A map from chain to the status of fetching the deployed code on that chain.
+    pub chain_statuses: HashMap<Chain, ChainStatus>,
+    /// The ABI of the verified contract.
+    pub abi: Value,
+}
+
+/// The outputs of compiling the verified contract.
+struct CompiledContract {
+    /// The creation code of the contract.
+    creation_code: Bytes,
+    /// The deployed code of the contract.
+    deployed_code: Bytes,
+    /// Immutable references and their offsets within the deployed code.
+    immutable_references: ImmutableReferences,
+    /// The ABI of the contract.
+    abi: Value,
+}
+
+/// Entrypoint for verifying a contract from its solc metadata. The solc Standard JSON input is
+/// reconstructed from the metadata's sources and settings, compiled with the exact compiler version
+/// named in the metadata, and the result is compared against the on-chain code. Unlike `verify`,
+/// this does not clone a repository or require a supported framework.
+#[tracing::instrument(
+    name = "Verifying contract from metadata",
+    skip(state, headers, json),
+    fields(
+        request_id = %request_id(&headers),
+        contract_address = ?json.contract_address,
+        creation_tx_hashes = ?json.creation_tx_hashes,
+    )
+)]
+pub async fn verify_metadata(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(json): Json<VerifyMetadataData>,
+) -> Result<Response, VerifyError> {
+    let metadata: Metadata = serde_json::from_value(json.metadata.clone())
+        .map_err(|e| VerifyError::BadRequest(format!("Invalid metadata: {}", e)))?;
+    let (source_path, contract_name) = compilation_target(&metadata)?;
+    let input = standard_json_input(&json.metadata)?;
+
+    println!("\nVERIFICATION INPUTS:");
+    println!("  Contract:         {}:{}", source_path, contract_name);
+    println!("  Compiler:         {}", metadata.compiler.version);
+    println!("  Contract Address: {:#?}", json.contract_address);
+
+    println!("\nCOMPILING CONTRACT");
+    let compiler_version = metadata.compiler.version.clone();
+    let solc_dir = state.settings.verification.solc_dir();
+    let output = compile(solc_dir.as_deref(), &compiler_version, input).await?;
+    let contract = compiled_contract(&output, &source_path, &contract_name)?;
+
+    println!("\nCOMPARING BYTECODE");
+    let provider = state.provider()?;
+    let deployed_code = provider.get_deployed_code(json.contract_address).await?;
+    if deployed_code.is_all_none() {
+        let msg =
+            format!("No code found at the given address. {}", chain_status_summary(&deployed_code));
+        return Err(VerifyError::BadRequest(msg.trim_end().to_string()))
+    }
+    let creation_data = match deployed_creation_tx_hashes(&json.creation_tx_hashes, &deployed_code)
+    {
+        Some(creation_tx_hashes) => {
+            Some(provider.get_creation_code(json.contract_address, Some(creation_tx_hashes)).await?)
+        }
+        None => None,
+    };
+
+    // Metadata always records the metadata settings, but fall back to solc's defaults if absent.
+    let settings = metadata
+        .settings
+        .metadata
+        .clone()
+        .unwrap_or_else(|| SettingsMetadata::new(BytecodeHash::Ipfs, true));
+    let found_creation = structure_found_creation_code(contract.creation_code, &settings);
+    let found_deployed = structure_found_deployed_code(
+        contract.deployed_code,
+        contract.immutable_references,
+        LinkReferences::new(),
+        &settings,
+    );
+
+    let mut matches = HashMap::new();
+    for (chain, code) in deployed_code.iter_entries() {
+        let deployed_code_match_type = structure_expected_deployed_code(&found_deployed, code)
+            .map(|expected| {
+                let max_padding = provider.max_deployed_code_padding(chain);
+                deployed_code_equality_check(&found_deployed, &expected, max_padding)
+            })
+            .unwrap_or_default();
+        let creation_code_match_type = creation_data
+            .as_ref()
+            .and_then(|data| data.get(chain))
+            .and_then(|data| {
+                structure_expected_creation_code(&found_creation, &data.creation_code).ok()
+            })
+            .map(|expected| creation_code_equality_check(&found_creation, &expected))
+            .unwrap_or_default();
+
+        if creation_code_match_type != MatchType::None
+            || deployed_code_match_type != MatchType::None
+        {
+            println!("  ✅ Found matching code on chain {:?}", chain);
+            let contract_match =
+                MetadataMatch { creation_code_match_type, deployed_code_match_type };
+            matches.insert(*chain, contract_match);
+        }
+    }
+
+    if matches.is_empty() {
+        return Ok((StatusCode::BAD_REQUEST, no_match_message(&deployed_code)).into_response())
+    }
+
+    let response = MetadataVerification {
+        contract_address: json.contract_address,
+        source_path,
+        contract_name,
+        compiler_version,
+        matches,
+        chain_statuses: deployed_code.statuses(),
+        abi: contract.abi,
+    };
+
+    println!("\nFINISHED");
+    println!("  200 response returned.");
+    Ok((StatusCode::OK, Json(response)).into_response())
+}
+
+/// Returns the source path and name of the contract the metadata was generated for.
+fn compilation_target(metadata: &Metadata) -> Result<(String, String), VerifyError> {
+    let mut targets = metadata.settings.compilation_target.iter();
+    match (targets.next(), targets.next()) {
+        (Some((path, name)), None) => Ok((path.clone(), name.clone())),
+        _ => Err(VerifyError::BadRequest(
+            "Metadata must have exactly one compilation target.".to_string(),
+        )),
+    }
+}
+
+/// Reconstructs the solc Standard JSON input that produced the given metadata. Metadata only
+/// embeds source content when compiled with `useLiteralContent`, so an error is returned if any
+/// source is missing its content.
+fn standard_json_input(metadata: &Value) -> Result<Value, VerifyError> {
+    let language = metadata.get("language").cloned().unwrap_or_else(|| json!("Solidity"));
+    let metadata_sources = metadata
+        .get("sources")
+        .and_then(Value::as_object)
+        .ok_or_else(|| VerifyError::BadRequest("Metadata is missing sources.".to_string()))?;
+
+    let mut sources = Map::new();
+    let mut missing_content = Vec::new();
+    for (path, source) in metadata_sources {
+        match source.get("content") {
+            Some(content) => {
+                sources.insert(path.clone(), json!({ "content": content }));
+            }
+            None => missing_content.push(path.as_str()),
+        }
+    }
+    if !missing_content.is_empty() {
+        return Err(VerifyError::BadRequest(format!(
+            "Metadata must include the content of every source. Missing content for: {}",
+            missing_content.join(", ")
+        )))
+    }
+
+    // The compilation target is metadata-only, and all other settings are passed through as is.
+    let mut settings =
+        metadata.get("settings").and_then(Value::as_object).cloned().unwrap_or_default();
+    settings.remove("compilationTarget");
+
+    // Metadata keys libraries by `path:name`, but Standard JSON input groups them by path.
+    if let Some(Value::Object(libraries)) = settings.remove("libraries") {
+        let mut grouped = Map::new();
+        for (key, address) in libraries {
+            let (path, name) = key.rsplit_once(':').unwrap_or(("", key.as_str()));
+            let entry = grouped.entry(path.to_string()).or_insert_with(|| json!({}));
+            if let Some(entry) = entry.as_object_mut() {
+                entry.insert(name.to_string(), address);
+            }
+        }
+        settings.insert("libraries".to_string(), Value::Object(grouped));
+    }
+
+    settings.insert(
+        "outputSelection".to_string(),
+        json!({ "*": { "*": [
+            "abi",
+            "evm.bytecode.object",
+            "evm.deployedBytecode.object",
+            "evm.deployedBytecode.immutableReferences",
+        ] } }),
+    );
+
+    Ok(json!({ "language": language, "sources": sources, "settings": settings }))
+}
+
+/// Compiles the Standard JSON input with the given solc version from `solc_dir` and returns the
+/// Standard JSON output. An error is returned if the compiler version is not installed or
+/// compilation fails.
+async fn compile(
+    solc_dir: Option<&Path>,
+    compiler_version: &str,
+    input: Value,
+) -> Result<Value, VerifyError> {
+    let solc = Solc::new(solc_path(solc_dir, compiler_version)?);
+    let output = tokio::task::spawn_blocking(move || solc.compile_as::<Value, Value>(&input))
+        .await
+        .map_err(|e| VerifyError::InternalServerError(e.to_string()))?
+        .map_err(|e| VerifyError::InternalServerError(format!("Failed to run solc: {}", e)))?;
+
+    let errors: Vec<&str> = output
+        .get("errors")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter(|error| error.get("severity").and_then(Value::as_str) == Some("error"))
+        .filter_map(|error| error.get("formattedMessage").and_then(Value::as_str))
+        .collect();
+    if !errors.is_empty() {
+        return Err(VerifyError::BadRequest(format!("Compilation failed:\n{}", errors.join("\n"))))
+    }
+    Ok(output)
+}
+
+/// Returns the path of the solc binary for the given version in `solc_dir`, the directory svm
+/// installs compilers to, so any version forge has used on this server is available.
+fn solc_path(solc_dir: Option<&Path>, compiler_version: &str) -> Result<PathBuf, VerifyError> {
+    let Some(solc_dir) = solc_dir else {
+        return Err(VerifyError::InternalServerError(
+            "The solc directory is not configured and svm's directory could not be found."
+                .to_string(),
+        ))
+    };
+    // Installed compilers are identified by their semver version without the commit.
+    let version = compiler_version.split('+').next().unwrap_or(compiler_version);
+    let path = solc_dir.join(version).join(format!("solc-{}", version));
+    if !path.is_file() {
+        return Err(VerifyError::NotImplemented(format!(
+            "solc {} is not installed on this server.",
+            version
+        )))
+    }
+    Ok(path)
+}
+
+/// Extracts the outputs for the given contract from the Standard JSON output.
+fn compiled_contract(
+    output: &Value,
+    source_path: &str,
+    contract_name: &str,
+) -> Result<CompiledContract, VerifyError> {
+    let contract = output
+        .get("contracts")
+        .and_then(|contracts| contracts.get(source_path))
+        .and_then(|contracts| contracts.get(contract_name))
+        .ok_or_else(|| {
+            VerifyError::InternalServerError(format!(
+                "Compiler output is missing {}:{}",
+                source_path, contract_name
+            ))
+        })?;
+
+    let bytecode = |pointer: &str| -> Result<Bytes, VerifyError> {
+        let object = contract.pointer(pointer).and_then(Value::as_str).unwrap_or_default();
+        object.parse::<Bytes>().map_err(|_| {
+            VerifyError::NotImplemented("Linked libraries are not yet supported.".to_string())
+        })
+    };
+    let immutable_references = contract
+        .pointer("/evm/deployedBytecode/immutableReferences")
+        .cloned()
+        .map(serde_json::from_value)
+        .transpose()?
+        .unwrap_or_default();
+
+    Ok(CompiledContract {
+        creation_code: bytecode("/evm/bytecode/object")?,
+        deployed_code: bytecode("/evm/deployedBytecode/object")?,
+        immutable_references,
+        abi: contract.get("abi").cloned().unwrap_or_else(|| json!([])),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_standard_json_input() {
+        let metadata = json!({
+            "language": "Solidity",
+            "sources": {
+                "src/Counter.sol": { "keccak256": "0x01", "content": "contract Counter {}" },
+                "src/Math.sol": { "keccak256": "0x02", "content": "library Math {}" },
+            },
+            "settings": {
+                "compilationTarget": { "src/Counter.sol": "Counter" },
+                "evmVersion": "paris",
+                "libraries": { "src/Math.sol:Math": "0x0000000000000000000000000000000000000001" },
+                "optimizer": { "enabled": true, "runs": 200 },
+                "remappings": [],
+            },
+        });
+
+        let input = standard_json_input(&metadata).unwrap_or_else(|_| panic!("Invalid input"));
+        assert_eq!(input["language"], "Solidity");
+        assert_eq!(
+            input["sources"]["src/Counter.sol"],
+            json!({ "content": "contract Counter {}" })
+        );
+        assert_eq!(input["settings"]["evmVersion"], "paris");
+        assert_eq!(input["settings"]["optimizer"], json!({ "enabled": true, "runs": 200 }));
+        assert_eq!(
+            input["settings"]["libraries"],
+            json!({ "src/Math.sol": { "Math": "0x0000000000000000000000000000000000000001" } })
+        );
+        assert!(input["settings"].get("compilationTarget").is_none());
+        assert!(input["settings"]["outputSelection"]["*"]["*"].is_array());
+    }
+
+    #[test]
+    fn test_standard_json_input_requires_content() {
+        let metadata = json!({
+            "language": "Solidity",
+            "sources": {
+                "src/Counter.sol": { "keccak256": "0x01", "urls": ["dweb:/ipfs/Qm"] },
+            },
+            "settings": {},
+        });
+
+        match standard_json_input(&metadata) {
+            Err(VerifyError::BadRequest(msg)) => assert!(msg.contains("src/Counter.sol"), "{msg}"),
+            _ => panic!("Expected a bad request error"),
+        }
+    }
+
+    #[test]
+    fn test_solc_path() {
+        let solc_dir = tempfile::TempDir::new().unwrap();
+        let version_dir = solc_dir.path().join("0.8.19");
+        std::fs::create_dir(&version_dir).unwrap();
+        std::fs::write(version_dir.join("solc-0.8.19"), "").unwrap();
+
+        // Compilers are found by their version without the commit.
+        let Ok(path) = solc_path(Some(solc_dir.path()), "0.8.19+commit.7dd6d404") else {
+            panic!("Expected solc 0.8.19 to be found")
+        };
+        assert_eq!(path, version_dir.join("solc-0.8.19"));
+        assert!(matches!(
+            solc_path(Some(solc_dir.path()), "0.8.20+commit.a1b79de6"),
+            Err(VerifyError::NotImplemented(_))
+        ));
+        assert!(matches!(solc_path(None, "0.8.19"), Err(VerifyError::InternalServerError(_))));
+    }
+}
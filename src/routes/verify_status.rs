@@ -0,0 +1,149 @@
+use crate::startup::AppState;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use uuid::Uuid;
+
+/// How long the state of a finished job is kept for clients to fetch it.
+const FINISHED_JOB_RETENTION: Duration = Duration::from_secs(60 * 60);
+
+/// How often finished jobs that have been kept for longer than `FINISHED_JOB_RETENTION` are
+/// removed.
+const JOB_PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The state of every asynchronous verification job, keyed by job ID.
+pub type Jobs = Arc<Mutex<HashMap<Uuid, JobState>>>;
+
+/// The status of an asynchronous verification job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum JobStatus {
+    /// The job is waiting for a worker.
+    Queued,
+    /// The job is running.
+    Running,
+    /// The verification succeeded, and the job's result contains the response.
+    Succeeded,
+    /// The verification failed, and the job's error contains the reason.
+    Failed,
+}
+
+/// The state of an asynchronous verification job, returned by `verify_status`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobState {
+    /// The status of the job.
+    pub status: JobStatus,
+    /// The response `verify` would have returned, e.g. a `SuccessfulVerification`. Only present
+    /// once the job has succeeded.
+    pub result: Option<Value>,
+    /// The error message `verify` would have returned. Only present once the job has failed.
+    pub error: Option<String>,
+    /// When the job finished, used to remove finished jobs once they have been kept long enough.
+    #[serde(skip)]
+    finished_at: Option<Instant>,
+}
+
+impl JobState {
+    /// Returns the state of a job that is waiting for a worker.
+    pub fn queued() -> Self {
+        Self { status: JobStatus::Queued, result: None, error: None, finished_at: None }
+    }
+
+    /// Returns the state of a job that is running.
+    pub fn running() -> Self {
+        Self { status: JobStatus::Running, ..Self::queued() }
+    }
+
+    /// Returns the state of a finished job from the response `verify` would have returned.
+    pub async fn finished(response: Response) -> Self {
+        let succeeded = response.status().is_success();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap_or_default();
+        let finished_at = Some(Instant::now());
+        if succeeded {
+            let result = serde_json::from_slice(&body).ok();
+            Self { status: JobStatus::Succeeded, result, error: None, finished_at }
+        } else {
+            let error = Some(String::from_utf8_lossy(&body).into_owned());
+            Self { status: JobStatus::Failed, result: None, error, finished_at }
+        }
+    }
+}
+
+/// Records the state of the job with the given ID.
+pub fn update_job(jobs: &Jobs, job_id: Uuid, state: JobState) {
+    jobs.lock().expect("Jobs lock is never poisoned").insert(job_id, state);
+}
+
+/// Removes finished jobs that have been kept for longer than `FINISHED_JOB_RETENTION`.
+fn prune_jobs(jobs: &Jobs) {
+    let mut jobs = jobs.lock().expect("Jobs lock is never poisoned");
+    jobs.retain(|_, job| job.finished_at.map_or(true, |at| at.elapsed() < FINISHED_JOB_RETENTION));
+}
+
+/// Spawns a task that prunes finished jobs every `JOB_PRUNE_INTERVAL`, so their states are
+/// removed even if no new jobs are started. The task stops once the jobs are dropped.
+pub fn spawn_job_pruning(jobs: &Jobs) {
+    let jobs = Arc::downgrade(jobs);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(JOB_PRUNE_INTERVAL);
+        loop {
+            interval.tick().await;
+            let Some(jobs) = jobs.upgrade() else { return };
+            prune_jobs(&jobs);
+        }
+    });
+}
+
+/// The response to a verification request that was accepted to run asynchronously.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AcceptedVerification {
+    /// The ID of the job, used to fetch its state from `/verify/status/{job_id}`.
+    pub job_id: Uuid,
+}
+
+/// Returns the state of the asynchronous verification job with the given ID, or 404 Not Found if
+/// there is no such job, e.g. because it finished more than an hour ago. If an HMAC secret is
+/// configured, the request must be signed like verification requests are.
+pub async fn verify_status(
+    State(state): State<Arc<AppState>>,
+    Path(job_id): Path<Uuid>,
+) -> Response {
+    let job = state.jobs.lock().expect("Jobs lock is never poisoned").get(&job_id).cloned();
+    match job {
+        Some(job) => Json(job).into_response(),
+        None => (StatusCode::NOT_FOUND, "No verification job found".to_string()).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prune_jobs() {
+        let jobs = Jobs::default();
+        let expired = Instant::now().checked_sub(FINISHED_JOB_RETENTION + Duration::from_secs(1));
+        let finished =
+            |finished_at| JobState { status: JobStatus::Failed, finished_at, ..JobState::queued() };
+        let (running, recent, old) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        update_job(&jobs, running, JobState::running());
+        update_job(&jobs, recent, finished(Some(Instant::now())));
+        update_job(&jobs, old, finished(expired));
+
+        // Only finished jobs that have been kept for longer than the retention period are removed.
+        prune_jobs(&jobs);
+        let jobs = jobs.lock().unwrap();
+        assert!(jobs.contains_key(&running) && jobs.contains_key(&recent));
+        assert_eq!(jobs.contains_key(&old), expired.is_none());
+    }
+}
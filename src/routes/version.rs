@@ -0,0 +1,18 @@
+use crate::{startup::AppState, tools::ToolVersions};
+use axum::{extract::State, Json};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Version information for the server.
+#[derive(Serialize, Deserialize)]
+pub struct VersionInfo {
+    /// The version of the Cove server.
+    pub version: String,
+    /// The versions of the external binaries used for verification, as detected at startup.
+    pub tools: ToolVersions,
+}
+
+/// Returns the server version and the versions of the binaries used for verification.
+pub async fn version(State(state): State<Arc<AppState>>) -> Json<VersionInfo> {
+    Json(VersionInfo { version: env!("CARGO_PKG_VERSION").to_string(), tools: state.tools.clone() })
+}
@@ -0,0 +1,244 @@
+use async_trait::async_trait;
+use ethers::providers::{
+    Http, HttpClientError, JsonRpcClient, JsonRpcError, MockError, ProviderError, RpcError,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::{self, Debug, Display},
+};
+
+/// The JSON-RPC transport used by all providers. Requests are sent over HTTP when running the
+/// server, and can instead be served from memory so verification can be tested without a network.
+#[derive(Debug)]
+pub enum RpcClient {
+    /// Sends requests to an RPC URL.
+    Http(Http),
+    /// Serves requests from fixture responses.
+    InMemory(InMemoryClient),
+}
+
+/// Errors returned by an `RpcClient`.
+#[derive(Debug)]
+pub enum RpcClientError {
+    /// An error from the HTTP transport.
+    Http(HttpClientError),
+    /// An error from the in-memory transport.
+    InMemory(MockError),
+}
+
+impl Display for RpcClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RpcClientError::Http(err) => Display::fmt(err, f),
+            RpcClientError::InMemory(err) => Display::fmt(err, f),
+        }
+    }
+}
+
+impl Error for RpcClientError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RpcClientError::Http(err) => Some(err),
+            RpcClientError::InMemory(err) => Some(err),
+        }
+    }
+}
+
+impl RpcError for RpcClientError {
+    fn as_error_response(&self) -> Option<&JsonRpcError> {
+        match self {
+            RpcClientError::Http(err) => err.as_error_response(),
+            RpcClientError::InMemory(err) => err.as_error_response(),
+        }
+    }
+
+    fn as_serde_error(&self) -> Option<&serde_json::Error> {
+        match self {
+            RpcClientError::Http(err) => err.as_serde_error(),
+            RpcClientError::InMemory(err) => err.as_serde_error(),
+        }
+    }
+}
+
+impl From<RpcClientError> for ProviderError {
+    fn from(err: RpcClientError) -> Self {
+        ProviderError::JsonRpcClientError(Box::new(err))
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for RpcClient {
+    type Error = RpcClientError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        match self {
+            RpcClient::Http(client) => {
+                client.request(method, params).await.map_err(RpcClientError::Http)
+            }
+            RpcClient::InMemory(client) => {
+                client.request(method, params).map_err(RpcClientError::InMemory)
+            }
+        }
+    }
+}
+
+impl RpcClient {
+    /// Sends the given `(method, params)` requests as a single JSON-RPC batch and returns their
+    /// results in the same order as the requests. This reduces the number of HTTP requests made
+    /// to rate-limited RPCs, but only works with nodes that support batch requests.
+    pub async fn batch_request(
+        &self,
+        requests: &[(&str, Value)],
+    ) -> Result<Vec<Value>, RpcClientError> {
+        match self {
+            RpcClient::Http(client) => batch_http_request(client.url().as_str(), requests)
+                .await
+                .map_err(RpcClientError::Http),
+            RpcClient::InMemory(client) => requests
+                .iter()
+                .map(|(method, params)| client.request(method, params))
+                .collect::<Result<_, _>>()
+                .map_err(RpcClientError::InMemory),
+        }
+    }
+}
+
+/// A single response from a JSON-RPC batch request.
+#[derive(Deserialize)]
+struct BatchResponse {
+    /// The ID of the request this is a response to.
+    id: usize,
+    /// The result of the request, which is `null` if the request failed.
+    #[serde(default)]
+    result: Value,
+    /// The error returned for the request, if it failed.
+    error: Option<JsonRpcError>,
+}
+
+/// Sends the given requests to `url` as a single JSON-RPC batch and returns their results in the
+/// same order as the requests.
+async fn batch_http_request(
+    url: &str,
+    requests: &[(&str, Value)],
+) -> Result<Vec<Value>, HttpClientError> {
+    let payload: Vec<Value> = requests
+        .iter()
+        .enumerate()
+        .map(|(id, (method, params))| {
+            json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+        })
+        .collect();
+    let body = reqwest::Client::new().post(url).json(&payload).send().await?.bytes().await?;
+    let serde_error =
+        |err| HttpClientError::SerdeJson { err, text: String::from_utf8_lossy(&body).to_string() };
+    let responses: Vec<BatchResponse> = serde_json::from_slice(&body).map_err(serde_error)?;
+
+    // Nodes may return batch responses in any order, so match them to requests by ID.
+    let mut results = vec![None; requests.len()];
+    for response in responses {
+        if let Some(error) = response.error {
+            return Err(HttpClientError::JsonRpcError(error))
+        }
+        if let Some(result) = results.get_mut(response.id) {
+            *result = Some(response.result);
+        }
+    }
+    results.into_iter().collect::<Option<Vec<_>>>().ok_or_else(|| {
+        serde_error(serde::de::Error::custom("batch response is missing a response"))
+    })
+}
+
+/// A JSON-RPC transport that returns fixture responses instead of querying a node. Responses are
+/// keyed by the method and its JSON-serialized params, and requests without a matching fixture
+/// return a JSON-RPC error.
+#[derive(Debug, Default, Clone)]
+pub struct InMemoryClient {
+    /// Mapping from `(method, serialized params)` to the result returned for that request.
+    responses: HashMap<(String, String), Value>,
+}
+
+impl InMemoryClient {
+    /// Create a new `InMemoryClient` with no fixture responses.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the client with `result` registered as the response to `method` called with
+    /// `params`. The params must serialize exactly as ethers serializes them, e.g. `(address,
+    /// "latest")` for `eth_getCode`.
+    pub fn with_response(
+        mut self,
+        method: &str,
+        params: impl Serialize,
+        result: impl Serialize,
+    ) -> Result<Self, serde_json::Error> {
+        let key = (method.to_string(), serde_json::to_string(&params)?);
+        self.responses.insert(key, serde_json::to_value(result)?);
+        Ok(self)
+    }
+
+    /// Returns the fixture response for the given request.
+    fn request<T: Serialize, R: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: T,
+    ) -> Result<R, MockError> {
+        let params = serde_json::to_string(&params)?;
+        let key = (method.to_string(), params);
+        let value = self.responses.get(&key).ok_or_else(|| {
+            MockError::JsonRpcError(JsonRpcError {
+                code: -32601,
+                message: format!("No fixture response for {} with params {}", key.0, key.1),
+                data: None,
+            })
+        })?;
+        Ok(serde_json::from_value(value.clone())?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{
+        providers::{Middleware, Provider},
+        types::{Address, Bytes},
+    };
+    use std::str::FromStr;
+
+    #[tokio::test]
+    async fn test_in_memory_client() -> Result<(), Box<dyn std::error::Error>> {
+        let address = Address::from_str("0x8d56e3e001132d84488DbacDbB01AfB8C3171242")?;
+        let code = Bytes::from_str("0x6080604052")?;
+        let client =
+            InMemoryClient::new().with_response("eth_getCode", (address, "latest"), &code)?;
+        let provider = Provider::new(RpcClient::InMemory(client));
+
+        assert_eq!(provider.get_code(address, None).await?, code);
+        assert!(provider.get_code(Address::zero(), None).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_batch_request() -> Result<(), Box<dyn std::error::Error>> {
+        let address = Address::from_str("0x8d56e3e001132d84488DbacDbB01AfB8C3171242")?;
+        let client = InMemoryClient::new()
+            .with_response("eth_getCode", (address, "latest"), "0x6080")?
+            .with_response("eth_chainId", (), "0x1")?;
+        let client = RpcClient::InMemory(client);
+
+        let requests = [("eth_chainId", json!(null)), ("eth_getCode", json!([address, "latest"]))];
+        let results = client.batch_request(&requests).await?;
+        assert_eq!(results, vec![json!("0x1"), json!("0x6080")]);
+
+        let requests = [("eth_getCode", json!([Address::zero(), "latest"]))];
+        assert!(client.batch_request(&requests).await.is_err());
+        Ok(())
+    }
+}
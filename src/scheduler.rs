@@ -0,0 +1,206 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// How often `Scheduler::wait_until_idle` checks whether jobs are still running.
+const IDLE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Runs verification jobs on a bounded pool of workers. Jobs submitted while every worker is busy
+/// wait in a bounded queue, and jobs submitted while the queue is full are rejected, so load beyond
+/// the server's capacity is turned away instead of spawning unbounded builds.
+#[derive(Debug)]
+pub struct Scheduler {
+    /// Holds one permit per worker. A job holds a permit while it runs.
+    workers: Arc<Semaphore>,
+    /// The number of workers, i.e. the maximum number of jobs that run at once.
+    worker_count: usize,
+    /// The maximum number of jobs waiting for a worker.
+    queue_capacity: usize,
+    /// The number of jobs currently waiting for a worker.
+    queued: AtomicUsize,
+    /// The number of jobs rejected because the queue was full, since the server started.
+    rejected: AtomicU64,
+}
+
+/// A snapshot of the scheduler's load.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SchedulerMetrics {
+    /// The number of workers, i.e. the maximum number of jobs that run at once.
+    pub workers: usize,
+    /// The maximum number of jobs waiting for a worker.
+    pub queue_capacity: usize,
+    /// The number of jobs currently waiting for a worker.
+    pub queue_depth: usize,
+    /// The number of jobs currently running.
+    pub active_jobs: usize,
+    /// The number of jobs rejected because the queue was full, since the server started.
+    pub rejected_jobs: u64,
+}
+
+/// Returned when a job is submitted while every worker is busy and the queue is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueFull;
+
+impl std::fmt::Display for QueueFull {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "The verification queue is full, please try again later.")
+    }
+}
+
+impl std::error::Error for QueueFull {}
+
+impl Scheduler {
+    /// Create a new `Scheduler` that runs at most `workers` jobs at once, with at most
+    /// `queue_capacity` more waiting for a worker.
+    pub fn new(workers: usize, queue_capacity: usize) -> Self {
+        Self {
+            workers: Arc::new(Semaphore::new(workers)),
+            worker_count: workers,
+            queue_capacity,
+            queued: AtomicUsize::new(0),
+            rejected: AtomicU64::new(0),
+        }
+    }
+
+    /// Reserves a worker for a job if one is free, or a place in the queue otherwise. Returns an
+    /// error if the queue is full. The reservation is released when the returned job is run or
+    /// dropped.
+    pub fn enqueue(self: &Arc<Self>) -> Result<Job, QueueFull> {
+        if let Ok(permit) = self.workers.clone().try_acquire_owned() {
+            return Ok(Job { scheduler: self.clone(), permit: Some(permit) })
+        }
+
+        let capacity = self.queue_capacity;
+        let reserved = self.queued.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |queued| {
+            (queued < capacity).then_some(queued + 1)
+        });
+        match reserved {
+            Ok(_) => Ok(Job { scheduler: self.clone(), permit: None }),
+            Err(_) => {
+                self.rejected.fetch_add(1, Ordering::SeqCst);
+                Err(QueueFull)
+            }
+        }
+    }
+
+    /// Returns a snapshot of the scheduler's load.
+    pub fn metrics(&self) -> SchedulerMetrics {
+        SchedulerMetrics {
+            workers: self.worker_count,
+            queue_capacity: self.queue_capacity,
+            queue_depth: self.queued.load(Ordering::SeqCst),
+            active_jobs: self.worker_count - self.workers.available_permits(),
+            rejected_jobs: self.rejected.load(Ordering::SeqCst),
+        }
+    }
+
+    /// Waits until no jobs are running or waiting in the queue. This is used on shutdown to let
+    /// verifications that run in the background finish.
+    pub async fn wait_until_idle(&self) {
+        loop {
+            let metrics = self.metrics();
+            if metrics.active_jobs == 0 && metrics.queue_depth == 0 {
+                return
+            }
+            tokio::time::sleep(IDLE_POLL_INTERVAL).await;
+        }
+    }
+}
+
+/// A job that has been accepted by the scheduler, holding either a worker or a place in the queue.
+#[derive(Debug)]
+pub struct Job {
+    /// The scheduler that accepted the job.
+    scheduler: Arc<Scheduler>,
+    /// The job's worker, or `None` while the job is waiting in the queue.
+    permit: Option<OwnedSemaphorePermit>,
+}
+
+impl Job {
+    /// Waits for a worker if the job is queued, then runs `task` on it. The worker, or the place
+    /// in the queue, is released when the task completes or is cancelled.
+    pub async fn run<F: Future>(mut self, task: F) -> F::Output {
+        if self.permit.is_none() {
+            let permit = self.scheduler.workers.clone().acquire_owned().await;
+            self.permit = Some(permit.expect("Scheduler semaphore is never closed"));
+            self.scheduler.queued.fetch_sub(1, Ordering::SeqCst);
+        }
+        task.await
+    }
+}
+
+impl Drop for Job {
+    fn drop(&mut self) {
+        // A job dropped while queued gives up its place in the queue.
+        if self.permit.is_none() {
+            self.scheduler.queued.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_queue_backpressure() {
+        let scheduler = Arc::new(Scheduler::new(1, 1));
+
+        // The first job takes the only worker, and the second waits in the queue.
+        let running = scheduler.enqueue().unwrap();
+        let queued = scheduler.enqueue().unwrap();
+        assert_eq!(scheduler.metrics().active_jobs, 1);
+        assert_eq!(scheduler.metrics().queue_depth, 1);
+
+        // Once the queue is full, jobs are rejected.
+        assert_eq!(scheduler.enqueue().unwrap_err(), QueueFull);
+        assert_eq!(scheduler.metrics().rejected_jobs, 1);
+
+        // A job dropped while queued gives up its place.
+        drop(queued);
+        assert_eq!(scheduler.metrics().queue_depth, 0);
+        let queued = scheduler.enqueue().unwrap();
+
+        // When the running job completes, the queued job runs on its worker.
+        assert_eq!(running.run(async { 1 }).await, 1);
+        let metrics = scheduler.metrics();
+        assert_eq!((metrics.active_jobs, metrics.queue_depth), (0, 1));
+        assert_eq!(queued.run(async { 2 }).await, 2);
+        assert_eq!(
+            scheduler.metrics(),
+            SchedulerMetrics {
+                workers: 1,
+                queue_capacity: 1,
+                queue_depth: 0,
+                active_jobs: 0,
+                rejected_jobs: 1,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_wait_until_idle() {
+        let scheduler = Arc::new(Scheduler::new(1, 1));
+        scheduler.wait_until_idle().await;
+
+        // Waiting returns once the running and queued jobs complete.
+        let running = scheduler.enqueue().unwrap();
+        let queued = scheduler.enqueue().unwrap();
+        let jobs = tokio::spawn(async move {
+            running.run(tokio::time::sleep(Duration::from_millis(50))).await;
+            queued.run(async {}).await;
+        });
+        tokio::time::timeout(Duration::from_secs(5), scheduler.wait_until_idle())
+            .await
+            .expect("Scheduler never became idle");
+        jobs.await.unwrap();
+    }
+}
@@ -1,12 +1,26 @@
-use crate::routes;
+use crate::{
+    auth,
+    circuit_breaker::CircuitBreaker,
+    config::{ChainSettings, Settings},
+    logger::{logger_from_settings, VerificationLogger},
+    metrics::Metrics,
+    provider::{ChainError, MultiChainProvider},
+    routes::{self, Jobs},
+    scheduler::Scheduler,
+    store::VerificationStore,
+    tools::ToolVersions,
+};
 use axum::{
+    middleware,
     routing::{get, post, IntoMakeService},
     Router, Server,
 };
 use dotenvy::dotenv;
+use ethers::types::Chain;
 use headers::HeaderName;
 use hyper::{server::conn::AddrIncoming, Method};
-use std::net::TcpListener;
+use std::{collections::HashMap, future::Future, net::TcpListener, sync::Arc, time::Duration};
+use tokio::sync::oneshot;
 use tower::ServiceBuilder;
 use tower_http::{
     cors::{Any, CorsLayer},
@@ -15,8 +29,199 @@ use tower_http::{
     ServiceBuilderExt,
 };
 
-/// Run the application on the given TcpListener and return the HTTP server instance.
-pub fn run(listener: TcpListener) -> hyper::Result<Server<AddrIncoming, IntoMakeService<Router>>> {
+/// State shared with all route handlers.
+pub struct AppState {
+    /// The application settings.
+    pub settings: Settings,
+    /// The provider used for all chain queries. When `None`, a provider that queries each
+    /// configured chain's RPC URL is created per request.
+    pub provider: Option<Arc<MultiChainProvider>>,
+    /// The versions of the external binaries used for verification, detected at startup.
+    pub tools: ToolVersions,
+    /// Tracks RPC failures across requests so repeatedly failing chains are skipped.
+    pub circuit_breaker: Arc<CircuitBreaker>,
+    /// Runs verifications on a bounded pool of workers, rejecting requests when its queue is full.
+    pub scheduler: Arc<Scheduler>,
+    /// The state of verifications that run asynchronously, keyed by job ID.
+    pub jobs: Jobs,
+    /// Stores successful verifications so `/contract` can return them. This is `None` if no
+    /// database directory is configured or the store could not be opened.
+    pub store: Option<VerificationStore>,
+    /// Collects metrics about verifications, which are served by `/metrics`.
+    pub metrics: Arc<Metrics>,
+    /// Logs verification requests and their results, as selected by the settings.
+    pub logger: Arc<dyn VerificationLogger>,
+}
+
+impl AppState {
+    /// Create the state from the settings, detecting tool versions and using the given provider
+    /// for chain queries if one is set.
+    fn new(settings: Settings, provider: Option<Arc<MultiChainProvider>>) -> Self {
+        let rpc = &settings.rpc;
+        let circuit_breaker = Arc::new(CircuitBreaker::new(
+            rpc.failure_threshold,
+            Duration::from_secs(rpc.failure_window_secs),
+            Duration::from_secs(rpc.cooldown_secs),
+        ));
+        let scheduler =
+            Arc::new(Scheduler::new(settings.scheduler.workers, settings.scheduler.queue_capacity));
+        let store = settings.database.dir.as_ref().and_then(|dir| {
+            VerificationStore::new(dir)
+                .map_err(|err| {
+                    tracing::warn!("Failed to open verification store {}: {err}", dir.display())
+                })
+                .ok()
+        });
+        let logger = logger_from_settings(&settings.request_log);
+        Self {
+            settings,
+            provider,
+            tools: ToolVersions::detect(),
+            circuit_breaker,
+            scheduler,
+            jobs: Jobs::default(),
+            store,
+            metrics: Arc::new(Metrics::default()),
+            logger,
+        }
+    }
+
+    /// Returns the provider used for chain queries, creating one that queries each configured
+    /// chain's RPC URL if none was set. Returns an error if a chain's RPC URL is missing or
+    /// invalid.
+    pub fn provider(&self) -> Result<Arc<MultiChainProvider>, ChainError> {
+        if let Some(provider) = &self.provider {
+            return Ok(provider.clone())
+        }
+        Ok(Arc::new(self.new_provider(&self.settings.chains)?))
+    }
+
+    /// Returns a provider that only queries the given chains. Unlike restricting `provider` with
+    /// `with_only_chains`, RPC providers are only created for the given chains. Returns an error
+    /// if any of the chains isn't configured or its RPC URL is missing or invalid.
+    pub fn provider_for_chains(
+        &self,
+        chains: &[Chain],
+    ) -> Result<Arc<MultiChainProvider>, ChainError> {
+        if let Some(provider) = &self.provider {
+            return Ok(Arc::new(provider.with_only_chains(chains)?))
+        }
+        let chain_settings = self
+            .settings
+            .chains
+            .iter()
+            .filter(|(chain_id, _)| chains.iter().any(|chain| u64::from(*chain) == **chain_id))
+            .map(|(chain_id, settings)| (*chain_id, settings.clone()))
+            .collect();
+        // Chains whose RPC URL is unset are skipped when creating the provider, so this also
+        // rejects those.
+        Ok(Arc::new(self.new_provider(&chain_settings)?.with_only_chains(chains)?))
+    }
+
+    /// Creates a provider that queries the given chains' RPC URLs, configured by the settings.
+    fn new_provider(
+        &self,
+        chains: &HashMap<u64, ChainSettings>,
+    ) -> Result<MultiChainProvider, ChainError> {
+        let batched_chains = self.settings.rpc.batch_requests.iter().copied();
+        let contract_creator_chains = self.settings.rpc.contract_creator_lookup.iter().copied();
+        let padding = self.settings.verification.deployed_code_padding.clone();
+        Ok(MultiChainProvider::new(chains)?
+            .with_batching(batched_chains)
+            .with_circuit_breaker(self.circuit_breaker.clone())
+            .with_metrics(self.metrics.clone())
+            .with_deployed_code_padding(padding)
+            .with_tracing(self.settings.rpc.tracing_enabled)
+            .with_contract_creator_lookup(contract_creator_chains)
+            .with_creation_search_max_blocks(self.settings.rpc.creation_search_max_blocks)
+            .with_retry(self.settings.rpc.retry_policy()))
+    }
+}
+
+/// Run the application on the given TcpListener and return the HTTP server instance. The settings
+/// are shared with all route handlers.
+pub fn run(
+    listener: TcpListener,
+    settings: Settings,
+) -> hyper::Result<Server<AddrIncoming, IntoMakeService<Router>>> {
+    serve(listener, Arc::new(AppState::new(settings, None)))
+}
+
+/// Run the application the same as `run` until `signal` resolves, then shut down gracefully: the
+/// server stops accepting connections, and in-flight requests and background verifications are
+/// given the configured shutdown timeout to finish. Verifications still running after the timeout
+/// are aborted when the returned future completes, which kills their builds and removes their
+/// build directories.
+pub async fn run_until_shutdown(
+    listener: TcpListener,
+    settings: Settings,
+    signal: impl Future<Output = ()>,
+) -> hyper::Result<()> {
+    let state = Arc::new(AppState::new(settings, None));
+    let timeout = state.settings.application.shutdown_timeout();
+    let (shutdown_sender, shutdown_started) = oneshot::channel();
+    let server = serve(listener, state.clone())?.with_graceful_shutdown(async move {
+        signal.await;
+        println!("Shutting down, waiting up to {}s for requests to finish.", timeout.as_secs());
+        let _ = shutdown_sender.send(());
+    });
+    tokio::pin!(server);
+
+    // The timeout only starts once the signal is received.
+    tokio::select! {
+        result = &mut server => return result,
+        _ = shutdown_started => (),
+    }
+    let drain = async {
+        let result = server.await;
+        state.scheduler.wait_until_idle().await;
+        result
+    };
+    match tokio::time::timeout(timeout, drain).await {
+        Ok(result) => result,
+        Err(_) => {
+            println!("WARNING: Shutdown timed out, aborting unfinished verifications.");
+            Ok(())
+        }
+    }
+}
+
+/// Returns a future that resolves when the process receives SIGTERM or SIGINT.
+pub async fn shutdown_signal() {
+    let interrupt = async {
+        tokio::signal::ctrl_c().await.expect("Failed to listen for SIGINT");
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = interrupt => (),
+        _ = terminate => (),
+    }
+}
+
+/// Run the application the same as `run`, but use the given provider for all chain queries. This
+/// is used to run the app against in-memory providers in tests.
+pub fn run_with_provider(
+    listener: TcpListener,
+    settings: Settings,
+    provider: MultiChainProvider,
+) -> hyper::Result<Server<AddrIncoming, IntoMakeService<Router>>> {
+    serve(listener, Arc::new(AppState::new(settings, Some(Arc::new(provider)))))
+}
+
+/// Build the router with the given state and serve it on the given TcpListener.
+fn serve(
+    listener: TcpListener,
+    state: Arc<AppState>,
+) -> hyper::Result<Server<AddrIncoming, IntoMakeService<Router>>> {
     if dotenv().is_err() {
         // We don't error since there's no `.env` file in CI.
         println!("WARNING: No .env file found, using default environment variables.");
@@ -44,14 +249,56 @@ pub fn run(listener: TcpListener) -> hyper::Result<Server<AddrIncoming, IntoMake
             HeaderName::from_static("authorization"),
         ]);
 
+    // Verification requests, and requests for the state of verification jobs, must be signed if an
+    // HMAC secret is configured.
+    let auth_layer = middleware::from_fn_with_state(state.clone(), auth::require_hmac_signature);
+
+    // Finished jobs are removed once they have been kept long enough, even if no new jobs start.
+    routes::spawn_job_pruning(&state.jobs);
+
     // Build our application with a single route.
     let app = Router::new()
         .route("/health_check", get(routes::health_check))
-        .route("/verify", post(routes::verify))
+        .route("/health_check/detailed", get(routes::health_check_detailed))
+        .route("/verify", post(routes::verify).layer(auth_layer.clone()))
+        .route("/verify/metadata", post(routes::verify_metadata).layer(auth_layer.clone()))
+        .route("/verify/stream", post(routes::verify_stream).layer(auth_layer.clone()))
+        .route("/verify/status/:job_id", get(routes::verify_status).layer(auth_layer))
+        .route("/verifications/:verification_id/sources.zip", get(routes::sources_zip))
         .route("/contract", get(routes::contract))
+        .route("/sourcify-export/:chain_id/:address", get(routes::sourcify_export))
+        .route("/version", get(routes::version))
+        .route("/metrics", get(routes::metrics))
         .layer(trace_layer)
-        .layer(cors_layer);
+        .layer(cors_layer)
+        .with_state(state);
 
     // Run it with hyper on the given TcpListener.
     Ok(axum::Server::from_tcp(listener)?.serve(app.into_make_service()))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::get_configuration;
+
+    #[test]
+    fn test_provider_for_chains() {
+        let chain =
+            |rpc_url: &str| ChainSettings { rpc_url: rpc_url.to_string(), archive_rpc_url: None };
+        let mut settings = get_configuration().expect("Failed to read configuration");
+        settings.chains =
+            HashMap::from([(1, chain("http://localhost:8545")), (137, chain("not a url"))]);
+        settings.database.dir = None;
+        let state = AppState::new(settings, None);
+        assert!(matches!(state.provider().err(), Some(ChainError::InvalidRpcUrl(..))));
+
+        // Only the given chains' providers are created, so other chains' invalid URLs are ignored.
+        let provider = state.provider_for_chains(&[Chain::Mainnet]).unwrap();
+        assert_eq!(provider.chains, vec![Chain::Mainnet]);
+        assert_eq!(
+            state.provider_for_chains(&[Chain::Optimism]).err(),
+            Some(ChainError::NotConfigured(Chain::Optimism))
+        );
+    }
+}
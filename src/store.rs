@@ -0,0 +1,172 @@
+use crate::routes::SuccessfulVerification;
+use ethers::types::{Address, Chain};
+use std::{
+    error::Error,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+/// The version of the format verifications are stored in. Increment this when a change to
+/// `SuccessfulVerification` means previously stored verifications can no longer be parsed.
+const SCHEMA_VERSION: u32 = 1;
+
+/// The file in the store containing the version of the format verifications are stored in.
+const SCHEMA_VERSION_FILE: &str = "schema_version";
+
+/// The directory in the store containing the index from chain and contract address to verification.
+const INDEX_DIR: &str = "index";
+
+/// Stores successful verifications as JSON files in a directory, one file per verification named
+/// by its verification ID. An index maps each chain and contract address the verification matched
+/// on to its verification ID, so verifications are looked up without reading every file.
+#[derive(Debug, Clone)]
+pub struct VerificationStore {
+    /// The directory verifications are stored in.
+    dir: PathBuf,
+}
+
+impl VerificationStore {
+    /// Create a new `VerificationStore` in the given directory, creating it if needed. If the
+    /// directory holds verifications stored in an older format, they are removed, since they can't
+    /// be parsed, and the contracts must be verified again to be stored.
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self, Box<dyn Error>> {
+        let dir = dir.into();
+        fs::create_dir_all(dir.join(INDEX_DIR))?;
+        let store = Self { dir };
+        store.migrate()?;
+        Ok(store)
+    }
+
+    /// Saves the verification, replacing any existing verification with the same ID, and indexes
+    /// it by each chain it matched on. Files are written atomically so a crash mid-write never
+    /// leaves a partial file in the store.
+    pub fn save(&self, verification: &SuccessfulVerification) -> Result<PathBuf, Box<dyn Error>> {
+        let path = self.dir.join(format!("{}.json", verification.verification_id));
+        write_atomic(&path, &serde_json::to_vec(verification)?)?;
+        for chain in verification.matches.keys() {
+            let index_path = self.index_path(*chain, verification.contract_address);
+            write_atomic(&index_path, verification.verification_id.as_bytes())?;
+        }
+        Ok(path)
+    }
+
+    /// Returns the most recently stored verification of the contract at `address` that matched on
+    /// `chain`. Files that can't be read or parsed are logged rather than failing the lookup.
+    pub fn get(&self, chain: Chain, address: Address) -> Option<SuccessfulVerification> {
+        let verification_id = fs::read_to_string(self.index_path(chain, address)).ok()?;
+        let path = self.dir.join(format!("{verification_id}.json"));
+        match read_verification(&path) {
+            Ok(verification) => Some(verification).filter(|verification| {
+                verification.contract_address == address
+                    && verification.matches.contains_key(&chain)
+            }),
+            Err(err) => {
+                tracing::warn!("Skipping unreadable verification {}: {err}", path.display());
+                None
+            }
+        }
+    }
+
+    /// Returns the path of the index entry for the contract at `address` on `chain`.
+    fn index_path(&self, chain: Chain, address: Address) -> PathBuf {
+        self.dir.join(INDEX_DIR).join(format!("{}-{address:?}", u64::from(chain)))
+    }
+
+    /// Removes verifications stored in a different format than `SCHEMA_VERSION`, then records the
+    /// current version. Stores created before the version was recorded are treated as version 0.
+    fn migrate(&self) -> Result<(), Box<dyn Error>> {
+        let version_path = self.dir.join(SCHEMA_VERSION_FILE);
+        let version = match fs::read_to_string(&version_path) {
+            Ok(version) => version.trim().parse::<u32>()?,
+            Err(_) => 0,
+        };
+        if version == SCHEMA_VERSION {
+            return Ok(())
+        }
+
+        tracing::warn!(
+            "Removing verifications stored with schema version {version} from {}",
+            self.dir.display()
+        );
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().map_or(false, |ext| ext == "json") {
+                fs::remove_file(path)?;
+            }
+        }
+        fs::remove_dir_all(self.dir.join(INDEX_DIR))?;
+        fs::create_dir_all(self.dir.join(INDEX_DIR))?;
+        write_atomic(&version_path, SCHEMA_VERSION.to_string().as_bytes())
+    }
+}
+
+/// Reads and parses a stored verification.
+fn read_verification(path: &Path) -> Result<SuccessfulVerification, Box<dyn Error>> {
+    Ok(serde_json::from_slice(&fs::read(path)?)?)
+}
+
+/// Writes `contents` to a temporary file in the same directory as `path`, flushes it to disk, and
+/// renames it to `path`. Temporary files don't have a `.json` extension and aren't named like index
+/// entries, so a write interrupted before the rename is never read back from the store.
+fn write_atomic(path: &Path, contents: &[u8]) -> Result<(), Box<dyn Error>> {
+    let dir = path.parent().ok_or("Store path has no parent directory")?;
+    let mut temp_file = tempfile::NamedTempFile::new_in(dir)?;
+    temp_file.write_all(contents)?;
+    temp_file.as_file().sync_all()?;
+    temp_file.persist(path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_partial_write_is_not_observable() {
+        let dir = TempDir::new().unwrap();
+        let store = VerificationStore::new(dir.path()).unwrap();
+        let path = dir.path().join("0x01.json");
+
+        // Simulate a crash mid-save by leaving a partially written temporary file behind.
+        let mut partial = tempfile::NamedTempFile::new_in(dir.path()).unwrap();
+        partial.write_all(br#"{"verification_id":"0x01","repo_url":"#).unwrap();
+        partial.keep().unwrap();
+        assert!(!path.exists());
+        assert!(store.get(Chain::Mainnet, Address::zero()).is_none());
+
+        // A corrupt file in the store is skipped instead of panicking.
+        fs::write(dir.path().join("0x02.json"), b"{").unwrap();
+        assert!(store.get(Chain::Mainnet, Address::zero()).is_none());
+
+        // A corrupt file that is indexed is skipped instead of panicking.
+        fs::write(store.index_path(Chain::Mainnet, Address::zero()), "0x02").unwrap();
+        assert!(store.get(Chain::Mainnet, Address::zero()).is_none());
+
+        // A completed write is only visible at its final path, with its full contents.
+        write_atomic(&path, br#"{"verification_id":"0x01"}"#).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), br#"{"verification_id":"0x01"}"#);
+    }
+
+    #[test]
+    fn test_migrate_removes_old_verifications() {
+        let dir = TempDir::new().unwrap();
+        let store = VerificationStore::new(dir.path()).unwrap();
+        let index_path = store.index_path(Chain::Mainnet, Address::zero());
+        fs::write(dir.path().join("0x01.json"), "{}").unwrap();
+        fs::write(&index_path, "0x01").unwrap();
+
+        // Verifications in the current format are kept.
+        VerificationStore::new(dir.path()).unwrap();
+        assert!(dir.path().join("0x01.json").exists());
+
+        // Verifications in an older format are removed, along with their index entries.
+        fs::write(dir.path().join(SCHEMA_VERSION_FILE), "0").unwrap();
+        VerificationStore::new(dir.path()).unwrap();
+        assert!(!dir.path().join("0x01.json").exists());
+        assert!(!index_path.exists());
+        let version = fs::read_to_string(dir.path().join(SCHEMA_VERSION_FILE)).unwrap();
+        assert_eq!(version, SCHEMA_VERSION.to_string());
+    }
+}
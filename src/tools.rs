@@ -0,0 +1,165 @@
+use serde::{Deserialize, Serialize};
+use std::process::{Command, Output};
+
+/// Versions of the external binaries that verification depends on. A version is `None` if the
+/// binary could not be run, which usually means it's not installed or not on the `PATH`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ToolVersions {
+    /// The output of `git --version`, used to clone repositories.
+    pub git: Option<String>,
+    /// The output of `forge --version`, used to build Foundry projects.
+    pub forge: Option<String>,
+}
+
+impl ToolVersions {
+    /// Detects the version of each required binary.
+    pub fn detect() -> Self {
+        Self { git: tool_version_blocking("git"), forge: tool_version_blocking("forge") }
+    }
+
+    /// Returns an error listing the required binaries that could not be found.
+    pub fn ensure_installed(&self) -> Result<(), String> {
+        let missing: Vec<&str> = [("git", &self.git), ("forge", &self.forge)]
+            .into_iter()
+            .filter(|(_, version)| version.is_none())
+            .map(|(name, _)| name)
+            .collect();
+        if missing.is_empty() {
+            return Ok(())
+        }
+        Err(format!(
+            "Required binaries not found: {}. Install them and ensure they are on the PATH.",
+            missing.join(", ")
+        ))
+    }
+}
+
+/// Returns an error if `allowed` is non-empty and the forge version is not one of the allowed
+/// versions. Each allowed version matches either the semver or the commit hash in the output of
+/// `forge --version`, e.g. `0.2.0` or `a1b2c3d` for `forge 0.2.0 (a1b2c3d 2023-06-01T00:00:00Z)`.
+pub fn check_forge_version(version: Option<&str>, allowed: &[String]) -> Result<(), String> {
+    if allowed.is_empty() {
+        return Ok(())
+    }
+    let Some(version) = version else {
+        return Err("Unable to detect the forge version to check against the allowlist.".into())
+    };
+    let Some((semver, commit)) = parse_forge_version(version) else {
+        return Err(format!("Unable to parse the installed forge version '{}'.", version))
+    };
+    let is_allowed = |allowed: &String| allowed == semver || Some(allowed.as_str()) == commit;
+    if allowed.iter().any(is_allowed) {
+        return Ok(())
+    }
+    Err(format!(
+        "Installed forge version '{}' is not one of the allowed versions: {}.",
+        version,
+        allowed.join(", ")
+    ))
+}
+
+/// Parses the output of `forge --version`, e.g. `forge 0.2.0 (a1b2c3d 2023-06-01T00:00:00Z)`, into
+/// its semver and commit hash. Newer versions print `forge Version: 1.0.0-stable` and the commit on
+/// a separate line, so the commit is `None` for those. Returns `None` if the output isn't in either
+/// format.
+fn parse_forge_version(version: &str) -> Option<(&str, Option<&str>)> {
+    let version = version.strip_prefix("forge ")?;
+    let mut parts = version.strip_prefix("Version: ").unwrap_or(version).split_whitespace();
+    let semver = parts.next()?;
+    // The version may have a pre-release or build suffix, e.g. `0.2.0-nightly`.
+    let core = semver.split(['-', '+']).next()?;
+    let is_semver =
+        core.split('.').count() == 3 && core.split('.').all(|n| n.parse::<u64>().is_ok());
+    if !is_semver {
+        return None
+    }
+    let commit = parts
+        .next()
+        .and_then(|part| part.strip_prefix('('))
+        .map(|commit| commit.trim_end_matches(')'));
+    Some((semver, commit.filter(|commit| !commit.is_empty())))
+}
+
+/// Runs `<binary> --version` and returns the first line of its output, or `None` if the binary
+/// could not be run or exited with an error.
+pub async fn tool_version(binary: &str) -> Option<String> {
+    version_line(tokio::process::Command::new(binary).arg("--version").output().await)
+}
+
+/// Runs `<binary> --version` like `tool_version`, blocking the current thread.
+fn tool_version_blocking(binary: &str) -> Option<String> {
+    version_line(Command::new(binary).arg("--version").output())
+}
+
+/// Returns the first line of the output of a `--version` command, or `None` if it failed.
+fn version_line(output: std::io::Result<Output>) -> Option<String> {
+    let output = output.ok()?;
+    if !output.status.success() {
+        return None
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout.lines().next().map(|line| line.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ensure_installed() {
+        let tools = ToolVersions { git: Some("git version 2.40.0".into()), forge: None };
+        let err = tools.ensure_installed().unwrap_err();
+        assert!(err.contains("forge") && !err.contains("git,"), "{err}");
+
+        let tools = ToolVersions {
+            git: Some("git version 2.40.0".into()),
+            forge: Some("forge 0.2.0 (a1b2c3d 2023-06-01T00:00:00.000000000Z)".into()),
+        };
+        assert!(tools.ensure_installed().is_ok());
+        assert_eq!(tool_version_blocking("cove-nonexistent-binary"), None);
+    }
+
+    #[tokio::test]
+    async fn test_tool_version() {
+        assert!(tool_version("git").await.unwrap().starts_with("git version"));
+        assert_eq!(tool_version("cove-nonexistent-binary").await, None);
+    }
+
+    #[test]
+    fn test_check_forge_version() {
+        let version = Some("forge 0.2.0 (a1b2c3d 2023-06-01T00:00:00.000000000Z)");
+        assert!(check_forge_version(version, &[]).is_ok());
+        assert!(check_forge_version(None, &[]).is_ok());
+        assert!(check_forge_version(version, &["0.2.0".into()]).is_ok());
+        assert!(check_forge_version(version, &["0.1.0".into(), "a1b2c3d".into()]).is_ok());
+
+        let err = check_forge_version(version, &["0.1.0".into()]).unwrap_err();
+        assert!(err.contains("not one of the allowed versions: 0.1.0"), "{err}");
+        assert!(check_forge_version(version, &["0.2".into()]).is_err());
+        assert!(check_forge_version(None, &["0.2.0".into()]).is_err());
+
+        // Only the semver and commit fields match, not any other part of the output.
+        assert!(check_forge_version(version, &["forge".into()]).is_err());
+        assert!(check_forge_version(version, &["2023-06-01T00:00:00.000000000Z".into()]).is_err());
+        let err = check_forge_version(Some("0.2.0"), &["0.2.0".into()]).unwrap_err();
+        assert!(err.contains("Unable to parse"), "{err}");
+    }
+
+    #[test]
+    fn test_parse_forge_version() {
+        let version = "forge 0.2.0 (a1b2c3d 2023-06-01T00:00:00.000000000Z)";
+        assert_eq!(parse_forge_version(version), Some(("0.2.0", Some("a1b2c3d"))));
+        assert_eq!(parse_forge_version("forge 0.2.0"), Some(("0.2.0", None)));
+        assert_eq!(parse_forge_version("forge 0.2.0-nightly"), Some(("0.2.0-nightly", None)));
+        assert_eq!(
+            parse_forge_version("forge 1.0.0-rc.1 (a1b2c3d)"),
+            Some(("1.0.0-rc.1", Some("a1b2c3d")))
+        );
+        assert_eq!(
+            parse_forge_version("forge Version: 1.0.0-stable"),
+            Some(("1.0.0-stable", None))
+        );
+        assert_eq!(parse_forge_version("forge nightly (a1b2c3d)"), None);
+        assert_eq!(parse_forge_version("anvil 0.2.0 (a1b2c3d)"), None);
+    }
+}
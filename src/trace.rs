@@ -0,0 +1,283 @@
+use crate::{provider::ChainProvider, retry::RetryPolicy};
+use ethers::{
+    providers::Middleware,
+    types::{
+        Action, Address, Bytes, CallFrame, Chain, GethDebugBuiltInTracerType, GethDebugTracerType,
+        GethDebugTracingOptions, GethTrace, GethTraceFrame, NameOrAddress, Res, Trace, TxHash,
+    },
+};
+use serde::{Deserialize, Serialize};
+use std::{error::Error, sync::Arc};
+
+/// The RPC method used to trace a transaction. Nodes expose different tracing APIs, so the method
+/// must be chosen based on the chain and on what the node supports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceMethod {
+    /// Parity/OpenEthereum-style `trace_transaction`, also supported by Erigon, Nethermind, and
+    /// most hosted providers.
+    Parity,
+    /// Geth-style `debug_traceTransaction` using the built-in `callTracer`.
+    Geth,
+    /// Arbitrum's `arbtrace_transaction`, which is required for transactions from before the
+    /// Nitro upgrade and otherwise mirrors the Parity-style response.
+    Arbitrum,
+}
+
+impl TraceMethod {
+    /// Returns the name of the RPC method.
+    pub fn rpc_method(&self) -> &'static str {
+        match self {
+            TraceMethod::Parity => "trace_transaction",
+            TraceMethod::Geth => "debug_traceTransaction",
+            TraceMethod::Arbitrum => "arbtrace_transaction",
+        }
+    }
+
+    /// Returns the trace methods to try for the given chain, in order of preference. Providers
+    /// don't advertise which tracing APIs they support, so we detect support by trying each
+    /// method in turn until one succeeds.
+    pub fn candidates(chain: Chain) -> Vec<TraceMethod> {
+        match chain {
+            Chain::Arbitrum | Chain::ArbitrumGoerli | Chain::ArbitrumNova => {
+                vec![TraceMethod::Geth, TraceMethod::Arbitrum]
+            }
+            _ => vec![TraceMethod::Parity, TraceMethod::Geth],
+        }
+    }
+}
+
+/// A contract deployment found in a transaction trace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TracedCreation {
+    /// The address of the deployed contract.
+    pub address: Address,
+    /// The creation code used to deploy the contract.
+    pub creation_code: Bytes,
+}
+
+/// Traces the transaction with the given hash and returns every contract deployed by it, including
+/// deployments made by factories. The trace method is selected based on the chain, falling back
+/// to the next candidate method if the provider does not support it. Each trace call is retried
+/// with `retry` after transient failures, so only unsupported methods fall back.
+pub async fn trace_creations(
+    provider: &Arc<ChainProvider>,
+    chain: Chain,
+    tx_hash: TxHash,
+    retry: &RetryPolicy,
+) -> Result<Vec<TracedCreation>, Box<dyn Error + Send + Sync>> {
+    let mut errors = Vec::new();
+    for method in TraceMethod::candidates(chain) {
+        match retry.retry(|| trace_with_method(provider, method, tx_hash)).await {
+            Ok(creations) => return Ok(creations),
+            Err(e) => errors.push(format!("{}: {}", method.rpc_method(), e)),
+        }
+    }
+    Err(format!("Unable to trace transaction {:?}: {}", tx_hash, errors.join("; ")).into())
+}
+
+/// Traces the transaction with the given hash and returns the creation code of the contract
+/// deployed at `address` by that transaction.
+pub async fn trace_creation_code(
+    provider: &Arc<ChainProvider>,
+    chain: Chain,
+    tx_hash: TxHash,
+    address: Address,
+    retry: &RetryPolicy,
+) -> Result<Bytes, Box<dyn Error + Send + Sync>> {
+    trace_creations(provider, chain, tx_hash, retry)
+        .await?
+        .into_iter()
+        .find(|creation| creation.address == address)
+        .map(|creation| creation.creation_code)
+        .ok_or_else(|| "Transaction trace does not contain a deployment of this contract.".into())
+}
+
+/// The response of the Otterscan `ots_getContractCreator` RPC method.
+#[derive(Debug, Serialize, Deserialize)]
+struct ContractCreator {
+    /// The hash of the transaction that deployed the contract.
+    hash: TxHash,
+}
+
+/// Finds the hash of the transaction that deployed the contract at `address`, using the
+/// `ots_getContractCreator` method that Erigon and Anvil nodes derive from their trace indexes.
+/// Other node clients don't support the method, so it's only used for chains whose RPCs are
+/// configured to support it. Returns `None` if the node doesn't know of a deployment at the
+/// address, and an error if the provider does not support the method.
+pub async fn find_creation_tx_hash(
+    provider: &Arc<ChainProvider>,
+    address: Address,
+) -> Result<Option<TxHash>, Box<dyn Error + Send + Sync>> {
+    let creator: Option<ContractCreator> =
+        provider.request("ots_getContractCreator", [address]).await?;
+    Ok(creator.map(|creator| creator.hash))
+}
+
+/// Traces the transaction using the given method and returns all contract deployments.
+async fn trace_with_method(
+    provider: &Arc<ChainProvider>,
+    method: TraceMethod,
+    tx_hash: TxHash,
+) -> Result<Vec<TracedCreation>, Box<dyn Error + Send + Sync>> {
+    match method {
+        TraceMethod::Parity => {
+            let traces = provider.trace_transaction(tx_hash).await?;
+            Ok(creations_from_parity_traces(&traces))
+        }
+        TraceMethod::Geth => {
+            let options = GethDebugTracingOptions {
+                tracer: Some(GethDebugTracerType::BuiltInTracer(
+                    GethDebugBuiltInTracerType::CallTracer,
+                )),
+                ..Default::default()
+            };
+            match provider.debug_trace_transaction(tx_hash, options).await? {
+                GethTrace::Known(GethTraceFrame::CallTracer(frame)) => {
+                    Ok(creations_from_call_frame(&frame))
+                }
+                _ => Err("Unexpected response format from debug_traceTransaction".into()),
+            }
+        }
+        TraceMethod::Arbitrum => {
+            let traces: Vec<Trace> = provider.request(method.rpc_method(), [tx_hash]).await?;
+            Ok(creations_from_parity_traces(&traces))
+        }
+    }
+}
+
+/// Extracts all successful contract deployments from Parity-style traces.
+fn creations_from_parity_traces(traces: &[Trace]) -> Vec<TracedCreation> {
+    traces
+        .iter()
+        .filter_map(|trace| match (&trace.action, &trace.result) {
+            (Action::Create(create), Some(Res::Create(result))) => {
+                Some(TracedCreation { address: result.address, creation_code: create.init.clone() })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Recursively extracts all successful contract deployments from a Geth `callTracer` frame.
+fn creations_from_call_frame(frame: &CallFrame) -> Vec<TracedCreation> {
+    let mut creations = Vec::new();
+    let is_create = matches!(frame.typ.as_str(), "CREATE" | "CREATE2");
+    if is_create && frame.error.is_none() {
+        if let Some(NameOrAddress::Address(address)) = frame.to {
+            creations.push(TracedCreation { address, creation_code: frame.input.clone() });
+        }
+    }
+    for call in frame.calls.iter().flatten() {
+        creations.extend(creations_from_call_frame(call));
+    }
+    creations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_candidates() {
+        assert_eq!(
+            TraceMethod::candidates(Chain::Mainnet),
+            vec![TraceMethod::Parity, TraceMethod::Geth]
+        );
+        assert_eq!(
+            TraceMethod::candidates(Chain::Arbitrum),
+            vec![TraceMethod::Geth, TraceMethod::Arbitrum]
+        );
+    }
+
+    #[test]
+    fn test_creations_from_parity_traces() {
+        let traces: Vec<Trace> = serde_json::from_value(serde_json::json!([
+            {
+                "action": {
+                    "from": "0x1111111111111111111111111111111111111111",
+                    "callType": "call",
+                    "gas": "0x1000",
+                    "input": "0x1234",
+                    "to": "0x2222222222222222222222222222222222222222",
+                    "value": "0x0"
+                },
+                "blockHash": "0x0000000000000000000000000000000000000000000000000000000000000001",
+                "blockNumber": 1,
+                "result": { "gasUsed": "0x10", "output": "0x" },
+                "subtraces": 1,
+                "traceAddress": [],
+                "type": "call"
+            },
+            {
+                "action": {
+                    "from": "0x2222222222222222222222222222222222222222",
+                    "gas": "0x1000",
+                    "init": "0x6080604052",
+                    "value": "0x0"
+                },
+                "blockHash": "0x0000000000000000000000000000000000000000000000000000000000000001",
+                "blockNumber": 1,
+                "result": {
+                    "address": "0x3333333333333333333333333333333333333333",
+                    "code": "0x6080",
+                    "gasUsed": "0x10"
+                },
+                "subtraces": 0,
+                "traceAddress": [0],
+                "type": "create"
+            }
+        ]))
+        .unwrap();
+
+        let creations = creations_from_parity_traces(&traces);
+        assert_eq!(
+            creations,
+            vec![TracedCreation {
+                address: Address::from_str("0x3333333333333333333333333333333333333333").unwrap(),
+                creation_code: Bytes::from_str("0x6080604052").unwrap(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_creations_from_call_frame() {
+        let frame: CallFrame = serde_json::from_value(serde_json::json!({
+            "type": "CALL",
+            "from": "0x1111111111111111111111111111111111111111",
+            "to": "0x2222222222222222222222222222222222222222",
+            "gas": "0x1000",
+            "gasUsed": "0x10",
+            "input": "0x1234",
+            "calls": [
+                {
+                    "type": "CREATE2",
+                    "from": "0x2222222222222222222222222222222222222222",
+                    "to": "0x3333333333333333333333333333333333333333",
+                    "gas": "0x1000",
+                    "gasUsed": "0x10",
+                    "input": "0x6080604052"
+                },
+                {
+                    "type": "CREATE",
+                    "from": "0x2222222222222222222222222222222222222222",
+                    "to": "0x4444444444444444444444444444444444444444",
+                    "gas": "0x1000",
+                    "gasUsed": "0x10",
+                    "input": "0x6080",
+                    "error": "execution reverted"
+                }
+            ]
+        }))
+        .unwrap();
+
+        let creations = creations_from_call_frame(&frame);
+        assert_eq!(
+            creations,
+            vec![TracedCreation {
+                address: Address::from_str("0x3333333333333333333333333333333333333333").unwrap(),
+                creation_code: Bytes::from_str("0x6080604052").unwrap(),
+            }]
+        );
+    }
+}
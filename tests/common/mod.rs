@@ -1,6 +1,11 @@
-use cove::telemetry::{get_subscriber, init_subscriber};
+use cove::{
+    config::get_configuration,
+    provider::MultiChainProvider,
+    telemetry::{get_subscriber, init_subscriber},
+};
 use once_cell::sync::Lazy;
 use std::net::TcpListener;
+use tempfile::TempDir;
 
 // Ensure that the `tracing` stack is only initialized once.
 static TRACING: Lazy<()> = Lazy::new(|| {
@@ -25,12 +30,25 @@ static TRACING: Lazy<()> = Lazy::new(|| {
 
 pub struct TestApp {
     pub address: String,
+    /// The directory the app stores verifications in, which is removed when the app is dropped.
+    _database_dir: TempDir,
 }
 
 // Launch our application in the background.
 // We are running tests, so it is not worth it to propagate errors: if we fail to perform the
 // required setup we can just panic and crash all the things.
 pub async fn spawn_app() -> TestApp {
+    spawn_app_inner(None).await
+}
+
+// Launch our application in the background, using the given provider for all chain queries instead
+// of the RPC providers.
+#[allow(dead_code)] // Not every test binary uses this.
+pub async fn spawn_app_with_provider(provider: MultiChainProvider) -> TestApp {
+    spawn_app_inner(Some(provider)).await
+}
+
+async fn spawn_app_inner(provider: Option<MultiChainProvider>) -> TestApp {
     Lazy::force(&TRACING);
 
     let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind random port");
@@ -39,11 +57,21 @@ pub async fn spawn_app() -> TestApp {
     let port = listener.local_addr().unwrap().port();
     let address = format!("http://127.0.0.1:{port}");
 
+    // Read the configuration used by the server. Each app stores verifications in its own
+    // directory, so tests don't see each other's verifications.
+    let mut configuration = get_configuration().expect("Failed to read configuration.");
+    let database_dir = tempfile::tempdir().expect("Failed to create database directory");
+    configuration.database.dir = Some(database_dir.path().to_path_buf());
+
     // Launch the server as a background task.
     // `tokio::`spawn returns a handle to the spawned future, but we have no use for it here, hence
     // the non-binding `let`.
-    let server = cove::startup::run(listener).expect("Failed to bind address");
+    let server = match provider {
+        Some(provider) => cove::startup::run_with_provider(listener, configuration, provider),
+        None => cove::startup::run(listener, configuration),
+    }
+    .expect("Failed to bind address");
     let _ = tokio::spawn(server);
 
-    TestApp { address }
+    TestApp { address, _database_dir: database_dir }
 }
@@ -0,0 +1,14 @@
+// SPDX-License-Identifier: UNLICENSED
+pragma solidity ^0.8.13;
+
+contract CounterBasic {
+  uint256 public number;
+
+  function setNumber(uint256 newNumber) public {
+    number = newNumber;
+  }
+
+  function increment() public {
+    number++;
+  }
+}
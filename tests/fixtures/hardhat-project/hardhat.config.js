@@ -0,0 +1,6 @@
+module.exports = {
+  solidity: {
+    version: "0.8.19",
+    settings: { optimizer: { enabled: true, runs: 200 } },
+  },
+};
@@ -1,3 +1,10 @@
+use cove::{
+    provider::MultiChainProvider,
+    routes::DetailedHealth,
+    rpc::{InMemoryClient, RpcClient},
+};
+use ethers::{providers::Provider, types::Chain};
+use std::{collections::HashMap, sync::Arc};
 mod common;
 
 // `tokio::test` is the testing equivalent of `tokio::main`. It also spares you from having to
@@ -19,3 +26,31 @@ async fn health_check_works() {
     assert_eq!(200, response.status().as_u16());
     assert_eq!(Some(0), response.content_length());
 }
+
+#[tokio::test]
+async fn detailed_health_check_reports_rpcs() -> Result<(), Box<dyn std::error::Error>> {
+    // Goerli's RPC responds with the latest block, and mainnet's RPC returns an error.
+    let goerli = InMemoryClient::new().with_response("eth_blockNumber", (), "0x10")?;
+    let providers = HashMap::from([
+        (Chain::Goerli, Arc::new(Provider::new(RpcClient::InMemory(goerli)))),
+        (Chain::Mainnet, Arc::new(Provider::new(RpcClient::InMemory(InMemoryClient::new())))),
+    ]);
+    let app = common::spawn_app_with_provider(MultiChainProvider::from_providers(providers)).await;
+
+    let response = reqwest::Client::new()
+        .get(&format!("{}/health_check/detailed", app.address))
+        .send()
+        .await?;
+
+    // A chain with an unreachable RPC makes the server unhealthy.
+    assert_eq!(503, response.status().as_u16());
+    let health: DetailedHealth = response.json().await?;
+    assert!(!health.healthy);
+    assert_eq!(health.rpcs[&Chain::Goerli].block_number, Some(16));
+    assert!(health.rpcs[&Chain::Goerli].reachable);
+    assert!(!health.rpcs[&Chain::Mainnet].reachable);
+    assert!(health.rpcs[&Chain::Mainnet].error.is_some());
+    assert!(health.git.available);
+    assert!(health.heimdall.available);
+    Ok(())
+}
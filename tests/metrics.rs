@@ -0,0 +1,59 @@
+use serde_json::json;
+mod common;
+
+#[tokio::test]
+async fn metrics_reports_idle_scheduler() {
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+
+    // Send the request.
+    let response = client
+        .get(&format!("{}/metrics", app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert on the response.
+    assert_eq!(200, response.status().as_u16());
+    let content_type = response.headers()["content-type"].to_str().unwrap().to_string();
+    assert!(content_type.starts_with("text/plain"));
+    let metrics = response.text().await.expect("Failed to read response.");
+    let lines: Vec<&str> = metrics.lines().collect();
+    assert!(lines.contains(&"cove_scheduler_queue_depth 0"));
+    assert!(lines.contains(&"cove_scheduler_active_jobs 0"));
+    assert!(lines.contains(&"cove_scheduler_rejected_jobs_total 0"));
+}
+
+#[tokio::test]
+async fn metrics_counts_rejected_verifications() {
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+
+    // A verification without a repo or sources is rejected before anything is cloned.
+    let body = json!({
+        "repoUrl": "",
+        "repoCommit": "",
+        "contractAddress": "0x0000000000000000000000000000000000000001",
+        "buildConfig": { "framework": "foundry" },
+    });
+    let response = client
+        .post(&format!("{}/verify", app.address))
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(400, response.status().as_u16());
+
+    // Assert on the metrics.
+    let metrics = client
+        .get(&format!("{}/metrics", app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .text()
+        .await
+        .expect("Failed to read response.");
+    let rejected = r#"cove_verifications_total{outcome="bad_request"} 1"#;
+    assert!(metrics.lines().any(|line| line == rejected));
+}
@@ -0,0 +1,31 @@
+use cove::{config::get_configuration, startup::run_until_shutdown};
+use std::{net::TcpListener, time::Duration};
+use tokio::sync::oneshot;
+
+#[tokio::test]
+async fn server_shuts_down_gracefully_on_signal() {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind random port");
+    let address = format!("http://127.0.0.1:{}", listener.local_addr().unwrap().port());
+    let mut configuration = get_configuration().expect("Failed to read configuration.");
+    configuration.database.dir = None;
+
+    // Run the server until the signal is sent.
+    let (signal, received) = oneshot::channel::<()>();
+    let server = tokio::spawn(run_until_shutdown(listener, configuration, async {
+        let _ = received.await;
+    }));
+    let status = reqwest::get(format!("{address}/health_check"))
+        .await
+        .expect("Failed to execute request.")
+        .status();
+    assert_eq!(200, status.as_u16());
+
+    // Once signaled, the idle server shuts down and stops accepting connections.
+    signal.send(()).unwrap();
+    let result = tokio::time::timeout(Duration::from_secs(10), server)
+        .await
+        .expect("Server did not shut down")
+        .unwrap();
+    assert!(result.is_ok());
+    assert!(reqwest::get(format!("{address}/health_check")).await.is_err());
+}
@@ -0,0 +1,24 @@
+mod common;
+
+#[tokio::test]
+async fn sources_zip_rejects_invalid_and_unknown_ids() {
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+
+    // An ID that isn't a verification ID is rejected before touching the filesystem.
+    let response = client
+        .get(&format!("{}/verifications/..%2Fsecret/sources.zip", app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(400, response.status().as_u16());
+
+    // A valid ID without an archive is not found.
+    let verification_id = format!("0x{}", "ab".repeat(32));
+    let response = client
+        .get(&format!("{}/verifications/{verification_id}/sources.zip", app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(404, response.status().as_u16());
+}
@@ -0,0 +1,30 @@
+mod common;
+
+#[tokio::test]
+async fn sourcify_export_rejects_invalid_and_unknown_contracts() {
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let address = "0x8d56e3e001132d84488DbacDbB01AfB8C3171242";
+
+    // Unknown chains and invalid addresses are rejected.
+    let response = client
+        .get(&format!("{}/sourcify-export/123456789/{address}", app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(400, response.status().as_u16());
+    let response = client
+        .get(&format!("{}/sourcify-export/1/0x1234", app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(400, response.status().as_u16());
+
+    // A contract that was never verified has nothing to export.
+    let response = client
+        .get(&format!("{}/sourcify-export/1/{address}", app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(404, response.status().as_u16());
+}
@@ -1,7 +1,22 @@
 use serde_json::json;
 mod common;
-use cove::routes::verify::SuccessfulVerification;
+use cove::{
+    bytecode::{parse_metadata, MatchType, MetadataHashType},
+    provider::{CreationCodeSource, MultiChainProvider},
+    routes::{
+        verify::{CreationMatchStatus, SuccessfulVerification},
+        verify_status::{AcceptedVerification, JobState, JobStatus},
+    },
+    rpc::{InMemoryClient, RpcClient},
+};
+use ethers::{
+    providers::Provider,
+    types::{Address, Bytes, Chain, Transaction, TransactionReceipt, TxHash, H256},
+    utils::keccak256,
+};
 use serde_json::from_str;
+use std::{collections::HashMap, path::Path, process::Command, str::FromStr, sync::Arc};
+use tempfile::TempDir;
 
 #[tokio::test]
 async fn verify_counters() -> Result<(), Box<dyn std::error::Error>> {
@@ -386,6 +401,1040 @@ async fn verify_counters() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// Commits the sample project to a local git repository so the server can clone it without
+/// network access, and builds it to get the fixture bytecode. Returns the repository, its commit,
+/// and the creation and deployed code of `CounterBasic`.
+fn offline_counter_project() -> Result<(TempDir, String, Bytes, Bytes), Box<dyn std::error::Error>>
+{
+    offline_counter_project_with_comment("")
+}
+
+/// Copies the fixture project in `tests/fixtures/<name>` to a new temporary directory.
+fn copy_fixture(name: &str) -> Result<TempDir, Box<dyn std::error::Error>> {
+    let fixture_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name);
+    let dir = TempDir::new()?;
+    let status =
+        Command::new("cp").arg("-r").arg(fixture_dir.join(".")).arg(dir.path()).status()?;
+    assert!(status.success(), "Failed to copy fixture project {name}");
+    Ok(dir)
+}
+
+/// Like `offline_counter_project`, but appends `comment` to the source of `CounterBasic`. The
+/// comment changes the metadata hash, so projects with different comments have different code.
+fn offline_counter_project_with_comment(
+    comment: &str,
+) -> Result<(TempDir, String, Bytes, Bytes), Box<dyn std::error::Error>> {
+    let repo = copy_fixture("counter-project")?;
+    if !comment.is_empty() {
+        let source_path = repo.path().join("src/CounterBasic.sol");
+        let source = std::fs::read_to_string(&source_path)?;
+        std::fs::write(&source_path, format!("{source}// {comment}\n"))?;
+    }
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .args(["-c", "user.name=cove", "-c", "user.email=cove@example.com"])
+            .args(args)
+            .current_dir(repo.path())
+            .output()
+    };
+    git(&["init", "--quiet"])?;
+    git(&["add", "."])?;
+    git(&["commit", "--quiet", "-m", "Add fixture project"])?;
+    let repo_commit = String::from_utf8(git(&["rev-parse", "HEAD"])?.stdout)?.trim().to_string();
+
+    // Build output is gitignored so the committed project is unchanged.
+    let status = Command::new("forge").arg("build").current_dir(repo.path()).status()?;
+    assert!(status.success(), "Failed to build fixture project");
+    let artifact =
+        std::fs::read_to_string(repo.path().join("out/CounterBasic.sol/CounterBasic.json"))?;
+    let artifact: serde_json::Value = serde_json::from_str(&artifact)?;
+    let creation_code = Bytes::from_str(artifact["bytecode"]["object"].as_str().unwrap())?;
+    let deployed_code = Bytes::from_str(artifact["deployedBytecode"]["object"].as_str().unwrap())?;
+    Ok((repo, repo_commit, creation_code, deployed_code))
+}
+
+/// Returns a provider that serves a deployment of the given code on a single chain from memory.
+fn offline_provider(
+    contract_address: Address,
+    tx_hash: TxHash,
+    creation_code: &Bytes,
+    deployed_code: &Bytes,
+) -> Result<MultiChainProvider, Box<dyn std::error::Error>> {
+    let client = with_offline_deployment(
+        InMemoryClient::new(),
+        contract_address,
+        tx_hash,
+        creation_code,
+        deployed_code,
+    )?;
+    let provider = Arc::new(Provider::new(RpcClient::InMemory(client)));
+    Ok(MultiChainProvider::from_providers(HashMap::from([(Chain::Goerli, provider)])))
+}
+
+/// Adds the responses for a deployment of the given code to an in-memory client.
+fn with_offline_deployment(
+    client: InMemoryClient,
+    contract_address: Address,
+    tx_hash: TxHash,
+    creation_code: &Bytes,
+    deployed_code: &Bytes,
+) -> Result<InMemoryClient, Box<dyn std::error::Error>> {
+    let tx = Transaction {
+        hash: tx_hash,
+        block_number: Some(1.into()),
+        input: creation_code.clone(),
+        ..Default::default()
+    };
+    let receipt = TransactionReceipt {
+        transaction_hash: tx_hash,
+        contract_address: Some(contract_address),
+        ..Default::default()
+    };
+    Ok(client
+        .with_response("eth_getCode", (contract_address, "latest"), deployed_code)?
+        .with_response("eth_getTransactionByHash", [tx_hash], &tx)?
+        .with_response("eth_getTransactionReceipt", [tx_hash], &receipt)?)
+}
+
+#[tokio::test]
+async fn verify_counter_basic_offline() -> Result<(), Box<dyn std::error::Error>> {
+    let (repo, repo_commit, creation_code, deployed_code) = offline_counter_project()?;
+
+    // Serve the deployment from memory on a single chain.
+    let contract_address = Address::from_str("0x8d56e3e001132d84488DbacDbB01AfB8C3171242")?;
+    let tx_hash =
+        TxHash::from_str("0x59724cfbee93a0c10f7cbd312c1d159d62ea602003dd61a407a5cf842b4103d6")?;
+    let provider = offline_provider(contract_address, tx_hash, &creation_code, &deployed_code)?;
+
+    let app = common::spawn_app_with_provider(provider).await;
+    let body = json!({
+        "repoUrl": repo.path().to_str().unwrap(),
+        "repoCommit": repo_commit,
+        "contractAddress": contract_address,
+        "buildConfig": { "framework": "foundry", "buildHint": "default" },
+        "creationTxHashes": { "goerli": tx_hash },
+    });
+    let response = reqwest::Client::new()
+        .post(&format!(
+            "{}/verify?pretty=true&standard_json_input=true&include_disassembly=true",
+            app.address
+        ))
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .send()
+        .await?;
+    assert_eq!(200, response.status().as_u16());
+
+    // The response is pretty-printed when requested.
+    let text = response.text().await?;
+    assert!(text.starts_with("{\n  \"verification_id\""), "{}", &text[..20.min(text.len())]);
+    let verification_result: SuccessfulVerification =
+        from_str(&text).expect("Failed to deserialize SuccessfulVerification");
+    let goerli_match = &verification_result.matches[&Chain::Goerli];
+    assert_eq!(goerli_match.deployed_code_match_type, MatchType::Full);
+    assert_eq!(goerli_match.creation_code_match_type, MatchType::Full);
+    let creation_match_status = verification_result.creation_match_status[&Chain::Goerli];
+    assert_eq!(creation_match_status, CreationMatchStatus::Matched);
+    let goerli_deployment = &verification_result.deployments[&Chain::Goerli];
+    assert_eq!(goerli_deployment.creation_tx_hash, Some(tx_hash));
+    assert_eq!(goerli_deployment.creation_code, Some(creation_code.clone()));
+    assert_eq!(goerli_deployment.runtime_code, deployed_code);
+    assert_eq!(goerli_deployment.constructor_args, Some(vec![]));
+    let explorer_links = &verification_result.explorer_urls[&Chain::Goerli];
+    assert_eq!(
+        explorer_links.address,
+        "https://goerli.etherscan.io/address/0x8d56e3e001132d84488dbacdbb01afb8c3171242"
+    );
+    assert_eq!(
+        explorer_links.creation_tx.as_deref(),
+        Some(format!("https://goerli.etherscan.io/tx/{:?}", tx_hash).as_str())
+    );
+    assert_eq!(goerli_deployment.runtime_code_hash, H256::from(keccak256(&deployed_code)));
+    assert!(verification_result.toolchain.solc_version.starts_with("0.8.19+commit."));
+    let onchain_metadata = goerli_deployment.metadata.as_ref().expect("Missing metadata");
+    assert_eq!(onchain_metadata.solc_version.as_deref(), Some("0.8.19"));
+    assert!(verification_result.toolchain.forge_version.is_some());
+    assert_eq!(verification_result.deployed_on, vec![Chain::Goerli]);
+    assert_eq!(verification_result.chains_matched, 1);
+    assert!(verification_result.artifacts_compiled >= 1);
+    let goerli_match = &verification_result.matches[&Chain::Goerli];
+    assert_eq!(goerli_match.metadata_hash_type, Some(MetadataHashType::Ipfs));
+    assert!(!goerli_match.deployed_code_padding_normalized);
+    assert_eq!(verification_result.metadata_hash_mismatch, None);
+    assert_eq!(goerli_deployment.creation_code_source, Some(CreationCodeSource::ProvidedTxHash));
+    assert_eq!(verification_result.ipfs_cids.len(), verification_result.sources.len());
+    let function_signatures: Vec<&str> = verification_result
+        .abi_summary
+        .functions
+        .iter()
+        .map(|function| function.signature.as_str())
+        .collect();
+    assert_eq!(function_signatures, vec!["increment()", "number()", "setNumber(uint256)"]);
+    let input = verification_result.standard_json_input.expect("Missing Standard JSON input");
+    assert_eq!(input["language"], "Solidity");
+    assert!(input["sources"]["src/CounterBasic.sol"]["content"].is_string());
+    let disassembly = goerli_deployment.disassembly.as_ref().expect("Missing disassembly");
+    assert!(disassembly.contains("PUSH1"));
+    assert!(verification_result.bundle_cid.starts_with("bafybei"));
+    assert_eq!(goerli_deployment.creation_code_hash, Some(H256::from(keccak256(&creation_code))));
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_hardhat_project() -> Result<(), Box<dyn std::error::Error>> {
+    // The deployment is served from memory, but Hardhat and solc are downloaded by npm.
+    let repo = copy_fixture("hardhat-project")?;
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .args(["-c", "user.name=cove", "-c", "user.email=cove@example.com"])
+            .args(args)
+            .current_dir(repo.path())
+            .output()
+    };
+    git(&["init", "--quiet"])?;
+    git(&["add", "."])?;
+    git(&["commit", "--quiet", "-m", "Add fixture project"])?;
+    let repo_commit = String::from_utf8(git(&["rev-parse", "HEAD"])?.stdout)?.trim().to_string();
+
+    // Dependencies and build output are gitignored so the committed project is unchanged.
+    let status = Command::new("npm")
+        .args(["install", "--ignore-scripts", "--no-audit"])
+        .current_dir(repo.path())
+        .status()?;
+    assert!(status.success(), "Failed to install fixture project dependencies");
+    let status = Command::new("npx")
+        .args(["hardhat", "compile", "--quiet"])
+        .current_dir(repo.path())
+        .status()?;
+    assert!(status.success(), "Failed to build fixture project");
+    let artifact = std::fs::read_to_string(
+        repo.path().join("artifacts/contracts/CounterBasic.sol/CounterBasic.json"),
+    )?;
+    let artifact: serde_json::Value = serde_json::from_str(&artifact)?;
+    let creation_code = Bytes::from_str(artifact["bytecode"].as_str().unwrap())?;
+    let deployed_code = Bytes::from_str(artifact["deployedBytecode"].as_str().unwrap())?;
+
+    let contract_address = Address::from_str("0x8d56e3e001132d84488DbacDbB01AfB8C3171242")?;
+    let tx_hash =
+        TxHash::from_str("0x59724cfbee93a0c10f7cbd312c1d159d62ea602003dd61a407a5cf842b4103d6")?;
+    let provider = offline_provider(contract_address, tx_hash, &creation_code, &deployed_code)?;
+    let app = common::spawn_app_with_provider(provider).await;
+    let body = json!({
+        "repoUrl": repo.path().to_str().unwrap(),
+        "repoCommit": repo_commit,
+        "contractAddress": contract_address,
+        "buildConfig": { "framework": "hardhat" },
+        "creationTxHashes": { "goerli": tx_hash },
+    });
+    let response = reqwest::Client::new()
+        .post(&format!("{}/verify", app.address))
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .send()
+        .await?;
+    assert_eq!(200, response.status().as_u16());
+
+    let verification_result: SuccessfulVerification =
+        from_str(&response.text().await?).expect("Failed to deserialize SuccessfulVerification");
+    let goerli_match = &verification_result.matches[&Chain::Goerli];
+    assert_eq!(goerli_match.deployed_code_match_type, MatchType::Full);
+    assert_eq!(goerli_match.creation_code_match_type, MatchType::Full);
+    assert_eq!(verification_result.sources[0].path, Path::new("contracts/CounterBasic.sol"));
+    assert!(verification_result.toolchain.solc_version.starts_with("0.8.19+commit."));
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_creation_match_status_offline() -> Result<(), Box<dyn std::error::Error>> {
+    let (repo, repo_commit, _, deployed_code) = offline_counter_project()?;
+
+    // The creation transaction deployed different code, so only the deployed code matches.
+    let contract_address = Address::from_str("0x8d56e3e001132d84488DbacDbB01AfB8C3171242")?;
+    let tx_hash =
+        TxHash::from_str("0x59724cfbee93a0c10f7cbd312c1d159d62ea602003dd61a407a5cf842b4103d6")?;
+    let other_creation_code = Bytes::from(vec![0xfe; 32]);
+    let provider =
+        offline_provider(contract_address, tx_hash, &other_creation_code, &deployed_code)?;
+    let app = common::spawn_app_with_provider(provider).await;
+    let body = json!({
+        "repoUrl": repo.path().to_str().unwrap(),
+        "repoCommit": repo_commit,
+        "contractAddress": contract_address,
+        "buildConfig": { "framework": "foundry", "buildHint": "default" },
+        "creationTxHashes": { "goerli": tx_hash },
+    });
+    let response = reqwest::Client::new()
+        .post(&format!("{}/verify", app.address))
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .send()
+        .await?;
+    assert_eq!(200, response.status().as_u16());
+    let verification_result: SuccessfulVerification =
+        from_str(&response.text().await?).expect("Failed to deserialize SuccessfulVerification");
+    let goerli_match = &verification_result.matches[&Chain::Goerli];
+    assert_eq!(goerli_match.deployed_code_match_type, MatchType::Full);
+    assert_eq!(goerli_match.creation_code_match_type, MatchType::None);
+    let creation_match_status = verification_result.creation_match_status[&Chain::Goerli];
+    assert_eq!(creation_match_status, CreationMatchStatus::NotFound);
+
+    // Creation code isn't compared when only deployed code is verified.
+    let mut deployed_only_body = body;
+    deployed_only_body["verifyMode"] = json!("deployedOnly");
+    let response = reqwest::Client::new()
+        .post(&format!("{}/verify", app.address))
+        .header("Content-Type", "application/json")
+        .body(deployed_only_body.to_string())
+        .send()
+        .await?;
+    assert_eq!(200, response.status().as_u16());
+    let verification_result: SuccessfulVerification =
+        from_str(&response.text().await?).expect("Failed to deserialize SuccessfulVerification");
+    let creation_match_status = verification_result.creation_match_status[&Chain::Goerli];
+    assert_eq!(creation_match_status, CreationMatchStatus::NotAttempted);
+    assert_eq!(verification_result.deployments[&Chain::Goerli].creation_tx_hash, None);
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_branch_ref_offline() -> Result<(), Box<dyn std::error::Error>> {
+    let (repo, repo_commit, creation_code, deployed_code) = offline_counter_project()?;
+    let status =
+        Command::new("git").args(["branch", "release"]).current_dir(repo.path()).status()?;
+    assert!(status.success(), "Failed to create branch");
+
+    let contract_address = Address::from_str("0x8d56e3e001132d84488DbacDbB01AfB8C3171242")?;
+    let tx_hash =
+        TxHash::from_str("0x59724cfbee93a0c10f7cbd312c1d159d62ea602003dd61a407a5cf842b4103d6")?;
+    let provider = offline_provider(contract_address, tx_hash, &creation_code, &deployed_code)?;
+    let app = common::spawn_app_with_provider(provider).await;
+    let body = json!({
+        "repoUrl": repo.path().to_str().unwrap(),
+        "repoRef": "release",
+        "contractAddress": contract_address,
+        "buildConfig": { "framework": "foundry", "buildHint": "default" },
+        "creationTxHashes": { "goerli": tx_hash },
+    });
+    let response = reqwest::Client::new()
+        .post(&format!("{}/verify", app.address))
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .send()
+        .await?;
+    assert_eq!(200, response.status().as_u16());
+
+    // The branch is returned along with the commit it resolved to.
+    let verification_result: SuccessfulVerification =
+        from_str(&response.text().await?).expect("Failed to deserialize SuccessfulVerification");
+    assert_eq!(verification_result.repo_commit, repo_commit);
+    assert_eq!(verification_result.repo_ref.as_deref(), Some("release"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_then_fetch_contract_offline() -> Result<(), Box<dyn std::error::Error>> {
+    let (repo, repo_commit, creation_code, deployed_code) = offline_counter_project()?;
+    let contract_address = Address::from_str("0x8d56e3e001132d84488DbacDbB01AfB8C3171242")?;
+    let tx_hash =
+        TxHash::from_str("0x59724cfbee93a0c10f7cbd312c1d159d62ea602003dd61a407a5cf842b4103d6")?;
+    let provider = offline_provider(contract_address, tx_hash, &creation_code, &deployed_code)?;
+    let app = common::spawn_app_with_provider(provider).await;
+    let client = reqwest::Client::new();
+    let body = json!({
+        "repoUrl": repo.path().to_str().unwrap(),
+        "repoCommit": repo_commit,
+        "contractAddress": contract_address,
+        "buildConfig": { "framework": "foundry", "buildHint": "default" },
+        "creationTxHashes": { "goerli": tx_hash },
+    });
+    let response = client
+        .post(&format!("{}/verify", app.address))
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .send()
+        .await?;
+    assert_eq!(200, response.status().as_u16());
+    let verification: SuccessfulVerification = response.json().await?;
+
+    // The stored verification is returned instead of decompiling the contract.
+    let url = format!("{}/contract?chain_id=5&address={:?}", app.address, contract_address);
+    let response = client.get(&url).send().await?;
+    assert_eq!(200, response.status().as_u16());
+    let contract: serde_json::Value = response.json().await?;
+    assert_eq!(contract["verified"], true);
+    assert_eq!(contract["verification"]["verification_id"], verification.verification_id);
+    assert_eq!(contract["verification"]["sources"][0]["path"], "src/CounterBasic.sol");
+
+    // Verifications that only return some fields skip assembling the full verification, so they
+    // aren't stored.
+    let app = common::spawn_app_with_provider(offline_provider(
+        contract_address,
+        tx_hash,
+        &creation_code,
+        &deployed_code,
+    )?)
+    .await;
+    let response = client
+        .post(&format!("{}/verify?fields=abi", app.address))
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .send()
+        .await?;
+    assert_eq!(200, response.status().as_u16());
+    let lightweight: serde_json::Value = response.json().await?;
+    assert!(lightweight["abi"].is_array());
+    assert!(lightweight.get("sources").is_none());
+    let url = format!("{}/contract?chain_id=5&address={:?}", app.address, contract_address);
+    // The contract is decompiled instead, which may fail without a real chain.
+    let contract: serde_json::Value =
+        client.get(&url).send().await?.json().await.unwrap_or_default();
+    assert!(contract.get("verification").is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_counter_partial_match_offline() -> Result<(), Box<dyn std::error::Error>> {
+    let (repo, repo_commit, creation_code, deployed_code) = offline_counter_project()?;
+
+    // Deploy code that differs from the build only in the metadata hash, as if the contract was
+    // deployed from sources with different comments. The first byte of the IPFS hash digest is
+    // changed, which keeps the CBOR encoding valid. The runtime code's metadata is also embedded
+    // in the creation code, so it's replaced there too.
+    let metadata_start = parse_metadata(&deployed_code).start_index.unwrap();
+    let metadata = deployed_code[metadata_start..].to_vec();
+    let mut onchain_metadata = metadata.clone();
+    onchain_metadata[10] ^= 0xff;
+    let replace_metadata = |code: &Bytes| -> Bytes {
+        let start = code.windows(metadata.len()).position(|window| window == metadata).unwrap();
+        [&code[..start], &onchain_metadata, &code[start + metadata.len()..]].concat().into()
+    };
+    let onchain_creation_code = replace_metadata(&creation_code);
+    let onchain_deployed_code = replace_metadata(&deployed_code);
+
+    let contract_address = Address::from_str("0x8d56e3e001132d84488DbacDbB01AfB8C3171242")?;
+    let tx_hash =
+        TxHash::from_str("0x59724cfbee93a0c10f7cbd312c1d159d62ea602003dd61a407a5cf842b4103d6")?;
+    let provider = offline_provider(
+        contract_address,
+        tx_hash,
+        &onchain_creation_code,
+        &onchain_deployed_code,
+    )?;
+
+    let app = common::spawn_app_with_provider(provider).await;
+    let body = json!({
+        "repoUrl": repo.path().to_str().unwrap(),
+        "repoCommit": repo_commit,
+        "contractAddress": contract_address,
+        "buildConfig": { "framework": "foundry", "buildHint": "default" },
+        "creationTxHashes": { "goerli": tx_hash },
+    });
+    let response = reqwest::Client::new()
+        .post(&format!("{}/verify", app.address))
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .send()
+        .await?;
+    assert_eq!(200, response.status().as_u16());
+
+    // The leading code matches but the metadata hash does not, so both matches are partial.
+    let verification_result: SuccessfulVerification =
+        from_str(&response.text().await?).expect("Failed to deserialize SuccessfulVerification");
+    let goerli_match = &verification_result.matches[&Chain::Goerli];
+    assert_eq!(goerli_match.deployed_code_match_type, MatchType::Partial);
+    assert_eq!(goerli_match.creation_code_match_type, MatchType::Partial);
+    let goerli_deployment = &verification_result.deployments[&Chain::Goerli];
+    assert_eq!(goerli_deployment.runtime_code, onchain_deployed_code);
+    assert_eq!(goerli_deployment.creation_code, Some(onchain_creation_code));
+    assert_eq!(verification_result.standard_json_input, None);
+
+    // Every required chain must match, and partial matches count.
+    let mut required_body = body.clone();
+    required_body["requiredChains"] = json!(["goerli"]);
+    let response = reqwest::Client::new()
+        .post(&format!("{}/verify", app.address))
+        .header("Content-Type", "application/json")
+        .body(required_body.to_string())
+        .send()
+        .await?;
+    assert_eq!(200, response.status().as_u16());
+    required_body["requiredChains"] = json!(["goerli", "mainnet"]);
+    let response = reqwest::Client::new()
+        .post(&format!("{}/verify", app.address))
+        .header("Content-Type", "application/json")
+        .body(required_body.to_string())
+        .send()
+        .await?;
+    assert_eq!(400, response.status().as_u16());
+    let text = response.text().await?;
+    assert!(text.contains("goerli: matched; mainnet: chain is not supported"), "{text}");
+
+    // Strict verification rejects the partial matches.
+    let mut strict_body = body;
+    strict_body["strict"] = json!(true);
+    let response = reqwest::Client::new()
+        .post(&format!("{}/verify", app.address))
+        .header("Content-Type", "application/json")
+        .body(strict_body.to_string())
+        .send()
+        .await?;
+    assert_eq!(400, response.status().as_u16());
+    let text = response.text().await?;
+    assert!(text.contains("goerli: ") && text.contains("only partially matched"), "{text}");
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_project_with_submodules_offline() -> Result<(), Box<dyn std::error::Error>> {
+    let git = |dir: &Path, args: &[&str]| {
+        Command::new("git")
+            .args(["-c", "user.name=cove", "-c", "user.email=cove@example.com"])
+            .args(["-c", "protocol.file.allow=always"])
+            .args(args)
+            .current_dir(dir)
+            .output()
+    };
+
+    // Commit a library that the project vendors as a submodule.
+    let library = TempDir::new()?;
+    std::fs::create_dir(library.path().join("src"))?;
+    let counter_base = "// SPDX-License-Identifier: UNLICENSED
+pragma solidity ^0.8.13;
+
+abstract contract CounterBase {
+  uint256 public number;
+
+  function setNumber(uint256 newNumber) public {
+    number = newNumber;
+  }
+}
+";
+    std::fs::write(library.path().join("src/CounterBase.sol"), counter_base)?;
+    git(library.path(), &["init", "--quiet"])?;
+    git(library.path(), &["add", "."])?;
+    git(library.path(), &["commit", "--quiet", "-m", "Add CounterBase"])?;
+
+    // Commit the sample project with its counter inheriting from the library.
+    let repo = copy_fixture("counter-project")?;
+    let counter = "// SPDX-License-Identifier: UNLICENSED
+pragma solidity ^0.8.13;
+
+import {CounterBase} from \"counter-base/CounterBase.sol\";
+
+contract CounterBasic is CounterBase {
+  function increment() public {
+    number++;
+  }
+}
+";
+    std::fs::write(repo.path().join("src/CounterBasic.sol"), counter)?;
+    std::fs::write(repo.path().join("remappings.txt"), "counter-base/=lib/counter-base/src/\n")?;
+    git(repo.path(), &["init", "--quiet"])?;
+    let library_path = library.path().to_str().unwrap();
+    git(repo.path(), &["submodule", "add", "--quiet", library_path, "lib/counter-base"])?;
+    git(repo.path(), &["add", "."])?;
+    git(repo.path(), &["commit", "--quiet", "-m", "Add fixture project"])?;
+    let repo_commit =
+        String::from_utf8(git(repo.path(), &["rev-parse", "HEAD"])?.stdout)?.trim().to_string();
+
+    // Remove the library's source in a later commit, so the project only builds if the submodule
+    // is checked out at the commit the project references rather than the library's latest one.
+    git(library.path(), &["rm", "--quiet", "src/CounterBase.sol"])?;
+    git(library.path(), &["commit", "--quiet", "-m", "Remove CounterBase"])?;
+
+    // Build output is gitignored so the committed project is unchanged.
+    let status = Command::new("forge").arg("build").current_dir(repo.path()).status()?;
+    assert!(status.success(), "Failed to build fixture project");
+    let artifact =
+        std::fs::read_to_string(repo.path().join("out/CounterBasic.sol/CounterBasic.json"))?;
+    let artifact: serde_json::Value = serde_json::from_str(&artifact)?;
+    let creation_code = Bytes::from_str(artifact["bytecode"]["object"].as_str().unwrap())?;
+    let deployed_code = Bytes::from_str(artifact["deployedBytecode"]["object"].as_str().unwrap())?;
+
+    let contract_address = Address::from_str("0x8d56e3e001132d84488DbacDbB01AfB8C3171242")?;
+    let tx_hash =
+        TxHash::from_str("0x59724cfbee93a0c10f7cbd312c1d159d62ea602003dd61a407a5cf842b4103d6")?;
+    let provider = offline_provider(contract_address, tx_hash, &creation_code, &deployed_code)?;
+    let app = common::spawn_app_with_provider(provider).await;
+    let body = json!({
+        "repoUrl": repo.path().to_str().unwrap(),
+        "repoCommit": repo_commit,
+        "contractAddress": contract_address,
+        "buildConfig": { "framework": "foundry", "buildHint": "default" },
+        "creationTxHashes": { "goerli": tx_hash },
+    });
+    let response = reqwest::Client::new()
+        .post(&format!("{}/verify", app.address))
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .send()
+        .await?;
+    assert_eq!(200, response.status().as_u16());
+
+    // The library's source is returned along with the project's.
+    let verification_result: SuccessfulVerification =
+        from_str(&response.text().await?).expect("Failed to deserialize SuccessfulVerification");
+    let goerli_match = &verification_result.matches[&Chain::Goerli];
+    assert_eq!(goerli_match.deployed_code_match_type, MatchType::Full);
+    assert_eq!(goerli_match.creation_code_match_type, MatchType::Full);
+    let paths: Vec<&Path> =
+        verification_result.sources.iter().map(|source| source.path.as_path()).collect();
+    assert!(paths.contains(&Path::new("lib/counter-base/src/CounterBase.sol")), "{paths:?}");
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_concurrent_requests_offline() -> Result<(), Box<dyn std::error::Error>> {
+    // Two projects whose code differs, deployed at different addresses on the same chain.
+    let deployments = [
+        ("first", "0x8d56e3e001132d84488DbacDbB01AfB8C3171242", 0x01),
+        ("second", "0x0d8c4d57b2a6bb7d3a9d6c1e5b7a0f3c4e2d1b0a", 0x02),
+    ]
+    .into_iter()
+    .map(|(comment, address, tx_byte)| {
+        let (repo, repo_commit, creation_code, deployed_code) =
+            offline_counter_project_with_comment(comment)?;
+        let address = Address::from_str(address)?;
+        Ok((
+            comment,
+            repo,
+            repo_commit,
+            address,
+            TxHash::repeat_byte(tx_byte),
+            creation_code,
+            deployed_code,
+        ))
+    })
+    .collect::<Result<Vec<_>, Box<dyn std::error::Error>>>()?;
+    assert_ne!(deployments[0].6, deployments[1].6);
+
+    let mut client = InMemoryClient::new();
+    for (_, _, _, address, tx_hash, creation_code, deployed_code) in &deployments {
+        client = with_offline_deployment(client, *address, *tx_hash, creation_code, deployed_code)?;
+    }
+    let provider = Arc::new(Provider::new(RpcClient::InMemory(client)));
+    let provider = MultiChainProvider::from_providers(HashMap::from([(Chain::Goerli, provider)]));
+    let app = common::spawn_app_with_provider(provider).await;
+
+    // Send both verifications at once, so their clones and builds overlap.
+    let requests = deployments.iter().map(|(_, repo, repo_commit, address, tx_hash, ..)| {
+        let body = json!({
+            "repoUrl": repo.path().to_str().unwrap(),
+            "repoCommit": repo_commit,
+            "contractAddress": address,
+            "buildConfig": { "framework": "foundry", "buildHint": "default" },
+            "creationTxHashes": { "goerli": tx_hash },
+        });
+        reqwest::Client::new()
+            .post(&format!("{}/verify", app.address))
+            .header("Content-Type", "application/json")
+            .body(body.to_string())
+            .send()
+    });
+    let responses = futures::future::join_all(requests).await;
+
+    // Each response describes its own contract, built from its own sources.
+    for (response, deployment) in responses.into_iter().zip(&deployments) {
+        let (comment, _, repo_commit, address, _, creation_code, deployed_code) = deployment;
+        let response = response?;
+        assert_eq!(200, response.status().as_u16(), "Verification of {comment} failed");
+        let text = response.text().await?;
+        let verification_result: SuccessfulVerification = from_str(&text)?;
+        assert_eq!(verification_result.contract_address, *address);
+        assert_eq!(&verification_result.repo_commit, repo_commit);
+        let goerli_deployment = &verification_result.deployments[&Chain::Goerli];
+        assert_eq!(goerli_deployment.runtime_code, *deployed_code);
+        assert_eq!(goerli_deployment.creation_code.as_ref(), Some(creation_code));
+        let goerli_match = &verification_result.matches[&Chain::Goerli];
+        assert_eq!(goerli_match.deployed_code_match_type, MatchType::Full);
+        assert_eq!(goerli_match.creation_code_match_type, MatchType::Full);
+        let source = &serde_json::from_str::<serde_json::Value>(&text)?["sources"][0];
+        assert_eq!(source["path"], "src/CounterBasic.sol");
+        assert_eq!(source["is_root"], true);
+        assert!(source["content"].as_str().unwrap().ends_with(&format!("// {comment}\n")));
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_unsupported_framework() -> Result<(), Box<dyn std::error::Error>> {
+    let app = common::spawn_app().await;
+    let body = json!({
+        "repoUrl": "https://github.com/ScopeLift/cove-test-repo",
+        "repoCommit": "b268862cf1ccf495d6dc20a86c41940dfb386d9b",
+        "contractAddress": "0x8d56e3e001132d84488DbacDbB01AfB8C3171242",
+        "buildConfig": { "framework": "truffle", "buildHint": "default" },
+    });
+    let response = reqwest::Client::new()
+        .post(&format!("{}/verify", app.address))
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .send()
+        .await?;
+
+    assert_eq!(501, response.status().as_u16());
+    assert!(response.text().await?.contains("Supported frameworks: foundry"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_rejects_malformed_remappings() -> Result<(), Box<dyn std::error::Error>> {
+    let app = common::spawn_app().await;
+    let body = json!({
+        "repoUrl": "https://github.com/ScopeLift/cove-test-repo",
+        "repoCommit": "b268862cf1ccf495d6dc20a86c41940dfb386d9b",
+        "contractAddress": "0x8d56e3e001132d84488DbacDbB01AfB8C3171242",
+        "buildConfig": {
+            "framework": "foundry",
+            "buildHint": "default",
+            "remappings": ["solmate/=lib/solmate/src/", "forge-std"],
+        },
+    });
+    let response = reqwest::Client::new()
+        .post(&format!("{}/verify", app.address))
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .send()
+        .await?;
+
+    assert_eq!(400, response.status().as_u16());
+    assert!(response.text().await?.contains("Invalid remapping 'forge-std'"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_rejects_remappings_for_ape() -> Result<(), Box<dyn std::error::Error>> {
+    let app = common::spawn_app().await;
+    let body = json!({
+        "repoUrl": "https://github.com/ScopeLift/cove-test-repo",
+        "repoCommit": "b268862cf1ccf495d6dc20a86c41940dfb386d9b",
+        "contractAddress": "0x8d56e3e001132d84488DbacDbB01AfB8C3171242",
+        "buildConfig": { "framework": "ape", "remappings": ["solmate/=lib/solmate/src/"] },
+    });
+    let response = reqwest::Client::new()
+        .post(&format!("{}/verify", app.address))
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .send()
+        .await?;
+
+    assert_eq!(400, response.status().as_u16());
+    assert!(response.text().await?.contains("Remappings are only supported for Foundry projects"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_rejects_symlinks_outside_repo() -> Result<(), Box<dyn std::error::Error>> {
+    // Commit a project whose source is a symlink to a file on the host.
+    let repo = TempDir::new()?;
+    std::fs::write(repo.path().join("foundry.toml"), "[profile.default]")?;
+    std::fs::create_dir(repo.path().join("src"))?;
+    std::os::unix::fs::symlink("/etc/hostname", repo.path().join("src/Leak.sol"))?;
+    std::os::unix::fs::symlink("../foundry.toml", repo.path().join("src/Config.toml"))?;
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .args(["-c", "user.name=cove", "-c", "user.email=cove@example.com"])
+            .args(args)
+            .current_dir(repo.path())
+            .output()
+    };
+    git(&["init", "--quiet"])?;
+    git(&["add", "."])?;
+    git(&["commit", "--quiet", "-m", "Add symlinks"])?;
+    let repo_commit = String::from_utf8(git(&["rev-parse", "HEAD"])?.stdout)?.trim().to_string();
+
+    let provider = Arc::new(Provider::new(RpcClient::InMemory(InMemoryClient::new())));
+    let provider = MultiChainProvider::from_providers(HashMap::from([(Chain::Goerli, provider)]));
+    let app = common::spawn_app_with_provider(provider).await;
+    let body = json!({
+        "repoUrl": repo.path().to_str().unwrap(),
+        "repoCommit": repo_commit,
+        "contractAddress": "0x8d56e3e001132d84488DbacDbB01AfB8C3171242",
+        "buildConfig": { "framework": "foundry", "buildHint": "default" },
+    });
+    let response = reqwest::Client::new()
+        .post(&format!("{}/verify", app.address))
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .send()
+        .await?;
+
+    // Only the symlink that leaves the repository is rejected.
+    assert_eq!(400, response.status().as_u16());
+    let text = response.text().await?;
+    assert!(text.ends_with("point outside of the repository: src/Leak.sol"), "{text}");
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_rejects_creation_tx_hashes_for_unconfigured_chains(
+) -> Result<(), Box<dyn std::error::Error>> {
+    let provider = Arc::new(Provider::new(RpcClient::InMemory(InMemoryClient::new())));
+    let provider = MultiChainProvider::from_providers(HashMap::from([(Chain::Goerli, provider)]));
+    let app = common::spawn_app_with_provider(provider).await;
+    let tx_hash = "0x4b1ad3bd4b9cc9d1e8ec28e52ab6d4a2c0c5b7c2c4bb1d5dfbd4ec4c0d8ad3a1";
+    let body = json!({
+        "repoUrl": "https://github.com/ScopeLift/cove-test-repo",
+        "repoCommit": "b268862cf1ccf495d6dc20a86c41940dfb386d9b",
+        "contractAddress": "0x8d56e3e001132d84488DbacDbB01AfB8C3171242",
+        "buildConfig": { "framework": "foundry", "buildHint": "default" },
+        "creationTxHashes": { "goerli": tx_hash, "polygon": tx_hash, "mainnet": tx_hash },
+    });
+    let response = reqwest::Client::new()
+        .post(&format!("{}/verify", app.address))
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .send()
+        .await?;
+
+    assert_eq!(400, response.status().as_u16());
+    let text = response.text().await?;
+    assert!(text.ends_with("without a configured RPC: mainnet, polygon"), "{text}");
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_rejects_unsupported_chains() -> Result<(), Box<dyn std::error::Error>> {
+    let provider = Arc::new(Provider::new(RpcClient::InMemory(InMemoryClient::new())));
+    let provider = MultiChainProvider::from_providers(HashMap::from([(Chain::Goerli, provider)]));
+    let app = common::spawn_app_with_provider(provider).await;
+    let client = reqwest::Client::new();
+    let body = |chains: serde_json::Value| {
+        json!({
+            "repoUrl": "https://github.com/ScopeLift/cove-test-repo",
+            "repoCommit": "b268862cf1ccf495d6dc20a86c41940dfb386d9b",
+            "contractAddress": "0x8d56e3e001132d84488DbacDbB01AfB8C3171242",
+            "buildConfig": { "framework": "foundry", "buildHint": "default" },
+            "chains": chains,
+        })
+    };
+
+    for (chains, expected) in [
+        (json!(["goerli", "optimism"]), "Chain optimism is not configured"),
+        (json!([]), "At least one chain must be given"),
+    ] {
+        let response = client
+            .post(&format!("{}/verify", app.address))
+            .header("Content-Type", "application/json")
+            .body(body(chains).to_string())
+            .send()
+            .await?;
+        assert_eq!(400, response.status().as_u16());
+        assert_eq!(response.text().await?, expected);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_uploaded_sources_offline() -> Result<(), Box<dyn std::error::Error>> {
+    let (repo, _, creation_code, deployed_code) = offline_counter_project()?;
+    let contract_address = Address::from_str("0x8d56e3e001132d84488DbacDbB01AfB8C3171242")?;
+    let tx_hash =
+        TxHash::from_str("0x59724cfbee93a0c10f7cbd312c1d159d62ea602003dd61a407a5cf842b4103d6")?;
+    let provider = offline_provider(contract_address, tx_hash, &creation_code, &deployed_code)?;
+    let app = common::spawn_app_with_provider(provider).await;
+
+    // The project's files are uploaded instead of cloned.
+    let sources: HashMap<&str, String> = ["foundry.toml", "src/CounterBasic.sol"]
+        .into_iter()
+        .map(|path| Ok((path, std::fs::read_to_string(repo.path().join(path))?)))
+        .collect::<Result<_, std::io::Error>>()?;
+    let body = json!({
+        "sources": sources,
+        "contractAddress": contract_address,
+        "buildConfig": { "framework": "foundry", "buildHint": "default" },
+        "creationTxHashes": { "goerli": tx_hash },
+    });
+    let response = reqwest::Client::new()
+        .post(&format!("{}/verify", app.address))
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .send()
+        .await?;
+    assert_eq!(200, response.status().as_u16());
+    let verification_result: SuccessfulVerification = response.json().await?;
+    assert_eq!(verification_result.repo_url, "");
+    let goerli_match = &verification_result.matches[&Chain::Goerli];
+    assert_eq!(goerli_match.deployed_code_match_type, MatchType::Full);
+    assert_eq!(goerli_match.creation_code_match_type, MatchType::Full);
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_rejects_invalid_uploaded_sources() -> Result<(), Box<dyn std::error::Error>> {
+    let provider = Arc::new(Provider::new(RpcClient::InMemory(InMemoryClient::new())));
+    let provider = MultiChainProvider::from_providers(HashMap::from([(Chain::Goerli, provider)]));
+    let app = common::spawn_app_with_provider(provider).await;
+    let client = reqwest::Client::new();
+    let body = |inputs: serde_json::Value| {
+        let mut body = json!({
+            "contractAddress": "0x8d56e3e001132d84488DbacDbB01AfB8C3171242",
+            "buildConfig": { "framework": "foundry", "buildHint": "default" },
+        });
+        body.as_object_mut().unwrap().extend(inputs.as_object().unwrap().clone());
+        body
+    };
+
+    for (inputs, expected) in [
+        (
+            json!({}),
+            "`repoUrl` and `repoCommit` or `repoRef` are required unless `sources` are given.",
+        ),
+        (
+            json!({ "repoUrl": "https://github.com/ScopeLift/cove-test-repo", "sources": {} }),
+            "`repoUrl`, `repoCommit`, and `repoRef` must be omitted when `sources` are given.",
+        ),
+        (
+            json!({ "repoRef": "main", "sources": {} }),
+            "`repoUrl`, `repoCommit`, and `repoRef` must be omitted when `sources` are given.",
+        ),
+        (
+            json!({
+                "repoUrl": "https://github.com/ScopeLift/cove-test-repo",
+                "repoCommit": "b268862cf1ccf495d6dc20a86c41940dfb386d9b",
+                "repoRef": "main",
+            }),
+            "Only one of `repoCommit` and `repoRef` can be given.",
+        ),
+        (json!({ "sources": {} }), "Failed to write sources: No sources were given"),
+        (
+            json!({ "sources": { "../foundry.toml": "" } }),
+            "Failed to write sources: Invalid source path ../foundry.toml",
+        ),
+    ] {
+        let response = client
+            .post(&format!("{}/verify", app.address))
+            .header("Content-Type", "application/json")
+            .body(body(inputs).to_string())
+            .send()
+            .await?;
+        assert_eq!(400, response.status().as_u16());
+        assert_eq!(response.text().await?, expected);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_creation_only_requires_creation_inputs() -> Result<(), Box<dyn std::error::Error>> {
+    let app = common::spawn_app().await;
+    let body = json!({
+        "repoUrl": "https://github.com/ScopeLift/cove-test-repo",
+        "repoCommit": "b268862cf1ccf495d6dc20a86c41940dfb386d9b",
+        "contractAddress": "0x8d56e3e001132d84488DbacDbB01AfB8C3171242",
+        "buildConfig": { "framework": "foundry", "buildHint": "default" },
+        "verifyMode": "creationOnly",
+    });
+    let response = reqwest::Client::new()
+        .post(&format!("{}/verify", app.address))
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .send()
+        .await?;
+
+    assert_eq!(400, response.status().as_u16());
+    assert!(response.text().await?.contains("requires `creationTxHashes` or `factoryDeployment`"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_stream_reports_errors() -> Result<(), Box<dyn std::error::Error>> {
+    let app = common::spawn_app().await;
+    let body = json!({
+        "repoUrl": "https://github.com/ScopeLift/cove-test-repo",
+        "repoCommit": "b268862cf1ccf495d6dc20a86c41940dfb386d9b",
+        "contractAddress": "0x8d56e3e001132d84488DbacDbB01AfB8C3171242",
+        "buildConfig": { "framework": "truffle", "buildHint": "default" },
+    });
+    let response = reqwest::Client::new()
+        .post(&format!("{}/verify/stream", app.address))
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .send()
+        .await?;
+
+    // Errors are sent as an event in the stream, since the stream has already started.
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(response.headers()["content-type"], "text/event-stream");
+    let events = response.text().await?;
+    assert!(events.starts_with("event:error\n"));
+    assert!(events.contains("Supported frameworks: foundry"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_stream_reports_milestones_offline() -> Result<(), Box<dyn std::error::Error>> {
+    let (repo, repo_commit, creation_code, deployed_code) = offline_counter_project()?;
+    let contract_address = Address::from_str("0x8d56e3e001132d84488DbacDbB01AfB8C3171242")?;
+    let tx_hash =
+        TxHash::from_str("0x59724cfbee93a0c10f7cbd312c1d159d62ea602003dd61a407a5cf842b4103d6")?;
+    let provider = offline_provider(contract_address, tx_hash, &creation_code, &deployed_code)?;
+    let app = common::spawn_app_with_provider(provider).await;
+    let body = json!({
+        "repoUrl": repo.path().to_str().unwrap(),
+        "repoCommit": repo_commit,
+        "contractAddress": contract_address,
+        "buildConfig": { "framework": "foundry", "buildHint": "default" },
+        "creationTxHashes": { "goerli": tx_hash },
+    });
+    let response = reqwest::Client::new()
+        .post(&format!("{}/verify/stream", app.address))
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .send()
+        .await?;
+    assert_eq!(200, response.status().as_u16());
+
+    // Each milestone is sent in order, followed by the result.
+    let events = response.text().await?;
+    let positions: Vec<usize> = ["cloning", "cloned", "building", "built", "comparing", "matched"]
+        .iter()
+        .map(|phase| events.find(&format!("\"phase\":\"{phase}\"")).expect(&events))
+        .collect();
+    assert!(positions.windows(2).all(|pair| pair[0] < pair[1]), "{events}");
+    assert!(events.contains("\"succeeded\":true"));
+    assert!(events.contains("event:result\n"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_async_reports_job_state() -> Result<(), Box<dyn std::error::Error>> {
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let body = json!({
+        "repoUrl": "https://github.com/ScopeLift/cove-test-repo",
+        "repoCommit": "b268862cf1ccf495d6dc20a86c41940dfb386d9b",
+        "contractAddress": "0x8d56e3e001132d84488DbacDbB01AfB8C3171242",
+        "buildConfig": { "framework": "truffle", "buildHint": "default" },
+    });
+    let response = client
+        .post(&format!("{}/verify?async=true", app.address))
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .send()
+        .await?;
+
+    // The job is accepted immediately, and its state is polled until it finishes.
+    assert_eq!(202, response.status().as_u16());
+    let accepted: AcceptedVerification = response.json().await?;
+    let status_url = format!("{}/verify/status/{}", app.address, accepted.job_id);
+    let mut job: JobState = client.get(&status_url).send().await?.json().await?;
+    for _ in 0..50 {
+        if matches!(job.status, JobStatus::Succeeded | JobStatus::Failed) {
+            break
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        job = client.get(&status_url).send().await?.json().await?;
+    }
+    assert_eq!(job.status, JobStatus::Failed);
+    assert!(job.result.is_none());
+    assert!(job.error.unwrap().contains("Supported frameworks: foundry"));
+
+    // Unknown jobs are not found.
+    let unknown_url = format!("{}/verify/status/{}", app.address, uuid::Uuid::new_v4());
+    assert_eq!(404, client.get(&unknown_url).send().await?.status().as_u16());
+    Ok(())
+}
+
 #[tokio::test]
 #[ignore = "This fails because leading bytecode differs in two places. This did not used to happen, TBD what broke here. It's worth noting that Seaport actually uses Hardhat for the production build, which may be related (it used to be the same bytecode aside from the metadata hash, though)"]
 async fn verify_seaport() -> Result<(), Box<dyn std::error::Error>> {
@@ -478,3 +1527,55 @@ async fn run_integration_test(
     assert_eq!(repo_commit, verification_result.repo_commit);
     Ok(())
 }
+
+#[tokio::test]
+async fn verify_multiple_chains_offline() -> Result<(), Box<dyn std::error::Error>> {
+    let (repo, repo_commit, creation_code, deployed_code) = offline_counter_project()?;
+
+    // The same contract deployed at the same address by a different transaction on each chain.
+    let contract_address = Address::from_str("0x8d56e3e001132d84488DbacDbB01AfB8C3171242")?;
+    let tx_hashes = HashMap::from([
+        (Chain::Goerli, TxHash::repeat_byte(0x01)),
+        (Chain::Sepolia, TxHash::repeat_byte(0x02)),
+    ]);
+    let providers = tx_hashes
+        .iter()
+        .map(|(chain, tx_hash)| {
+            let client = with_offline_deployment(
+                InMemoryClient::new(),
+                contract_address,
+                *tx_hash,
+                &creation_code,
+                &deployed_code,
+            )?;
+            Ok((*chain, Arc::new(Provider::new(RpcClient::InMemory(client)))))
+        })
+        .collect::<Result<HashMap<_, _>, Box<dyn std::error::Error>>>()?;
+    let app = common::spawn_app_with_provider(MultiChainProvider::from_providers(providers)).await;
+
+    let body = json!({
+        "repoUrl": repo.path().to_str().unwrap(),
+        "repoCommit": repo_commit,
+        "contractAddress": contract_address,
+        "buildConfig": { "framework": "foundry", "buildHint": "default" },
+        "creationTxHashes": tx_hashes,
+    });
+    let response = reqwest::Client::new()
+        .post(&format!("{}/verify", app.address))
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .send()
+        .await?;
+    assert_eq!(200, response.status().as_u16());
+
+    // Each chain's creation data is returned, not just one chain's.
+    let verification_result: SuccessfulVerification = from_str(&response.text().await?)?;
+    assert_eq!(verification_result.chains_matched, 2);
+    for (chain, tx_hash) in &tx_hashes {
+        let deployment = &verification_result.deployments[chain];
+        assert_eq!(deployment.creation_tx_hash, Some(*tx_hash));
+        assert_eq!(deployment.creation_code.as_ref(), Some(&creation_code));
+        assert_eq!(deployment.runtime_code, deployed_code);
+    }
+    Ok(())
+}
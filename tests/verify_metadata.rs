@@ -0,0 +1,46 @@
+use serde_json::json;
+mod common;
+
+#[tokio::test]
+async fn verify_metadata_requires_source_content() {
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+
+    // Metadata that references its sources by URL instead of including their content.
+    let body = json!({
+        "contractAddress": "0x8d56e3e001132d84488DbacDbB01AfB8C3171242",
+        "metadata": {
+            "compiler": { "version": "0.8.19+commit.7dd6d404" },
+            "language": "Solidity",
+            "output": { "abi": [], "devdoc": {}, "userdoc": {} },
+            "settings": {
+                "compilationTarget": { "src/Counter.sol": "Counter" },
+                "evmVersion": "paris",
+                "libraries": {},
+                "metadata": { "bytecodeHash": "ipfs" },
+                "optimizer": { "enabled": true, "runs": 200 },
+                "remappings": []
+            },
+            "sources": {
+                "src/Counter.sol": {
+                    "keccak256": "0x0000000000000000000000000000000000000000000000000000000000000001",
+                    "urls": ["dweb:/ipfs/QmVerifyMetadata"]
+                }
+            },
+            "version": 1
+        }
+    });
+
+    // Send the request.
+    let response = client
+        .post(&format!("{}/verify/metadata", app.address))
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert on the response.
+    assert_eq!(400, response.status().as_u16());
+    let message = response.text().await.expect("Failed to read response.");
+    assert!(message.contains("src/Counter.sol"), "{message}");
+}
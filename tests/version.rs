@@ -0,0 +1,20 @@
+use cove::routes::VersionInfo;
+mod common;
+
+#[tokio::test]
+async fn version_returns_server_version() {
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+
+    // Send the request.
+    let response = client
+        .get(&format!("{}/version", app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert on the response.
+    assert_eq!(200, response.status().as_u16());
+    let version_info: VersionInfo = response.json().await.expect("Failed to parse response.");
+    assert_eq!(version_info.version, env!("CARGO_PKG_VERSION"));
+}